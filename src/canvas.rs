@@ -1,12 +1,17 @@
-use crate::color::{Color, FillRule, FillStyle};
+use crate::color::{Antialiasing, Color, FillMode, FillStyle, RgbSpace};
+use crate::dither::{quantize, Dithering, TRUNCATION_FACTOR};
 use crate::geometry::{Path, Point};
-use crate::renderer::{blend_func, fill_path, render_path, BlendFunc, RenderState, NUM_CHANNELS};
+use crate::math::map_viewbox;
+use crate::recording::{BlendMode, Recording};
+use crate::renderer::{
+    blend_func, fill_path, fill_path_aliased, fill_path_subpixel, render_device_path, render_path,
+    BlendFunc, RenderState, NUM_CHANNELS, SUBPIXELS,
+};
 use std::vec::Vec;
 
 #[derive(Debug, Clone, Copy)]
 pub struct AccumulationCell {
     pub area: f32,
-    pub id: i32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -17,6 +22,72 @@ pub struct ViewBox {
     pub height: f64,
 }
 
+///
+/// How curves contribute to the accumulation buffer.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CurveRendering {
+    ///
+    /// Curves are flattened into line segments, `CanvasDescription::tolerance` controls how
+    /// close the segments follow the curve.
+    ///
+    #[default]
+    Flattened,
+    ///
+    /// Quadratic curves are split at pixel boundaries and every piece contributes its exact area
+    /// to the cell it covers, so curves stay smooth at any zoom level. Cubic curves are first
+    /// approximated with quadratic curves in device space. `CanvasDescription::tolerance` is not
+    /// used in this mode.
+    ///
+    Analytic,
+}
+
+///
+/// The values blending functions and antialiasing operate on.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compositing {
+    ///
+    /// Colors are blended as they are encoded in the working color space, for the default sRGB
+    /// space that's the values they're given with.
+    ///
+    #[default]
+    Encoded,
+    ///
+    /// Colors are converted to linear light when they are drawn and `Canvas::buffer` holds linear
+    /// values that `Canvas::to_u8` encodes back. Antialiased edges and blended colors keep their
+    /// perceived brightness. Alpha is never encoded.
+    ///
+    LinearLight,
+}
+
+impl Compositing {
+    ///
+    /// Converts an sRGB color to the values stored in a canvas working in `space`.
+    ///
+    pub fn decode(&self, space: RgbSpace, color: &Color) -> Color {
+        match self {
+            Compositing::Encoded => space.from_srgb(color),
+            Compositing::LinearLight => space.linear().from_srgb(color),
+        }
+    }
+
+    ///
+    /// Converts a color stored in a canvas working in `space` to the channels of `space`.
+    ///
+    pub fn encode(&self, space: RgbSpace, color: &Color) -> Color {
+        match self {
+            Compositing::Encoded => *color,
+            Compositing::LinearLight => Color {
+                r: space.from_linear(color.r),
+                g: space.from_linear(color.g),
+                b: space.from_linear(color.b),
+                a: color.a,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CanvasDescription {
     pub width: usize,
@@ -24,6 +95,16 @@ pub struct CanvasDescription {
     pub viewbox: ViewBox,
     pub tolerance: f64,
     pub background_color: Color,
+    pub curve_rendering: CurveRendering,
+    pub compositing: Compositing,
+    ///
+    /// The color space of the pixels, colors are converted to it when they're drawn.
+    ///
+    pub color_space: RgbSpace,
+    ///
+    /// How `Canvas::to_u8` quantizes the pixels.
+    ///
+    pub dithering: Dithering,
 }
 
 impl Default for CanvasDescription {
@@ -39,6 +120,10 @@ impl Default for CanvasDescription {
             },
             tolerance: 1.5,
             background_color: Color::default(),
+            curve_rendering: CurveRendering::default(),
+            compositing: Compositing::default(),
+            color_space: RgbSpace::default(),
+            dithering: Dithering::default(),
         }
     }
 }
@@ -46,65 +131,201 @@ impl Default for CanvasDescription {
 pub struct Canvas {
     pub buffer: Vec<f64>,
     pub accumulation_buffer: Vec<AccumulationCell>,
+    ///
+    /// Accumulation buffer with `SUBPIXELS` cells per pixel, only allocated once a shape is drawn
+    /// with `Antialiasing::Subpixel`.
+    ///
+    pub subpixel_accumulation_buffer: Vec<AccumulationCell>,
     pub desc: CanvasDescription,
+    ///
+    /// The blending function shapes are drawn with. Recordings don't see functions assigned here
+    /// directly, set it with `Canvas::set_blend_mode` or `Canvas::set_blending_function`.
+    ///
     pub blend: BlendFunc,
+    pub(crate) blend_mode: Option<BlendMode>,
+    ///
+    /// The shapes drawn since `Canvas::start_recording`, `None` when not recording.
+    ///
+    pub recording: Option<Recording>,
 }
 
 impl Canvas {
     pub fn new(desc: CanvasDescription) -> Canvas {
         let image_size = desc.width * desc.height * NUM_CHANNELS;
         let mut buffer = vec![0.0_f64; image_size];
+        let background_color = desc
+            .compositing
+            .decode(desc.color_space, &desc.background_color);
 
         buffer
             .as_mut_slice()
             .chunks_mut(NUM_CHANNELS)
             .for_each(|chunk| {
-                chunk[0] = desc.background_color.r;
-                chunk[1] = desc.background_color.g;
-                chunk[2] = desc.background_color.b;
-                chunk[3] = desc.background_color.a;
+                chunk[0] = background_color.r;
+                chunk[1] = background_color.g;
+                chunk[2] = background_color.b;
+                chunk[3] = background_color.a;
             });
 
         Canvas {
             buffer,
-            accumulation_buffer: vec![
-                AccumulationCell { area: 0.0, id: 0 };
-                desc.width * desc.height
-            ],
+            accumulation_buffer: vec![AccumulationCell { area: 0.0 }; desc.width * desc.height],
+            subpixel_accumulation_buffer: vec![],
             desc,
             blend: blend_func::source_over,
+            blend_mode: Some(BlendMode::SourceOver),
+            recording: None,
         }
     }
 
+    ///
+    /// Blends with one of the functions of `blend_func`, which recorded documents can express.
+    ///
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend = mode.func();
+        self.blend_mode = Some(mode);
+    }
+
+    ///
+    /// Blends with any function. Recordings can't express it, even when it's one of the
+    /// functions of `blend_func`, and export the shapes drawn with it like source over: use
+    /// `Canvas::set_blend_mode` for those.
+    ///
     pub fn set_blending_function(&mut self, f: BlendFunc) {
         self.blend = f;
+        self.blend_mode = None;
+    }
+
+    ///
+    /// The mode set with `Canvas::set_blend_mode`, `None` after `Canvas::set_blending_function`.
+    ///
+    pub fn blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+
+    ///
+    /// Starts keeping the shapes drawn with `Canvas::draw_shape`, dropping the ones recorded so
+    /// far.
+    ///
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new(self.desc));
+    }
+
+    ///
+    /// Stops recording and returns the shapes drawn since `Canvas::start_recording`.
+    ///
+    pub fn take_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
     }
 
     pub fn to_u8(&self) -> Vec<u8> {
-        return self
-            .buffer
-            .iter()
-            .map(|value| {
-                // https://stackoverflow.com/a/56842762/8622014
-                const FACTOR: f64 = (u8::MAX as f64) - f64::EPSILON * 128_f64;
-
-                (*value * FACTOR) as u8
-            })
-            .collect::<Vec<u8>>();
+        self.to_u8_dithered(self.desc.dithering)
+    }
+
+    ///
+    /// Same as `to_u8` with `dithering` instead of the one of the description.
+    ///
+    pub(crate) fn to_u8_dithered(&self, dithering: Dithering) -> Vec<u8> {
+        if dithering != Dithering::None {
+            let encoded = self
+                .buffer
+                .chunks(NUM_CHANNELS)
+                .flat_map(|pixel| {
+                    let color = Color {
+                        r: pixel[0],
+                        g: pixel[1],
+                        b: pixel[2],
+                        a: pixel[3],
+                    };
+                    let encoded = self.desc.compositing.encode(self.desc.color_space, &color);
+                    [encoded.r, encoded.g, encoded.b, encoded.a]
+                })
+                .collect::<Vec<f64>>();
+
+            return quantize(&encoded, self.desc.width, dithering);
+        }
+
+        let to_u8 = |value: &f64| (*value * TRUNCATION_FACTOR) as u8;
+
+        match self.desc.compositing {
+            Compositing::Encoded => self.buffer.iter().map(to_u8).collect::<Vec<u8>>(),
+            Compositing::LinearLight => {
+                // Encoding every channel is slow, instead `thresholds[i]` is the smallest linear
+                // value that gets encoded to `i + 1` which gives the exact result with a binary
+                // search.
+                let thresholds = (1..=u8::MAX)
+                    .map(|i| {
+                        self.desc
+                            .color_space
+                            .to_linear(i as f64 / TRUNCATION_FACTOR)
+                    })
+                    .collect::<Vec<f64>>();
+
+                self.buffer
+                    .chunks(NUM_CHANNELS)
+                    .flat_map(|pixel| {
+                        let encode = |value: f64| thresholds.partition_point(|t| *t <= value) as u8;
+                        [
+                            encode(pixel[0]),
+                            encode(pixel[1]),
+                            encode(pixel[2]),
+                            to_u8(&pixel[3]),
+                        ]
+                    })
+                    .collect::<Vec<u8>>()
+            }
+        }
     }
 
     pub fn draw_shape(
         &mut self,
         path: Path,
         fill_style: FillStyle,
-        fill_rule: FillRule,
+        fill_mode: impl Into<FillMode>,
         transform: impl Fn(&Point) -> Point,
     ) {
-        let mut state = RenderState {
-            canvas: self,
-            id: 0,
-        };
-        let bounds = render_path(&mut state, path, transform);
-        fill_path(&mut state, fill_style, fill_rule, &bounds)
+        let fill_mode = fill_mode.into();
+
+        if let Some(recording) = &mut self.recording {
+            recording.record(path, fill_style, fill_mode, self.blend_mode, &transform);
+        }
+
+        match fill_mode.antialiasing {
+            Antialiasing::Grayscale => {
+                let mut state = RenderState {
+                    desc: &self.desc,
+                    accumulation_buffer: &mut self.accumulation_buffer,
+                };
+                let bounds = render_path(&mut state, path, transform);
+                fill_path(self, fill_style, fill_mode.fill_rule, &bounds)
+            }
+            Antialiasing::Subpixel(order) => {
+                let desc = self.desc;
+                let subpixel_desc = CanvasDescription {
+                    width: desc.width * SUBPIXELS,
+                    ..desc
+                };
+                self.subpixel_accumulation_buffer.resize(
+                    subpixel_desc.width * desc.height,
+                    AccumulationCell { area: 0.0 },
+                );
+
+                let mut state = RenderState {
+                    desc: &subpixel_desc,
+                    accumulation_buffer: &mut self.subpixel_accumulation_buffer,
+                };
+                let bounds = render_device_path(&mut state, path, |p| {
+                    let p = map_viewbox(&desc, &transform(p));
+                    Point {
+                        x: p.x * SUBPIXELS as f64,
+                        y: p.y,
+                    }
+                });
+                fill_path_subpixel(self, fill_style, fill_mode.fill_rule, order, &bounds)
+            }
+            Antialiasing::None => {
+                fill_path_aliased(self, path, fill_style, fill_mode.fill_rule, transform)
+            }
+        }
     }
 }
@@ -1,7 +1,18 @@
 use crate::geometry::Point;
 use crate::math::Angle;
 
-#[derive(Debug, Clone, Copy)]
+mod css;
+mod icc;
+mod rgb_space;
+mod space;
+
+pub use css::ParseColorError;
+pub use rgb_space::RgbSpace;
+pub use space::{
+    linear_to_srgb, srgb_to_linear, ColorSpace, Hsl, Hsv, Lab, Lch, LinearRgb, Oklab, Oklch, Xyz,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f64,
     pub g: f64,
@@ -160,14 +171,58 @@ pub enum FillStyle<'a> {
     },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum FillRule {
     EvenOdd,
+    #[default]
     NonZero,
 }
 
-impl Default for FillRule {
-    fn default() -> FillRule {
-        FillRule::NonZero
+///
+/// Order of the subpixels of a display, from left to right.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubpixelOrder {
+    Rgb,
+    Bgr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Antialiasing {
+    ///
+    /// The coverage of a pixel is the area of the shape inside it.
+    ///
+    #[default]
+    Grayscale,
+    ///
+    /// The shape is rasterized at 3 times the horizontal resolution and every color channel is
+    /// blended with the coverage of its subpixel, filtered like FreeType's default LCD filter to
+    /// limit color fringes. Only meant for opaque canvases shown on a display with that layout.
+    ///
+    Subpixel(SubpixelOrder),
+    ///
+    /// Pixels are either covered or not, depending on whether their center is inside the shape.
+    /// A center exactly on an edge belongs to the shape on its right, or below it for horizontal
+    /// edges (the top-left rule), so shapes sharing an edge neither overlap nor leave gaps.
+    /// Curves are always flattened.
+    ///
+    None,
+}
+
+///
+/// How `Canvas::draw_shape` fills a shape, a `FillRule` alone uses the default antialiasing.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FillMode {
+    pub fill_rule: FillRule,
+    pub antialiasing: Antialiasing,
+}
+
+impl From<FillRule> for FillMode {
+    fn from(fill_rule: FillRule) -> Self {
+        FillMode {
+            fill_rule,
+            ..Default::default()
+        }
     }
 }
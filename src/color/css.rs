@@ -0,0 +1,563 @@
+//!
+//! Parsing colors written in CSS syntax and formatting them back.
+//!
+use crate::color::{
+    clamp,
+    space::{Hsl, Lab, Lch, LinearRgb, Oklab, Oklch},
+    Color,
+};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    Empty,
+    InvalidHex(String),
+    UnknownName(String),
+    UnknownFunction(String),
+    UnknownColorSpace(String),
+    MissingParenthesis,
+    InvalidArgumentCount { function: String, found: usize },
+    InvalidComponent(String),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::Empty => write!(f, "empty color"),
+            ParseColorError::InvalidHex(hex) => write!(f, "invalid hex color `{}`", hex),
+            ParseColorError::UnknownName(name) => write!(f, "unknown color name `{}`", name),
+            ParseColorError::UnknownFunction(name) => {
+                write!(f, "unknown color function `{}()`", name)
+            }
+            ParseColorError::UnknownColorSpace(name) => {
+                write!(f, "unknown color space `{}`", name)
+            }
+            ParseColorError::MissingParenthesis => write!(f, "missing closing parenthesis"),
+            ParseColorError::InvalidArgumentCount { function, found } => {
+                write!(f, "`{}()` can't take {} arguments", function, found)
+            }
+            ParseColorError::InvalidComponent(component) => {
+                write!(f, "invalid color component `{}`", component)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Component {
+    Number(f64),
+    Percentage(f64),
+    Degrees(f64),
+    None,
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn parse_component(text: &str) -> Result<Component, ParseColorError> {
+    const ANGLE_UNITS: [(&str, f64); 4] = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / std::f64::consts::PI),
+        ("turn", 360.0),
+    ];
+    let invalid = || ParseColorError::InvalidComponent(text.to_string());
+
+    if text == "none" {
+        return Ok(Component::None);
+    }
+
+    if let Some(percentage) = text.strip_suffix('%') {
+        return parse_number(percentage)
+            .map(Component::Percentage)
+            .ok_or_else(invalid);
+    }
+
+    for (unit, degrees) in ANGLE_UNITS {
+        if let Some(angle) = text.strip_suffix(unit) {
+            return parse_number(angle)
+                .map(|angle| Component::Degrees(angle * degrees))
+                .ok_or_else(invalid);
+        }
+    }
+
+    parse_number(text)
+        .map(Component::Number)
+        .ok_or_else(invalid)
+}
+
+///
+/// Splits the arguments of a color function into its components and the optional alpha.
+/// Both the legacy comma separated syntax and the space separated one are accepted.
+///
+fn parse_arguments(
+    function: &str,
+    arguments: &str,
+) -> Result<([Component; 3], Option<Component>), ParseColorError> {
+    let (components, alpha): (Vec<&str>, Option<&str>) = if arguments.contains(',') {
+        let mut components = arguments.split(',').map(str::trim).collect::<Vec<_>>();
+        let alpha = if components.len() == 4 {
+            components.pop()
+        } else {
+            None
+        };
+
+        (components, alpha)
+    } else {
+        let mut parts = arguments.splitn(2, '/');
+        let components = parts.next().unwrap_or("").split_whitespace().collect();
+        let alpha = parts.next().map(str::trim);
+
+        (components, alpha)
+    };
+
+    if components.len() != 3 || components.iter().any(|c| c.is_empty()) {
+        return Err(ParseColorError::InvalidArgumentCount {
+            function: function.to_string(),
+            found: components.len() + alpha.is_some() as usize,
+        });
+    }
+
+    let alpha = match alpha {
+        Some(alpha) if alpha.is_empty() || alpha.contains(char::is_whitespace) => {
+            return Err(ParseColorError::InvalidComponent(alpha.to_string()))
+        }
+        Some(alpha) => Some(parse_component(alpha)?),
+        None => None,
+    };
+
+    Ok((
+        [
+            parse_component(components[0])?,
+            parse_component(components[1])?,
+            parse_component(components[2])?,
+        ],
+        alpha,
+    ))
+}
+
+///
+/// Returns the value of a component, `percentage_scale` is the value of `100%`.
+///
+fn number(component: Component, percentage_scale: f64) -> Result<f64, ParseColorError> {
+    match component {
+        Component::Number(n) => Ok(n),
+        Component::Percentage(p) => Ok(p / 100.0 * percentage_scale),
+        Component::None => Ok(0.0),
+        Component::Degrees(d) => Err(ParseColorError::InvalidComponent(format!("{}deg", d))),
+    }
+}
+
+fn hue(component: Component) -> Result<f64, ParseColorError> {
+    match component {
+        Component::Number(degrees) | Component::Degrees(degrees) => Ok(degrees.rem_euclid(360.0)),
+        Component::None => Ok(0.0),
+        Component::Percentage(p) => Err(ParseColorError::InvalidComponent(format!("{}%", p))),
+    }
+}
+
+fn alpha(component: Option<Component>) -> Result<f64, ParseColorError> {
+    match component {
+        Some(component) => Ok(clamp(number(component, 1.0)?, 0.0, 1.0)),
+        None => Ok(1.0),
+    }
+}
+
+fn hwb_to_rgb(hue: f64, whiteness: f64, blackness: f64) -> Color {
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return Color {
+            r: gray,
+            g: gray,
+            b: gray,
+            a: 1.0,
+        };
+    }
+
+    let Color { r, g, b, .. } = Color::from(Hsl {
+        h: hue,
+        s: 1.0,
+        l: 0.5,
+        alpha: 1.0,
+    });
+    let [r, g, b] = [r, g, b].map(|c| c * (1.0 - whiteness - blackness) + whiteness);
+
+    Color { r, g, b, a: 1.0 }
+}
+
+fn parse_hex(hex: &str) -> Result<Color, ParseColorError> {
+    let invalid = || ParseColorError::InvalidHex(format!("#{}", hex));
+
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).unwrap() as f64;
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f64;
+    let channels = match hex.len() {
+        3 | 4 => (0..hex.len()).map(|i| digit(i) * 17.0).collect::<Vec<_>>(),
+        6 | 8 => (0..hex.len()).step_by(2).map(byte).collect::<Vec<_>>(),
+        _ => return Err(invalid()),
+    };
+
+    Ok(Color {
+        r: channels[0] / 255.0,
+        g: channels[1] / 255.0,
+        b: channels[2] / 255.0,
+        a: channels.get(3).map_or(1.0, |a| a / 255.0),
+    })
+}
+
+fn parse_color_function(arguments: &str) -> Result<Color, ParseColorError> {
+    let arguments = arguments.trim_start();
+    let space_end = arguments
+        .find(char::is_whitespace)
+        .unwrap_or(arguments.len());
+    let (space, components) = arguments.split_at(space_end);
+    let ([r, g, b], a) = parse_arguments("color", components)?;
+    let rgb = [number(r, 1.0)?, number(g, 1.0)?, number(b, 1.0)?];
+    let a = alpha(a)?;
+
+    match space {
+        // Like CSS, channels outside of [0, 1] are kept as colors outside of the sRGB gamut.
+        "srgb" => Ok(Color {
+            r: rgb[0],
+            g: rgb[1],
+            b: rgb[2],
+            a,
+        }),
+        "srgb-linear" => Ok(Color::from(LinearRgb {
+            r: rgb[0],
+            g: rgb[1],
+            b: rgb[2],
+            alpha: a,
+        })
+        .gamut_map()),
+        _ => Err(ParseColorError::UnknownColorSpace(space.to_string())),
+    }
+}
+
+fn parse_function(function: &str, arguments: &str) -> Result<Color, ParseColorError> {
+    const FUNCTIONS: [&str; 10] = [
+        "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch", "color",
+    ];
+
+    if !FUNCTIONS.contains(&function) {
+        return Err(ParseColorError::UnknownFunction(function.to_string()));
+    }
+
+    if function == "color" {
+        return parse_color_function(arguments);
+    }
+
+    let ([c0, c1, c2], a) = parse_arguments(function, arguments)?;
+    let a = alpha(a)?;
+
+    let color = match function {
+        "rgb" | "rgba" => {
+            // Out of range channels are clamped, not gamut mapped.
+            return Ok(Color {
+                r: number(c0, 255.0)? / 255.0,
+                g: number(c1, 255.0)? / 255.0,
+                b: number(c2, 255.0)? / 255.0,
+                a,
+            }
+            .clamp());
+        }
+        "hsl" | "hsla" => Color::from(Hsl {
+            h: hue(c0)?,
+            s: clamp(number(c1, 100.0)? / 100.0, 0.0, 1.0),
+            l: clamp(number(c2, 100.0)? / 100.0, 0.0, 1.0),
+            alpha: a,
+        }),
+        "hwb" => Color {
+            a,
+            ..hwb_to_rgb(
+                hue(c0)?,
+                clamp(number(c1, 100.0)? / 100.0, 0.0, 1.0),
+                clamp(number(c2, 100.0)? / 100.0, 0.0, 1.0),
+            )
+        },
+        "lab" => Color::from(Lab {
+            l: number(c0, 100.0)?,
+            a: number(c1, 125.0)?,
+            b: number(c2, 125.0)?,
+            alpha: a,
+        }),
+        "lch" => Color::from(Lch {
+            l: number(c0, 100.0)?,
+            c: number(c1, 150.0)?.max(0.0),
+            h: hue(c2)?,
+            alpha: a,
+        }),
+        "oklab" => Color::from(Oklab {
+            l: number(c0, 1.0)?,
+            a: number(c1, 0.4)?,
+            b: number(c2, 0.4)?,
+            alpha: a,
+        }),
+        "oklch" => Color::from(Oklch {
+            l: number(c0, 1.0)?,
+            c: number(c1, 0.4)?.max(0.0),
+            h: hue(c2)?,
+            alpha: a,
+        }),
+        _ => return Err(ParseColorError::UnknownFunction(function.to_string())),
+    };
+
+    Ok(color.gamut_map())
+}
+
+///
+/// The named colors from CSS Color Module Level 4, sorted by name.
+///
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+fn parse_name(name: &str) -> Result<Color, ParseColorError> {
+    if name == "transparent" {
+        return Ok(Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.0,
+        });
+    }
+
+    let index = NAMED_COLORS
+        .binary_search_by(|(n, _)| (*n).cmp(name))
+        .map_err(|_| ParseColorError::UnknownName(name.to_string()))?;
+    let rgb = NAMED_COLORS[index].1;
+
+    Ok(Color {
+        r: ((rgb >> 16) & 0xff) as f64 / 255.0,
+        g: ((rgb >> 8) & 0xff) as f64 / 255.0,
+        b: (rgb & 0xff) as f64 / 255.0,
+        a: 1.0,
+    })
+}
+
+impl Color {
+    ///
+    /// Parses hex colors (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`), named colors and the
+    /// `rgb()`, `rgba()`, `hsl()`, `hsla()`, `hwb()`, `lab()`, `lch()`, `oklab()`, `oklch()` and
+    /// `color()` functions, case insensitive.
+    ///
+    /// Colors outside of sRGB are brought into it with `Color::gamut_map`, except for
+    /// `color(srgb ...)` which keeps its channels so `Display` can write any color.
+    ///
+    pub fn parse(text: &str) -> Result<Color, ParseColorError> {
+        let text = text.trim().to_ascii_lowercase();
+
+        if text.is_empty() {
+            return Err(ParseColorError::Empty);
+        }
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+
+        match text.find('(') {
+            Some(open) => {
+                let arguments = text[open + 1..]
+                    .strip_suffix(')')
+                    .ok_or(ParseColorError::MissingParenthesis)?;
+
+                parse_function(text[..open].trim_end(), arguments)
+            }
+            None => parse_name(&text),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Color::parse(text)
+    }
+}
+
+///
+/// Colors with 8-bit channels are written as hex, anything else as `color(srgb ...)` which keeps
+/// every digit so `Color::parse` gives back the same color.
+///
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let channels = [self.r, self.g, self.b, self.a];
+        let is_8bit = channels
+            .iter()
+            .all(|c| (0.0..=1.0).contains(c) && (c * 255.0).round() / 255.0 == *c);
+
+        if is_8bit {
+            let [r, g, b, a] = channels.map(|c| (c * 255.0).round() as u8);
+            write!(f, "#{:02x}{:02x}{:02x}", r, g, b)?;
+
+            if a != 255 {
+                write!(f, "{:02x}", a)?;
+            }
+
+            return Ok(());
+        }
+
+        write!(f, "color(srgb {} {} {}", self.r, self.g, self.b)?;
+
+        if self.a != 1.0 {
+            write!(f, " / {}", self.a)?;
+        }
+
+        write!(f, ")")
+    }
+}
@@ -0,0 +1,151 @@
+//!
+//! Minimal ICC v4 display profiles describing an `RgbSpace`: the colorants, the transfer
+//! function as a parametric curve and the D65 to D50 adaptation.
+//! See https://www.color.org/specification/ICC.1-2022-05.pdf
+//!
+use super::rgb_space::{RgbSpace, REC2020_ALPHA, REC2020_BETA};
+use super::space::{multiply, D65_TO_D50};
+
+const HEADER_SIZE: usize = 128;
+
+// The PCS illuminant as written in every profile, which isn't exactly `D50_WHITE`.
+const PCS_ILLUMINANT: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+fn s15_fixed16(v: f64) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    xyz.iter().for_each(|v| tag.extend(s15_fixed16(*v)));
+    tag
+}
+
+fn text_tag(text: &str) -> Vec<u8> {
+    let utf16 = text
+        .encode_utf16()
+        .flat_map(|c| c.to_be_bytes())
+        .collect::<Vec<u8>>();
+    let mut tag = b"mluc\0\0\0\0".to_vec();
+
+    // One record for `en-US`, the text follows the 28 bytes of header and record.
+    tag.extend(1_u32.to_be_bytes());
+    tag.extend(12_u32.to_be_bytes());
+    tag.extend(b"enUS");
+    tag.extend((utf16.len() as u32).to_be_bytes());
+    tag.extend(28_u32.to_be_bytes());
+    tag.extend(utf16);
+    tag
+}
+
+fn curve_tag(space: RgbSpace) -> Vec<u8> {
+    // Parametric curves of type 3: Y = (aX + b)^g if X >= d, cX otherwise.
+    let (function, parameters) = match space {
+        RgbSpace::Srgb | RgbSpace::DisplayP3 => (
+            3_u16,
+            vec![2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.040_45],
+        ),
+        RgbSpace::Rec2020 => (
+            3,
+            vec![
+                1.0 / 0.45,
+                1.0 / REC2020_ALPHA,
+                (REC2020_ALPHA - 1.0) / REC2020_ALPHA,
+                1.0 / 4.5,
+                REC2020_BETA * 4.5,
+            ],
+        ),
+        _ => (0, vec![1.0]),
+    };
+
+    let mut tag = b"para\0\0\0\0".to_vec();
+    tag.extend(function.to_be_bytes());
+    tag.extend([0, 0]);
+    parameters.iter().for_each(|v| tag.extend(s15_fixed16(*v)));
+    tag
+}
+
+impl RgbSpace {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RgbSpace::Srgb => "sRGB",
+            RgbSpace::LinearSrgb => "Linear sRGB",
+            RgbSpace::DisplayP3 => "Display P3",
+            RgbSpace::LinearDisplayP3 => "Linear Display P3",
+            RgbSpace::Rec2020 => "Rec. ITU-R BT.2020",
+            RgbSpace::LinearRec2020 => "Linear Rec. ITU-R BT.2020",
+        }
+    }
+
+    ///
+    /// An ICC v4 display profile for this space, to embed in images.
+    ///
+    pub fn icc_profile(&self) -> Vec<u8> {
+        let to_xyz = self.linear_to_xyz();
+        let colorant = |i: usize| multiply(&D65_TO_D50, [to_xyz[0][i], to_xyz[1][i], to_xyz[2][i]]);
+        let mut adaptation = b"sf32\0\0\0\0".to_vec();
+        D65_TO_D50
+            .iter()
+            .flatten()
+            .for_each(|v| adaptation.extend(s15_fixed16(*v)));
+
+        // The three channels share the same curve.
+        let tags: [(&[&[u8; 4]], Vec<u8>); 8] = [
+            (&[b"desc"], text_tag(self.name())),
+            (&[b"cprt"], text_tag("No copyright, use freely")),
+            (&[b"wtpt"], xyz_tag(PCS_ILLUMINANT)),
+            (&[b"chad"], adaptation),
+            (&[b"rXYZ"], xyz_tag(colorant(0))),
+            (&[b"gXYZ"], xyz_tag(colorant(1))),
+            (&[b"bXYZ"], xyz_tag(colorant(2))),
+            (&[b"rTRC", b"gTRC", b"bTRC"], curve_tag(*self)),
+        ];
+
+        let num_entries = tags
+            .iter()
+            .map(|(signatures, _)| signatures.len())
+            .sum::<usize>();
+        let table_size = 4 + 12 * num_entries;
+        let mut table = (num_entries as u32).to_be_bytes().to_vec();
+        let mut data = Vec::<u8>::new();
+
+        for (signatures, tag) in &tags {
+            let offset = HEADER_SIZE + table_size + data.len();
+
+            for signature in signatures.iter() {
+                table.extend(*signature);
+                table.extend((offset as u32).to_be_bytes());
+                table.extend((tag.len() as u32).to_be_bytes());
+            }
+
+            data.extend(tag);
+            // Tags start on 4 byte boundaries.
+            data.resize(data.len().div_ceil(4) * 4, 0);
+        }
+
+        let size = HEADER_SIZE + table_size + data.len();
+        let mut profile = Vec::with_capacity(size);
+
+        profile.extend((size as u32).to_be_bytes());
+        profile.extend([0; 4]); // Preferred CMM
+        profile.extend([4, 0x40, 0, 0]); // Version 4.4
+        profile.extend(b"mntrRGB XYZ ");
+        // Creation date, fixed so the output is reproducible.
+        [2022_u16, 1, 1, 0, 0, 0]
+            .iter()
+            .for_each(|v| profile.extend(v.to_be_bytes()));
+        profile.extend(b"acsp");
+        profile.extend([0; 24]); // Platform, flags, manufacturer, model and attributes
+        profile.extend([0; 4]); // Perceptual rendering intent
+        PCS_ILLUMINANT
+            .iter()
+            .for_each(|v| profile.extend(s15_fixed16(*v)));
+        profile.extend([0; 4]); // Creator
+        profile.extend([0; 16]); // Profile ID, optional
+        profile.extend([0; 28]);
+        profile.extend(table);
+        profile.extend(data);
+
+        profile
+    }
+}
@@ -0,0 +1,175 @@
+//!
+//! RGB color spaces a canvas can work in. `Color` is always sRGB, with channels outside of
+//! [0, 1] for colors sRGB can't display, and is converted to the working space when it's drawn.
+//!
+use super::space::{
+    linear_to_srgb, multiply, srgb_to_linear, LINEAR_SRGB_TO_XYZ, XYZ_TO_LINEAR_SRGB,
+};
+use super::Color;
+
+///
+/// An RGB color space: primaries with a D65 white point and a transfer function. The linear
+/// variants store light intensities directly.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RgbSpace {
+    #[default]
+    Srgb,
+    LinearSrgb,
+    DisplayP3,
+    LinearDisplayP3,
+    Rec2020,
+    LinearRec2020,
+}
+
+#[rustfmt::skip]
+const LINEAR_DISPLAY_P3_TO_XYZ: [[f64; 3]; 3] = [
+    [608_311.0 / 1_250_200.0, 189_793.0 / 714_400.0, 198_249.0 / 1_000_160.0],
+    [35_783.0 / 156_275.0, 247_089.0 / 357_200.0, 198_249.0 / 2_500_400.0],
+    [0.0, 32_229.0 / 714_400.0, 5_220_557.0 / 5_000_800.0],
+];
+
+#[rustfmt::skip]
+const XYZ_TO_LINEAR_DISPLAY_P3: [[f64; 3]; 3] = [
+    [446_124.0 / 178_915.0, -333_277.0 / 357_830.0, -72_051.0 / 178_915.0],
+    [-14_852.0 / 17_905.0, 63_121.0 / 35_810.0, 423.0 / 17_905.0],
+    [11_844.0 / 330_415.0, -50_337.0 / 660_830.0, 316_169.0 / 330_415.0],
+];
+
+#[rustfmt::skip]
+const LINEAR_REC2020_TO_XYZ: [[f64; 3]; 3] = [
+    [63_426_534.0 / 99_577_255.0, 20_160_776.0 / 139_408_157.0, 47_086_771.0 / 278_816_314.0],
+    [26_158_966.0 / 99_577_255.0, 472_592_308.0 / 697_040_785.0, 8_267_143.0 / 139_408_157.0],
+    [0.0, 19_567_812.0 / 697_040_785.0, 295_819_943.0 / 278_816_314.0],
+];
+
+#[rustfmt::skip]
+const XYZ_TO_LINEAR_REC2020: [[f64; 3]; 3] = [
+    [30_757_411.0 / 17_917_100.0, -6_372_589.0 / 17_917_100.0, -4_539_589.0 / 17_917_100.0],
+    [-19_765_991.0 / 29_648_200.0, 47_925_759.0 / 29_648_200.0, 467_509.0 / 29_648_200.0],
+    [792_561.0 / 44_930_125.0, -1_921_689.0 / 44_930_125.0, 42_328_811.0 / 44_930_125.0],
+];
+
+// The constants of the ITU-R BT.2020 transfer function at full precision, as used by CSS.
+pub(super) const REC2020_ALPHA: f64 = 1.099_296_826_809_44;
+pub(super) const REC2020_BETA: f64 = 0.018_053_968_510_807;
+
+impl RgbSpace {
+    pub fn is_linear(&self) -> bool {
+        matches!(
+            self,
+            RgbSpace::LinearSrgb | RgbSpace::LinearDisplayP3 | RgbSpace::LinearRec2020
+        )
+    }
+
+    ///
+    /// The same primaries without the transfer function.
+    ///
+    pub fn linear(&self) -> RgbSpace {
+        match self {
+            RgbSpace::Srgb | RgbSpace::LinearSrgb => RgbSpace::LinearSrgb,
+            RgbSpace::DisplayP3 | RgbSpace::LinearDisplayP3 => RgbSpace::LinearDisplayP3,
+            RgbSpace::Rec2020 | RgbSpace::LinearRec2020 => RgbSpace::LinearRec2020,
+        }
+    }
+
+    ///
+    /// Decodes an encoded channel to light intensity. Negative values are mirrored.
+    ///
+    pub fn to_linear(&self, c: f64) -> f64 {
+        match self {
+            RgbSpace::Srgb | RgbSpace::DisplayP3 => srgb_to_linear(c),
+            RgbSpace::Rec2020 => {
+                if c.abs() < REC2020_BETA * 4.5 {
+                    c / 4.5
+                } else {
+                    c.signum() * ((c.abs() + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45)
+                }
+            }
+            _ => c,
+        }
+    }
+
+    ///
+    /// Encodes a light intensity, the inverse of `to_linear`.
+    ///
+    pub fn from_linear(&self, c: f64) -> f64 {
+        match self {
+            RgbSpace::Srgb | RgbSpace::DisplayP3 => linear_to_srgb(c),
+            RgbSpace::Rec2020 => {
+                if c.abs() < REC2020_BETA {
+                    4.5 * c
+                } else {
+                    c.signum() * (REC2020_ALPHA * c.abs().powf(0.45) - (REC2020_ALPHA - 1.0))
+                }
+            }
+            _ => c,
+        }
+    }
+
+    ///
+    /// The matrix converting linear channels to CIE XYZ (D65).
+    ///
+    pub fn linear_to_xyz(&self) -> [[f64; 3]; 3] {
+        match self.linear() {
+            RgbSpace::LinearDisplayP3 => LINEAR_DISPLAY_P3_TO_XYZ,
+            RgbSpace::LinearRec2020 => LINEAR_REC2020_TO_XYZ,
+            _ => LINEAR_SRGB_TO_XYZ,
+        }
+    }
+
+    fn xyz_to_linear(&self) -> [[f64; 3]; 3] {
+        match self.linear() {
+            RgbSpace::LinearDisplayP3 => XYZ_TO_LINEAR_DISPLAY_P3,
+            RgbSpace::LinearRec2020 => XYZ_TO_LINEAR_REC2020,
+            _ => XYZ_TO_LINEAR_SRGB,
+        }
+    }
+
+    ///
+    /// Converts an sRGB color to the channels of this space. Alpha is kept as is.
+    ///
+    pub fn from_srgb(&self, color: &Color) -> Color {
+        if *self == RgbSpace::Srgb {
+            return *color;
+        }
+
+        let linear = [color.r, color.g, color.b].map(srgb_to_linear);
+        let linear = match self.linear() {
+            RgbSpace::LinearSrgb => linear,
+            _ => multiply(&self.xyz_to_linear(), multiply(&LINEAR_SRGB_TO_XYZ, linear)),
+        };
+        let [r, g, b] = linear.map(|c| self.from_linear(c));
+
+        Color {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+
+    ///
+    /// Converts the channels of this space to an sRGB color, the inverse of `from_srgb`. Colors
+    /// outside of the sRGB gamut have channels outside of [0, 1].
+    ///
+    pub fn to_srgb(&self, color: &Color) -> Color {
+        if *self == RgbSpace::Srgb {
+            return *color;
+        }
+
+        let linear = [color.r, color.g, color.b].map(|c| self.to_linear(c));
+        let linear = match self.linear() {
+            RgbSpace::LinearSrgb => linear,
+            _ => multiply(&XYZ_TO_LINEAR_SRGB, multiply(&self.linear_to_xyz(), linear)),
+        };
+        let [r, g, b] = linear.map(linear_to_srgb);
+
+        Color {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+}
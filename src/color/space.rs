@@ -0,0 +1,788 @@
+//!
+//! Conversions between `Color` (sRGB) and other color spaces, and operations built on top of them.
+//!
+//! Lab and LCh use the D50 white point like CSS, XYZ uses D65 like sRGB. Hues are in degrees and
+//! are 0 for achromatic colors. Converting back to `Color` doesn't clamp, use `Color::in_gamut`
+//! and `Color::gamut_map` for colors that might not fit in sRGB.
+//!
+use crate::color::{clamp, Color};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+    pub alpha: f64,
+}
+
+///
+/// The spaces `Color::mix` can interpolate in.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    Srgb,
+    LinearSrgb,
+    Hsl,
+    Hsv,
+    Lab,
+    Lch,
+    #[default]
+    Oklab,
+    Oklch,
+    Xyz,
+}
+
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c.abs() <= 0.040_45 {
+        c / 12.92
+    } else {
+        c.signum() * ((c.abs() + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c.abs() <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        c.signum() * (1.055 * c.abs().powf(1.0 / 2.4) - 0.055)
+    }
+}
+
+pub(super) fn multiply(matrix: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+#[rustfmt::skip]
+pub(super) const LINEAR_SRGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.412_390_799_265_959_34, 0.357_584_339_383_878, 0.180_480_788_401_834_3],
+    [0.212_639_005_871_510_27, 0.715_168_678_767_756, 0.072_192_315_360_733_71],
+    [0.019_330_818_715_591_82, 0.119_194_779_794_625_98, 0.950_532_152_249_660_7],
+];
+
+#[rustfmt::skip]
+pub(super) const XYZ_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [3.240_969_941_904_522_6, -1.537_383_177_570_094, -0.498_610_760_293_003_4],
+    [-0.969_243_636_280_879_6, 1.875_967_501_507_720_2, 0.041_555_057_407_175_59],
+    [0.055_630_079_696_993_66, -0.203_976_958_888_976_52, 1.056_971_514_242_878_6],
+];
+
+// Bradford chromatic adaptation between the D65 and D50 white points.
+#[rustfmt::skip]
+pub(super) const D65_TO_D50: [[f64; 3]; 3] = [
+    [1.047_929_820_840_548_8, 0.022_946_793_341_019_088, -0.050_192_229_543_135_57],
+    [0.029_627_815_688_159_344, 0.990_434_484_573_249, -0.017_073_825_029_385_14],
+    [-0.009_243_058_152_591_178, 0.015_055_144_896_577_895, 0.751_874_289_958_000_8],
+];
+
+// The inverse of `D65_TO_D50`, so converting back and forth doesn't drift.
+#[rustfmt::skip]
+const D50_TO_D65: [[f64; 3]; 3] = [
+    [0.955_473_394_204_897_9, -0.023_098_374_726_038_654, 0.063_259_194_989_114_96],
+    [-0.028_369_712_866_394_44, 1.009_995_337_455_560_4, 0.021_041_475_607_354_32],
+    [0.012_314_034_948_960_157, -0.020_507_584_814_405_57, 1.330_365_912_644_437_4],
+];
+
+pub(super) const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+
+///
+/// Below these saturations and chromas the hue is meaningless and reported as 0. The OKLab
+/// matrices are only precise up to about 1e-8 so grays don't quite end up at 0.
+///
+const ACHROMATIC_SATURATION: f64 = 1e-9;
+const ACHROMATIC_LAB_CHROMA: f64 = 1e-4;
+const ACHROMATIC_OKLAB_CHROMA: f64 = 1e-6;
+
+fn to_polar(a: f64, b: f64, achromatic_threshold: f64) -> (f64, f64) {
+    let chroma = a.hypot(b);
+
+    if chroma < achromatic_threshold {
+        (chroma, 0.0)
+    } else {
+        (chroma, b.atan2(a).to_degrees().rem_euclid(360.0))
+    }
+}
+
+fn from_polar(chroma: f64, hue: f64) -> (f64, f64) {
+    let (sin, cos) = hue.to_radians().sin_cos();
+    (chroma * cos, chroma * sin)
+}
+
+impl From<Color> for LinearRgb {
+    fn from(color: Color) -> Self {
+        LinearRgb {
+            r: srgb_to_linear(color.r),
+            g: srgb_to_linear(color.g),
+            b: srgb_to_linear(color.b),
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<LinearRgb> for Color {
+    fn from(color: LinearRgb) -> Self {
+        Color {
+            r: linear_to_srgb(color.r),
+            g: linear_to_srgb(color.g),
+            b: linear_to_srgb(color.b),
+            a: color.alpha,
+        }
+    }
+}
+
+impl From<Color> for Xyz {
+    fn from(color: Color) -> Self {
+        let linear = LinearRgb::from(color);
+        let [x, y, z] = multiply(&LINEAR_SRGB_TO_XYZ, [linear.r, linear.g, linear.b]);
+
+        Xyz {
+            x,
+            y,
+            z,
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Xyz> for Color {
+    fn from(color: Xyz) -> Self {
+        let [r, g, b] = multiply(&XYZ_TO_LINEAR_SRGB, [color.x, color.y, color.z]);
+
+        Color::from(LinearRgb {
+            r,
+            g,
+            b,
+            alpha: color.alpha,
+        })
+    }
+}
+
+impl From<Color> for Hsl {
+    fn from(color: Color) -> Self {
+        let max = color.r.max(color.g).max(color.b);
+        let min = color.r.min(color.g).min(color.b);
+        let l = (max + min) / 2.0;
+        let delta = max - min;
+
+        if delta < ACHROMATIC_SATURATION {
+            return Hsl {
+                h: 0.0,
+                s: 0.0,
+                l,
+                alpha: color.a,
+            };
+        }
+
+        let s = if l <= 0.0 || l >= 1.0 {
+            0.0
+        } else {
+            (max - l) / f64::min(l, 1.0 - l)
+        };
+
+        Hsl {
+            h: hue_of(&color, max, delta),
+            s,
+            l,
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(color: Hsl) -> Self {
+        let a = color.s * f64::min(color.l, 1.0 - color.l);
+        let f = |n: f64| {
+            let k = (n + color.h.rem_euclid(360.0) / 30.0) % 12.0;
+            color.l - a * f64::min(k - 3.0, 9.0 - k).clamp(-1.0, 1.0)
+        };
+
+        Color {
+            r: f(0.0),
+            g: f(8.0),
+            b: f(4.0),
+            a: color.alpha,
+        }
+    }
+}
+
+///
+/// The hue shared by HSL and HSV, `delta` being the difference between the biggest and the
+/// smallest channel.
+///
+fn hue_of(color: &Color, max: f64, delta: f64) -> f64 {
+    let hue = if max == color.r {
+        (color.g - color.b) / delta
+    } else if max == color.g {
+        (color.b - color.r) / delta + 2.0
+    } else {
+        (color.r - color.g) / delta + 4.0
+    };
+
+    (hue * 60.0).rem_euclid(360.0)
+}
+
+impl From<Color> for Hsv {
+    fn from(color: Color) -> Self {
+        let max = color.r.max(color.g).max(color.b);
+        let min = color.r.min(color.g).min(color.b);
+        let delta = max - min;
+
+        if delta < ACHROMATIC_SATURATION {
+            return Hsv {
+                h: 0.0,
+                s: 0.0,
+                v: max,
+                alpha: color.a,
+            };
+        }
+
+        Hsv {
+            h: hue_of(&color, max, delta),
+            s: if max > 0.0 { delta / max } else { 0.0 },
+            v: max,
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(color: Hsv) -> Self {
+        let f = |n: f64| {
+            let k = (n + color.h.rem_euclid(360.0) / 60.0) % 6.0;
+            color.v - color.v * color.s * f64::min(k, 4.0 - k).clamp(0.0, 1.0)
+        };
+
+        Color {
+            r: f(5.0),
+            g: f(3.0),
+            b: f(1.0),
+            a: color.alpha,
+        }
+    }
+}
+
+impl From<Color> for Lab {
+    fn from(color: Color) -> Self {
+        let xyz = Xyz::from(color);
+        let d50 = multiply(&D65_TO_D50, [xyz.x, xyz.y, xyz.z]);
+        let [fx, fy, fz] = [0, 1, 2].map(|i| {
+            let t = d50[i] / D50_WHITE[i];
+
+            if t > LAB_EPSILON {
+                t.cbrt()
+            } else {
+                (LAB_KAPPA * t + 16.0) / 116.0
+            }
+        });
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Lab> for Color {
+    fn from(color: Lab) -> Self {
+        let fy = (color.l + 16.0) / 116.0;
+        let fx = color.a / 500.0 + fy;
+        let fz = fy - color.b / 200.0;
+        let inverse = |f: f64| {
+            if f.powi(3) > LAB_EPSILON {
+                f.powi(3)
+            } else {
+                (116.0 * f - 16.0) / LAB_KAPPA
+            }
+        };
+        let y = if color.l > LAB_KAPPA * LAB_EPSILON {
+            fy.powi(3)
+        } else {
+            color.l / LAB_KAPPA
+        };
+        let d50 = [
+            inverse(fx) * D50_WHITE[0],
+            y * D50_WHITE[1],
+            inverse(fz) * D50_WHITE[2],
+        ];
+        let [x, y, z] = multiply(&D50_TO_D65, d50);
+
+        Color::from(Xyz {
+            x,
+            y,
+            z,
+            alpha: color.alpha,
+        })
+    }
+}
+
+impl From<Lab> for Lch {
+    fn from(color: Lab) -> Self {
+        let (c, h) = to_polar(color.a, color.b, ACHROMATIC_LAB_CHROMA);
+
+        Lch {
+            l: color.l,
+            c,
+            h,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Lch> for Lab {
+    fn from(color: Lch) -> Self {
+        let (a, b) = from_polar(color.c, color.h);
+
+        Lab {
+            l: color.l,
+            a,
+            b,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Color> for Lch {
+    fn from(color: Color) -> Self {
+        Lch::from(Lab::from(color))
+    }
+}
+
+impl From<Lch> for Color {
+    fn from(color: Lch) -> Self {
+        Color::from(Lab::from(color))
+    }
+}
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        let linear = LinearRgb::from(color);
+        let (r, g, b) = (linear.r, linear.g, linear.b);
+        let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+        let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+        let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+
+        Oklab {
+            l: 0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+            a: 1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+            b: 0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+            alpha: color.a,
+        }
+    }
+}
+
+impl From<Oklab> for Color {
+    fn from(color: Oklab) -> Self {
+        let (l, a, b) = (color.l, color.a, color.b);
+        let l_ = (l + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+        let m_ = (l - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+        let s_ = (l - 0.089_484_177_5 * a - 1.291_485_548 * b).powi(3);
+
+        Color::from(LinearRgb {
+            r: 4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+            g: -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+            b: -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701 * s_,
+            alpha: color.alpha,
+        })
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(color: Oklab) -> Self {
+        let (c, h) = to_polar(color.a, color.b, ACHROMATIC_OKLAB_CHROMA);
+
+        Oklch {
+            l: color.l,
+            c,
+            h,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(color: Oklch) -> Self {
+        let (a, b) = from_polar(color.c, color.h);
+
+        Oklab {
+            l: color.l,
+            a,
+            b,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Color> for Oklch {
+    fn from(color: Color) -> Self {
+        Oklch::from(Oklab::from(color))
+    }
+}
+
+impl From<Oklch> for Color {
+    fn from(color: Oklch) -> Self {
+        Color::from(Oklab::from(color))
+    }
+}
+
+impl ColorSpace {
+    ///
+    /// Index of the hue among the components returned by `components`, if the space has one.
+    ///
+    fn hue_index(self) -> Option<usize> {
+        match self {
+            ColorSpace::Hsl | ColorSpace::Hsv => Some(0),
+            ColorSpace::Lch | ColorSpace::Oklch => Some(2),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Whether the hue in `components` is meaningless because the color is a gray.
+    ///
+    fn is_achromatic(self, components: &[f64; 3]) -> bool {
+        match self {
+            ColorSpace::Hsl | ColorSpace::Hsv => components[1] < ACHROMATIC_SATURATION,
+            ColorSpace::Lch => components[1] < ACHROMATIC_LAB_CHROMA,
+            ColorSpace::Oklch => components[1] < ACHROMATIC_OKLAB_CHROMA,
+            _ => false,
+        }
+    }
+
+    fn components(self, color: Color) -> [f64; 3] {
+        match self {
+            ColorSpace::Srgb => [color.r, color.g, color.b],
+            ColorSpace::LinearSrgb => {
+                let c = LinearRgb::from(color);
+                [c.r, c.g, c.b]
+            }
+            ColorSpace::Hsl => {
+                let c = Hsl::from(color);
+                [c.h, c.s, c.l]
+            }
+            ColorSpace::Hsv => {
+                let c = Hsv::from(color);
+                [c.h, c.s, c.v]
+            }
+            ColorSpace::Lab => {
+                let c = Lab::from(color);
+                [c.l, c.a, c.b]
+            }
+            ColorSpace::Lch => {
+                let c = Lch::from(color);
+                [c.l, c.c, c.h]
+            }
+            ColorSpace::Oklab => {
+                let c = Oklab::from(color);
+                [c.l, c.a, c.b]
+            }
+            ColorSpace::Oklch => {
+                let c = Oklch::from(color);
+                [c.l, c.c, c.h]
+            }
+            ColorSpace::Xyz => {
+                let c = Xyz::from(color);
+                [c.x, c.y, c.z]
+            }
+        }
+    }
+
+    fn color(self, [c0, c1, c2]: [f64; 3], alpha: f64) -> Color {
+        match self {
+            ColorSpace::Srgb => Color {
+                r: c0,
+                g: c1,
+                b: c2,
+                a: alpha,
+            },
+            ColorSpace::LinearSrgb => Color::from(LinearRgb {
+                r: c0,
+                g: c1,
+                b: c2,
+                alpha,
+            }),
+            ColorSpace::Hsl => Color::from(Hsl {
+                h: c0,
+                s: c1,
+                l: c2,
+                alpha,
+            }),
+            ColorSpace::Hsv => Color::from(Hsv {
+                h: c0,
+                s: c1,
+                v: c2,
+                alpha,
+            }),
+            ColorSpace::Lab => Color::from(Lab {
+                l: c0,
+                a: c1,
+                b: c2,
+                alpha,
+            }),
+            ColorSpace::Lch => Color::from(Lch {
+                l: c0,
+                c: c1,
+                h: c2,
+                alpha,
+            }),
+            ColorSpace::Oklab => Color::from(Oklab {
+                l: c0,
+                a: c1,
+                b: c2,
+                alpha,
+            }),
+            ColorSpace::Oklch => Color::from(Oklch {
+                l: c0,
+                c: c1,
+                h: c2,
+                alpha,
+            }),
+            ColorSpace::Xyz => Color::from(Xyz {
+                x: c0,
+                y: c1,
+                z: c2,
+                alpha,
+            }),
+        }
+    }
+}
+
+///
+/// Euclidean distance in OKLab, about 0.02 is the smallest difference that's noticeable.
+///
+fn delta_e_ok(lhs: &Oklab, rhs: &Oklab) -> f64 {
+    ((lhs.l - rhs.l).powi(2) + (lhs.a - rhs.a).powi(2) + (lhs.b - rhs.b).powi(2)).sqrt()
+}
+
+impl Color {
+    ///
+    /// Relative luminance as defined by WCAG 2.
+    ///
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = LinearRgb::from(self.clamp());
+        0.2126 * linear.r + 0.7152 * linear.g + 0.0722 * linear.b
+    }
+
+    ///
+    /// WCAG 2 contrast ratio between the two colors, from 1 to 21. Alpha is ignored.
+    ///
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (l0, l1) = (self.relative_luminance(), other.relative_luminance());
+        (l0.max(l1) + 0.05) / (l0.min(l1) + 0.05)
+    }
+
+    ///
+    /// Adds `amount` (from 0 to 1) to the HSL lightness.
+    ///
+    pub fn lighten(&self, amount: f64) -> Color {
+        let hsl = Hsl::from(*self);
+        Color::from(Hsl {
+            l: clamp(hsl.l + amount, 0.0, 1.0),
+            ..hsl
+        })
+    }
+
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    ///
+    /// Adds `amount` (from 0 to 1) to the HSL saturation.
+    ///
+    pub fn saturate(&self, amount: f64) -> Color {
+        let hsl = Hsl::from(*self);
+        Color::from(Hsl {
+            s: clamp(hsl.s + amount, 0.0, 1.0),
+            ..hsl
+        })
+    }
+
+    pub fn desaturate(&self, amount: f64) -> Color {
+        self.saturate(-amount)
+    }
+
+    ///
+    /// Interpolates from `self` (`t = 0`) to `other` (`t = 1`) in `space`, the same way CSS
+    /// `color-mix()` does: components are premultiplied by alpha and hues take the shorter arc.
+    ///
+    pub fn mix(&self, other: &Color, t: f64, space: ColorSpace) -> Color {
+        let mut from = space.components(*self);
+        let mut to = space.components(*other);
+        let alpha = self.a + (other.a - self.a) * t;
+        let hue = space.hue_index();
+
+        if let Some(hue) = hue {
+            // The hue of a gray is meaningless, take the other one so the mix doesn't drift.
+            if space.is_achromatic(&from) {
+                from[hue] = to[hue];
+            } else if space.is_achromatic(&to) {
+                to[hue] = from[hue];
+            }
+        }
+
+        for i in 0..3 {
+            if Some(i) == hue {
+                let delta = (to[i] - from[i] + 180.0).rem_euclid(360.0) - 180.0;
+                to[i] = from[i] + delta;
+            } else {
+                from[i] *= self.a;
+                to[i] *= other.a;
+            }
+        }
+
+        let mut components = [0.0; 3];
+
+        for i in 0..3 {
+            components[i] = from[i] + (to[i] - from[i]) * t;
+
+            if Some(i) == hue {
+                components[i] = components[i].rem_euclid(360.0);
+            } else if alpha > 0.0 {
+                components[i] /= alpha;
+            }
+        }
+
+        space.color(components, alpha)
+    }
+
+    ///
+    /// Whether the color can be displayed in sRGB, with a small tolerance for rounding errors.
+    ///
+    pub fn in_gamut(&self) -> bool {
+        const EPSILON: f64 = 1e-6;
+
+        [self.r, self.g, self.b]
+            .iter()
+            .all(|c| (-EPSILON..=1.0 + EPSILON).contains(c))
+    }
+
+    ///
+    /// Brings the color into sRGB using the CSS Color 4 algorithm: the OKLCH chroma is reduced
+    /// until clipping the color changes it less than what's noticeable, keeping lightness and hue.
+    ///
+    pub fn gamut_map(&self) -> Color {
+        const JND: f64 = 0.02;
+        const EPSILON: f64 = 0.0001;
+
+        if self.in_gamut() {
+            return self.clamp();
+        }
+
+        let origin = Oklch::from(*self);
+
+        if origin.l >= 1.0 {
+            return Color {
+                a: self.a,
+                ..Color::white()
+            };
+        }
+
+        if origin.l <= 0.0 {
+            return Color {
+                a: self.a,
+                ..Color::black()
+            };
+        }
+
+        let delta_e = |color: &Color, clipped: &Color| {
+            delta_e_ok(&Oklab::from(*color), &Oklab::from(*clipped))
+        };
+        let mut current = *self;
+        let mut clipped = current.clamp();
+
+        if delta_e(&current, &clipped) < JND {
+            return clipped;
+        }
+
+        let (mut min, mut max) = (0.0, origin.c);
+        let mut min_in_gamut = true;
+
+        while max - min > EPSILON {
+            let chroma = (min + max) / 2.0;
+            current = Color::from(Oklch {
+                c: chroma,
+                ..origin
+            });
+
+            if min_in_gamut && current.in_gamut() {
+                min = chroma;
+                continue;
+            }
+
+            clipped = current.clamp();
+            let error = delta_e(&current, &clipped);
+
+            if error < JND {
+                if JND - error < EPSILON {
+                    break;
+                }
+
+                min_in_gamut = false;
+                min = chroma;
+            } else {
+                max = chroma;
+            }
+        }
+
+        clipped
+    }
+}
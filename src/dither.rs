@@ -0,0 +1,303 @@
+//!
+//! Dithering used when the canvas is quantized to 8 bits per channel. Each pixel is quantized
+//! up or down so that on average an area has the right value, which hides banding.
+//!
+use crate::renderer::NUM_CHANNELS;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dithering {
+    ///
+    /// Values are truncated, see `Canvas::to_u8`.
+    ///
+    #[default]
+    None,
+    ///
+    /// Ordered dithering with an 8x8 Bayer matrix, fast but the pattern is noticeable.
+    ///
+    Bayer,
+    ///
+    /// Ordered dithering with a 64x64 blue noise texture generated from `seed`, the noise has
+    /// no visible pattern.
+    ///
+    BlueNoise { seed: u64 },
+    ///
+    /// Floyd-Steinberg error diffusion, the quantization error of a pixel is spread over its
+    /// neighbours that haven't been quantized yet.
+    ///
+    FloydSteinberg,
+}
+
+// https://stackoverflow.com/a/56842762/8622014
+pub(crate) const TRUNCATION_FACTOR: f64 = (u8::MAX as f64) - f64::EPSILON * 128_f64;
+
+const BAYER_LEVELS: usize = 3;
+const BAYER_SIZE: usize = 1 << BAYER_LEVELS;
+
+const BLUE_NOISE_SIZE: usize = 64;
+// Standard deviation of the gaussian used to measure how clustered the points are.
+const BLUE_NOISE_SIGMA: f64 = 1.5;
+const BLUE_NOISE_RADIUS: isize = 8;
+// Ratio of points placed randomly before they're spread out.
+const BLUE_NOISE_INITIAL_DENSITY: f64 = 0.1;
+
+///
+/// Thresholds in [0, 1) of an 8x8 Bayer matrix, built by recursively splitting each 2x2 block
+/// like `[[0, 2], [3, 1]]`.
+///
+fn bayer_matrix() -> Vec<f64> {
+    let mut matrix = vec![0.0; BAYER_SIZE * BAYER_SIZE];
+
+    for y in 0..BAYER_SIZE {
+        for x in 0..BAYER_SIZE {
+            let mut rank = 0;
+
+            for bit in 0..BAYER_LEVELS {
+                let (bx, by) = ((x >> bit) & 1, (y >> bit) & 1);
+                rank += (2 * (bx ^ by) + by) << (2 * (BAYER_LEVELS - 1 - bit));
+            }
+
+            matrix[y * BAYER_SIZE + x] = (rank as f64 + 0.5) / (BAYER_SIZE * BAYER_SIZE) as f64;
+        }
+    }
+
+    matrix
+}
+
+///
+/// SplitMix64, enough to scatter the initial points of the blue noise.
+///
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+///
+/// Thresholds in [0, 1) of a tileable blue noise texture, generated with Ulichney's
+/// void-and-cluster method: points are ranked by removing the most clustered one and then
+/// adding points in the largest voids, so every threshold level is spread evenly.
+///
+fn blue_noise(seed: u64) -> Vec<f64> {
+    const NUM_PIXELS: usize = BLUE_NOISE_SIZE * BLUE_NOISE_SIZE;
+
+    // Contribution of a point to the energy of the pixels around it, further away it's
+    // negligible.
+    let window = (-BLUE_NOISE_RADIUS..=BLUE_NOISE_RADIUS)
+        .flat_map(|dy| (-BLUE_NOISE_RADIUS..=BLUE_NOISE_RADIUS).map(move |dx| (dx, dy)))
+        .map(|(dx, dy)| {
+            let distance = (dx * dx + dy * dy) as f64;
+            let weight = (-distance / (2.0 * BLUE_NOISE_SIGMA * BLUE_NOISE_SIGMA)).exp();
+            (dx, dy, weight)
+        })
+        .collect::<Vec<(isize, isize, f64)>>();
+    let mut points = vec![false; NUM_PIXELS];
+    let mut energy = vec![0.0; NUM_PIXELS];
+
+    let toggle = |points: &mut [bool], energy: &mut [f64], index: usize| {
+        points[index] = !points[index];
+        let sign = if points[index] { 1.0 } else { -1.0 };
+        let (px, py) = (
+            (index % BLUE_NOISE_SIZE) as isize,
+            (index / BLUE_NOISE_SIZE) as isize,
+        );
+        // The texture is tiled so distances wrap around.
+        let wrap = |v: isize| v.rem_euclid(BLUE_NOISE_SIZE as isize) as usize;
+
+        for (dx, dy, weight) in &window {
+            energy[wrap(py + dy) * BLUE_NOISE_SIZE + wrap(px + dx)] += sign * weight;
+        }
+    };
+    // The tightest cluster is the point with the most energy, the largest void the empty pixel
+    // with the least.
+    let tightest_cluster = |points: &[bool], energy: &[f64]| {
+        (0..NUM_PIXELS)
+            .filter(|i| points[*i])
+            .max_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+            .unwrap()
+    };
+    let largest_void = |points: &[bool], energy: &[f64]| {
+        (0..NUM_PIXELS)
+            .filter(|i| !points[*i])
+            .min_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+            .unwrap()
+    };
+
+    let mut state = seed;
+    let num_initial = (NUM_PIXELS as f64 * BLUE_NOISE_INITIAL_DENSITY) as usize;
+    let mut placed = 0;
+
+    while placed < num_initial {
+        let index = (next_random(&mut state) % NUM_PIXELS as u64) as usize;
+
+        if !points[index] {
+            toggle(&mut points, &mut energy, index);
+            placed += 1;
+        }
+    }
+
+    // Spread the initial points by moving the most clustered one into the largest void until
+    // that doesn't change anything, every pixel getting a chance to move at most.
+    for _ in 0..NUM_PIXELS {
+        let cluster = tightest_cluster(&points, &energy);
+        toggle(&mut points, &mut energy, cluster);
+        let void = largest_void(&points, &energy);
+        toggle(&mut points, &mut energy, void);
+
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; NUM_PIXELS];
+    let (initial_points, initial_energy) = (points.clone(), energy.clone());
+
+    for rank in (0..num_initial).rev() {
+        let cluster = tightest_cluster(&points, &energy);
+        toggle(&mut points, &mut energy, cluster);
+        ranks[cluster] = rank;
+    }
+
+    let (mut points, mut energy) = (initial_points, initial_energy);
+
+    for rank in num_initial..NUM_PIXELS {
+        let void = largest_void(&points, &energy);
+        toggle(&mut points, &mut energy, void);
+        ranks[void] = rank;
+    }
+
+    ranks
+        .iter()
+        .map(|rank| (*rank as f64 + 0.5) / NUM_PIXELS as f64)
+        .collect()
+}
+
+///
+/// The blue noise textures generated so far by seed, generating one takes a while and every
+/// export with `Dithering::BlueNoise` needs it.
+///
+static BLUE_NOISE_CACHE: OnceLock<Mutex<HashMap<u64, Arc<[f64]>>>> = OnceLock::new();
+
+fn cached_blue_noise(seed: u64) -> Arc<[f64]> {
+    let cache = BLUE_NOISE_CACHE.get_or_init(Default::default);
+
+    if let Some(thresholds) = cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&seed)
+    {
+        return thresholds.clone();
+    }
+
+    // Generated without holding the lock, the texture of another seed can be generated
+    // meanwhile.
+    let thresholds = Arc::from(blue_noise(seed));
+
+    cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(seed)
+        .or_insert(thresholds)
+        .clone()
+}
+
+fn to_u8(value: f64) -> u8 {
+    value.clamp(0.0, u8::MAX as f64) as u8
+}
+
+///
+/// Thresholds in [0, 1) of the ordered dithering modes, with the size of their square matrix.
+///
+pub(crate) fn threshold_matrix(dithering: Dithering) -> Option<(Arc<[f64]>, usize)> {
+    match dithering {
+        Dithering::Bayer => Some((Arc::from(bayer_matrix()), BAYER_SIZE)),
+        Dithering::BlueNoise { seed } => Some((cached_blue_noise(seed), BLUE_NOISE_SIZE)),
+        _ => None,
+    }
+}
+
+///
+/// Floyd-Steinberg error diffusion over pixels with channels in [0, 255]. `quantize` picks the
+/// output of a pixel and returns it along with the channels it stands for, the difference is
+/// spread over the neighbours that come after it.
+///
+pub(crate) fn diffuse_errors<T>(
+    mut pixels: Vec<[f64; NUM_CHANNELS]>,
+    width: usize,
+    mut quantize: impl FnMut(&[f64; NUM_CHANNELS]) -> (T, [f64; NUM_CHANNELS]),
+) -> Vec<T> {
+    let mut result = Vec::with_capacity(pixels.len());
+
+    for i in 0..pixels.len() {
+        // Clamping first keeps values out of range from spreading huge errors.
+        let pixel = pixels[i].map(|v| v.clamp(0.0, u8::MAX as f64));
+        let (output, quantized) = quantize(&pixel);
+        let x = i % width;
+        result.push(output);
+
+        let mut spread = |offset: usize, weight: f64| {
+            if let Some(neighbour) = pixels.get_mut(i + offset) {
+                for channel in 0..NUM_CHANNELS {
+                    neighbour[channel] += (pixel[channel] - quantized[channel]) * weight / 16.0;
+                }
+            }
+        };
+
+        if x + 1 < width {
+            spread(1, 7.0);
+            spread(width + 1, 1.0);
+        }
+        if x > 0 {
+            spread(width - 1, 3.0);
+        }
+        spread(width, 5.0);
+    }
+
+    result
+}
+
+///
+/// Quantizes RGBA values in [0, 1] to 8 bits.
+///
+pub(crate) fn quantize(values: &[f64], width: usize, dithering: Dithering) -> Vec<u8> {
+    let scale = u8::MAX as f64;
+
+    if let Some((thresholds, size)) = threshold_matrix(dithering) {
+        return values
+            .chunks(NUM_CHANNELS)
+            .enumerate()
+            .flat_map(|(i, pixel)| {
+                let (x, y) = (i % width, i / width);
+                let threshold = thresholds[(y % size) * size + x % size];
+                pixel
+                    .iter()
+                    .map(move |v| to_u8((v * scale + threshold).floor()))
+                    .collect::<Vec<u8>>()
+            })
+            .collect();
+    }
+
+    match dithering {
+        Dithering::FloydSteinberg => {
+            let pixels = values
+                .chunks(NUM_CHANNELS)
+                .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]].map(|v| v * scale))
+                .collect();
+
+            diffuse_errors(pixels, width, |pixel| {
+                let rounded = pixel.map(f64::round);
+                (rounded.map(|v| v as u8), rounded)
+            })
+            .concat()
+        }
+        _ => values
+            .iter()
+            .map(|v| to_u8(v * TRUNCATION_FACTOR))
+            .collect(),
+    }
+}
@@ -1,3 +1,5 @@
+use crate::color::clamp;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Point {
     pub x: f64,
@@ -61,20 +63,156 @@ pub enum PathOps {
 
 pub type Path<'a> = &'a [PathOps];
 
+///
+/// A path command with absolute coordinates, see `segments`.
+///
+#[derive(Debug, Clone, Copy)]
+pub enum Segment {
+    MoveTo(Point),
+    Line(Point, Point),
+    Quad(QuadraticBezier),
+    Cubic(CubicBezier),
+    ///
+    /// Goes from the current point back to the start of the subpath.
+    ///
+    Close(Point, Point),
+}
+
+///
+/// Resolves the relative commands of `path` and maps every point through `transform`.
+///
+pub fn segments(path: Path, transform: impl Fn(&Point) -> Point) -> Vec<Segment> {
+    let mut result = Vec::with_capacity(path.len());
+    let mut start = Point { x: 0.0, y: 0.0 };
+    let mut current = Point { x: 0.0, y: 0.0 };
+    let mut current_mapped = transform(&current);
+    let offset = |p: &Point, x: f64, y: f64| Point {
+        x: p.x + x,
+        y: p.y + y,
+    };
+
+    for op in path.iter() {
+        let (segment, next) = match *op {
+            PathOps::MoveTo { x, y } => {
+                let p = Point { x, y };
+                start = p;
+                (Segment::MoveTo(transform(&p)), p)
+            }
+            PathOps::MoveToRel { x, y } => {
+                let p = offset(&current, x, y);
+                start = p;
+                (Segment::MoveTo(transform(&p)), p)
+            }
+            PathOps::LineTo { x, y } => {
+                let p = Point { x, y };
+                (Segment::Line(current_mapped, transform(&p)), p)
+            }
+            PathOps::LineToRel { x, y } => {
+                let p = offset(&current, x, y);
+                (Segment::Line(current_mapped, transform(&p)), p)
+            }
+            PathOps::QuadTo { x1, y1, x2, y2 } => {
+                let (p1, p2) = (Point { x: x1, y: y1 }, Point { x: x2, y: y2 });
+                let curve = QuadraticBezier::new(current_mapped, transform(&p1), transform(&p2));
+                (Segment::Quad(curve), p2)
+            }
+            PathOps::QuadToRel { x1, y1, x2, y2 } => {
+                let (p1, p2) = (offset(&current, x1, y1), offset(&current, x2, y2));
+                let curve = QuadraticBezier::new(current_mapped, transform(&p1), transform(&p2));
+                (Segment::Quad(curve), p2)
+            }
+            PathOps::CubicTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            } => {
+                let (p1, p2) = (Point { x: x1, y: y1 }, Point { x: x2, y: y2 });
+                let p3 = Point { x: x3, y: y3 };
+                let curve = CubicBezier::new(
+                    current_mapped,
+                    transform(&p1),
+                    transform(&p2),
+                    transform(&p3),
+                );
+                (Segment::Cubic(curve), p3)
+            }
+            PathOps::CubicToRel {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            } => {
+                let (p1, p2) = (offset(&current, x1, y1), offset(&current, x2, y2));
+                let p3 = offset(&current, x3, y3);
+                let curve = CubicBezier::new(
+                    current_mapped,
+                    transform(&p1),
+                    transform(&p2),
+                    transform(&p3),
+                );
+                (Segment::Cubic(curve), p3)
+            }
+            PathOps::Close => (Segment::Close(current_mapped, transform(&start)), start),
+        };
+
+        current_mapped = match segment {
+            Segment::MoveTo(p) | Segment::Line(_, p) | Segment::Close(_, p) => p,
+            Segment::Quad(curve) => curve.points()[2],
+            Segment::Cubic(curve) => curve.points()[3],
+        };
+        current = next;
+        result.push(segment);
+    }
+
+    result
+}
+
+///
+/// Bounds of a shape in canvas pixels, the coordinates are rounded down and can extend past the
+/// canvas.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BoundingBox {
-    pub min_x: usize,
-    pub min_y: usize,
-    pub max_x: usize,
-    pub max_y: usize,
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
 }
 
 impl Default for BoundingBox {
     fn default() -> Self {
         Self {
-            min_x: usize::MAX,
-            min_y: usize::MAX,
-            max_x: usize::MIN,
-            max_y: usize::MIN,
+            min_x: i64::MAX,
+            min_y: i64::MAX,
+            max_x: i64::MIN,
+            max_y: i64::MIN,
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_y > self.max_y
+    }
+
+    ///
+    /// The square of pixels around `center`, at least one pixel wide. Gradients are laid out over
+    /// bounds, this places them independently of the shape they fill.
+    ///
+    #[cfg(any(feature = "text", feature = "svg"))]
+    pub(crate) fn square(center: Point, half_width: f64) -> BoundingBox {
+        let half_width = half_width.max(0.5);
+
+        BoundingBox {
+            min_x: (center.x - half_width).round() as i64,
+            min_y: (center.y - half_width).round() as i64,
+            max_x: (center.x + half_width).round() as i64,
+            max_y: (center.y + half_width).round() as i64,
         }
     }
 }
@@ -87,7 +225,7 @@ fn approximate_integral(x: f64) -> f64 {
 fn approximate_inverse_integral(x: f64) -> f64 {
     const B: f64 = 0.39;
 
-    x * (1.0 - B + f64::sqrt(B * B - 0.25 * x * x))
+    x * (1.0 - B + f64::sqrt(B * B + 0.25 * x * x))
 }
 
 pub struct ParabolaParams {
@@ -128,6 +266,15 @@ impl QuadraticBezier {
         Point { x, y }
     }
 
+    pub fn derivative(&self, t: f64) -> Point {
+        let one_minus_t = 1.0 - t;
+
+        Point {
+            x: 2.0 * one_minus_t * (self.x1 - self.x0) + 2.0 * t * (self.x2 - self.x1),
+            y: 2.0 * one_minus_t * (self.y1 - self.y0) + 2.0 * t * (self.y2 - self.y1),
+        }
+    }
+
     pub fn map_to_basic(&self) -> ParabolaParams {
         let ddx = 2.0 * self.x1 - self.x0 - self.x2;
         let ddy = 2.0 * self.y1 - self.y0 - self.y2;
@@ -160,6 +307,107 @@ impl QuadraticBezier {
         result.push(1.0);
         result
     }
+
+    pub fn points(&self) -> [Point; 3] {
+        [
+            Point {
+                x: self.x0,
+                y: self.y0,
+            },
+            Point {
+                x: self.x1,
+                y: self.y1,
+            },
+            Point {
+                x: self.x2,
+                y: self.y2,
+            },
+        ]
+    }
+
+    ///
+    /// Returns the part of the curve between `t0` and `t1` as a new curve.
+    ///
+    pub fn subsegment(&self, t0: f64, t1: f64) -> QuadraticBezier {
+        let p0 = self.eval(t0);
+        let p2 = self.eval(t1);
+        let dt = t1 - t0;
+        let p1 = Point {
+            x: p0.x + dt * ((1.0 - t0) * (self.x1 - self.x0) + t0 * (self.x2 - self.x1)),
+            y: p0.y + dt * ((1.0 - t0) * (self.y1 - self.y0) + t0 * (self.y2 - self.y1)),
+        };
+
+        QuadraticBezier::new(p0, p1, p2)
+    }
+
+    ///
+    /// Values of `t` inside (0, 1) where either `x` or `y` reaches an extremum. Between two
+    /// consecutive values the curve is monotonic on both axes.
+    ///
+    pub fn extrema(&self) -> Vec<f64> {
+        let mut result = Vec::with_capacity(2);
+
+        for (a, b, c) in [(self.x0, self.x1, self.x2), (self.y0, self.y1, self.y2)] {
+            let denominator = a - 2.0 * b + c;
+
+            if denominator.abs() > f64::EPSILON {
+                let t = (a - b) / denominator;
+
+                if t > 0.0 && t < 1.0 {
+                    result.push(t);
+                }
+            }
+        }
+
+        result
+    }
+
+    ///
+    /// Returns the `t` in `[t0, t1]` for which the coordinate on the given axis equals `value`.
+    /// The curve is expected to be monotonic on that axis between `t0` and `t1`.
+    ///
+    pub fn solve_monotonic(&self, vertical: bool, value: f64, t0: f64, t1: f64) -> f64 {
+        let (p0, p1, p2) = if vertical {
+            (self.y0, self.y1, self.y2)
+        } else {
+            (self.x0, self.x1, self.x2)
+        };
+        let a = p0 - 2.0 * p1 + p2;
+        let b = 2.0 * (p1 - p0);
+        let c = p0 - value;
+        let inside = |t: f64| t >= t0 - 1e-9 && t <= t1 + 1e-9;
+
+        let t = if a.abs() <= f64::EPSILON {
+            -c / b
+        } else {
+            let discriminant = f64::max(b * b - 4.0 * a * c, 0.0);
+            let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+            let (r0, r1) = (q / a, if q != 0.0 { c / q } else { q / a });
+
+            if inside(r0) {
+                r0
+            } else {
+                r1
+            }
+        };
+
+        if t.is_finite() {
+            clamp(t, t0, t1)
+        } else {
+            0.5 * (t0 + t1)
+        }
+    }
+
+    ///
+    /// Computes the integral of `(x - offset) dy` along the curve. For a curve that's contained
+    /// in a single pixel this is the signed area between the curve and the pixel's left side.
+    ///
+    pub fn area_from(&self, offset: f64) -> f64 {
+        let (x0, x1, x2) = (self.x0 - offset, self.x1 - offset, self.x2 - offset);
+        let (d0, d1) = (self.y1 - self.y0, self.y2 - self.y1);
+
+        x0 * (d0 / 2.0 + d1 / 6.0) + x1 * (d0 + d1) / 3.0 + x2 * (d0 / 6.0 + d1 / 2.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -188,6 +436,27 @@ impl CubicBezier {
         }
     }
 
+    pub fn points(&self) -> [Point; 4] {
+        [
+            Point {
+                x: self.x0,
+                y: self.y0,
+            },
+            Point {
+                x: self.x1,
+                y: self.y1,
+            },
+            Point {
+                x: self.x2,
+                y: self.y2,
+            },
+            Point {
+                x: self.x3,
+                y: self.y3,
+            },
+        ]
+    }
+
     pub fn eval(&self, t: f64) -> Point {
         let one_minus_t = 1.0 - t;
         let one_minus_t_squared = (1.0 - t) * (1.0 - t);
@@ -299,4 +568,190 @@ impl CubicBezier {
 
         result
     }
+
+    ///
+    /// Approximates the curve with quadratic Bézier curves, `tolerance` being the maximum
+    /// distance allowed between the approximation and the real curve.
+    ///
+    /// The error of approximating a cubic with a single quadratic is proportional to the third
+    /// derivative, which shrinks cubically when the curve is split in `n` equal parts.
+    ///
+    pub fn to_quadratics(&self, tolerance: f64) -> Vec<QuadraticBezier> {
+        let dx = self.x3 - 3.0 * self.x2 + 3.0 * self.x1 - self.x0;
+        let dy = self.y3 - 3.0 * self.y2 + 3.0 * self.y1 - self.y0;
+        let err = f64::sqrt(3.0) / 36.0 * f64::hypot(dx, dy);
+        let n = f64::max(f64::ceil(f64::cbrt(err / tolerance)), 1.0) as usize;
+
+        (0..n)
+            .map(|i| {
+                let t0 = i as f64 / n as f64;
+                let t1 = (i + 1) as f64 / n as f64;
+                let p0 = self.eval(t0);
+                let p3 = self.eval(t1);
+                let d0 = self.derivative(t0);
+                let d1 = self.derivative(t1);
+                let dt = (t1 - t0) / 3.0;
+                let c1 = Point {
+                    x: p0.x + d0.x * dt,
+                    y: p0.y + d0.y * dt,
+                };
+                let c2 = Point {
+                    x: p3.x - d1.x * dt,
+                    y: p3.y - d1.y * dt,
+                };
+                let control = Point {
+                    x: (3.0 * (c1.x + c2.x) - p0.x - p3.x) / 4.0,
+                    y: (3.0 * (c1.y + c2.y) - p0.y - p3.y) / 4.0,
+                };
+
+                QuadraticBezier::new(p0, control, p3)
+            })
+            .collect()
+    }
+
+    pub fn derivative(&self, t: f64) -> Point {
+        let one_minus_t = 1.0 - t;
+
+        Point {
+            x: 3.0 * one_minus_t * one_minus_t * (self.x1 - self.x0)
+                + 6.0 * one_minus_t * t * (self.x2 - self.x1)
+                + 3.0 * t * t * (self.x3 - self.x2),
+            y: 3.0 * one_minus_t * one_minus_t * (self.y1 - self.y0)
+                + 6.0 * one_minus_t * t * (self.y2 - self.y1)
+                + 3.0 * t * t * (self.y3 - self.y2),
+        }
+    }
+}
+
+///
+/// A subpath flattened into lines.
+///
+#[derive(Debug, Clone)]
+pub struct Polyline {
+    pub points: Vec<Point>,
+    ///
+    /// Whether the subpath ends with `PathOps::Close`, the closing line is then the last one of
+    /// `points`.
+    ///
+    pub closed: bool,
+}
+
+///
+/// Flattens the curves of `path` into lines that don't stray further than `tolerance` from them,
+/// in the same way as `CanvasDescription::tolerance`. Subpaths without any segment are kept as a
+/// single point.
+///
+pub fn flatten(path: Path, tolerance: f64) -> Vec<Polyline> {
+    let mut result: Vec<Polyline> = vec![];
+    let current = |result: &mut Vec<Polyline>, start: Point| {
+        if result.last().is_none_or(|polyline| polyline.closed) {
+            result.push(Polyline {
+                points: vec![start],
+                closed: false,
+            });
+        }
+        result.len() - 1
+    };
+
+    for segment in segments(path, |p| *p) {
+        match segment {
+            Segment::MoveTo(p) => result.push(Polyline {
+                points: vec![p],
+                closed: false,
+            }),
+            Segment::Line(p0, p1) => {
+                let i = current(&mut result, p0);
+                result[i].points.push(p1);
+            }
+            Segment::Quad(curve) => {
+                let i = current(&mut result, curve.points()[0]);
+                let ts = curve.subdivide(tolerance);
+                result[i]
+                    .points
+                    .extend(ts[1..].iter().map(|t| curve.eval(*t)));
+            }
+            Segment::Cubic(curve) => {
+                let i = current(&mut result, curve.points()[0]);
+                result[i]
+                    .points
+                    .extend(curve.subdivide(tolerance).into_iter().skip(1));
+            }
+            Segment::Close(p0, p1) => {
+                let i = current(&mut result, p0);
+                result[i].points.push(p1);
+                result[i].closed = true;
+            }
+        }
+    }
+
+    result
+}
+
+///
+/// The arc-length parameterization of a path, flattened into lines. Distances run along the
+/// subpaths one after the other, the gaps between them don't count.
+///
+pub struct PathMeasure {
+    ///
+    /// The lines of the flattened path with the distance at their start, in path order.
+    ///
+    lines: Vec<(Point, Point, f64)>,
+    length: f64,
+}
+
+impl PathMeasure {
+    ///
+    /// Measures `path` flattened with `flatten`.
+    ///
+    pub fn new(path: Path, tolerance: f64) -> PathMeasure {
+        let mut lines = vec![];
+        let mut length = 0.0;
+
+        for (p0, p1) in flatten(path, tolerance)
+            .iter()
+            .flat_map(|polyline| polyline.points.windows(2).map(|w| (w[0], w[1])))
+        {
+            let line_length = p0.distance_to(&p1);
+            if line_length > 0.0 {
+                lines.push((p0, p1, length));
+                length += line_length;
+            }
+        }
+
+        PathMeasure { lines, length }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    ///
+    /// The point at `distance` from the start of the path and the unit tangent there, `None` past
+    /// either end.
+    ///
+    pub fn point_at(&self, distance: f64) -> Option<(Point, Point)> {
+        if !(0.0..=self.length).contains(&distance) || self.lines.is_empty() {
+            return None;
+        }
+
+        let index = self
+            .lines
+            .partition_point(|(_, _, start)| *start <= distance)
+            .saturating_sub(1);
+        let (p0, p1, start) = self.lines[index];
+        let line_length = p0.distance_to(&p1);
+        let tangent = Point {
+            x: (p1.x - p0.x) / line_length,
+            y: (p1.y - p0.y) / line_length,
+        };
+        let t = distance - start;
+
+        Some((
+            Point {
+                x: p0.x + tangent.x * t,
+                y: p0.y + tangent.y * t,
+            },
+            tangent,
+        ))
+    }
 }
@@ -1,5 +1,18 @@
 pub mod canvas;
 pub mod color;
+pub mod dither;
 pub mod geometry;
+pub mod mask;
 pub mod math;
+pub mod palette;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod recording;
+#[cfg(feature = "reference")]
+pub mod reference;
 pub mod renderer;
+pub mod sdf;
+#[cfg(feature = "svg")]
+pub mod svg;
+#[cfg(feature = "text")]
+pub mod text;
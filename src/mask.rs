@@ -0,0 +1,78 @@
+//!
+//! Coverage-only rendering: shapes are rasterized exactly like `Canvas::draw_shape` but only the
+//! coverage of each pixel is kept, without any color or blending. That's all glyph atlases,
+//! distance fields and clip masks need, at a quarter of the memory of an RGBA canvas.
+//!
+use crate::{
+    canvas::{AccumulationCell, CanvasDescription},
+    color::FillRule,
+    geometry::{Path, Point},
+    renderer::{render_path, sweep_coverage, RenderState},
+};
+
+pub struct Mask {
+    ///
+    /// Only the size, viewbox, tolerance and curve rendering are used, masks have no colors.
+    ///
+    pub desc: CanvasDescription,
+    ///
+    /// The coverage of every pixel in [0, 1], row by row.
+    ///
+    pub coverage: Vec<f32>,
+    pub accumulation_buffer: Vec<AccumulationCell>,
+}
+
+impl Mask {
+    pub fn new(desc: CanvasDescription) -> Mask {
+        let num_pixels = desc.width * desc.height;
+
+        Mask {
+            desc,
+            coverage: vec![0.0; num_pixels],
+            accumulation_buffer: vec![AccumulationCell { area: 0.0 }; num_pixels],
+        }
+    }
+
+    ///
+    /// Adds the coverage of `path` to the mask. Overlapping shapes combine like opaque shapes
+    /// drawn with `blend_func::source_over`, so the mask matches the alpha channel of a
+    /// transparent canvas the same shapes are drawn on.
+    ///
+    pub fn draw_shape(
+        &mut self,
+        path: Path,
+        fill_rule: FillRule,
+        transform: impl Fn(&Point) -> Point,
+    ) {
+        let width = self.desc.width;
+        let coverage = &mut self.coverage;
+        let mut state = RenderState {
+            desc: &self.desc,
+            accumulation_buffer: &mut self.accumulation_buffer,
+        };
+        let bounds = render_path(&mut state, path, transform);
+
+        sweep_coverage(&mut state, fill_rule, &bounds, |x, y, alpha| {
+            let pixel = &mut coverage[y * width + x];
+            *pixel = alpha + *pixel * (1.0 - alpha);
+        });
+    }
+
+    ///
+    /// Resets the coverage so the mask can be reused for another shape.
+    ///
+    pub fn clear(&mut self) {
+        self.coverage.fill(0.0);
+    }
+
+    ///
+    /// The coverage as an 8-bit alpha (A8) image. Unlike `Canvas::to_u8` values are rounded, a
+    /// fully covered pixel is 255.
+    ///
+    pub fn to_u8(&self) -> Vec<u8> {
+        self.coverage
+            .iter()
+            .map(|alpha| (alpha.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8)
+            .collect()
+    }
+}
@@ -0,0 +1,395 @@
+//!
+//! Reduces a canvas to a palette of at most 256 colors, for indexed images and displays that
+//! can only show a few colors. Colors are compared in OKLab, with alpha as a fourth axis, so the
+//! palette follows the differences that are actually seen.
+//!
+use crate::{
+    canvas::Canvas,
+    color::{Color, Oklab, RgbSpace},
+    dither::{diffuse_errors, threshold_matrix, Dithering},
+    renderer::NUM_CHANNELS,
+};
+use std::{collections::HashMap, ops::Range};
+
+pub const MAX_COLORS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantizer {
+    ///
+    /// Recursively splits the box holding the most spread out colors at its weighted median
+    /// along its longest axis. Each box becomes the average of its colors.
+    ///
+    #[default]
+    MedianCut,
+    ///
+    /// Refines the median cut palette with `iterations` rounds of k-means, which is slower but
+    /// gets closer to the original colors.
+    ///
+    KMeans { iterations: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QuantizeOptions {
+    ///
+    /// The size of the palette, between 1 and `MAX_COLORS`. Images with fewer colors keep them
+    /// exactly.
+    ///
+    pub num_colors: usize,
+    pub quantizer: Quantizer,
+    ///
+    /// Ordered dithering offsets pixels by about the distance between the palette colors before
+    /// picking the closest one, Floyd-Steinberg spreads the difference to the neighbours.
+    ///
+    pub dithering: Dithering,
+}
+
+impl Default for QuantizeOptions {
+    fn default() -> Self {
+        QuantizeOptions {
+            num_colors: MAX_COLORS,
+            quantizer: Quantizer::default(),
+            dithering: Dithering::default(),
+        }
+    }
+}
+
+///
+/// An image where every pixel is an index into `palette`.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedImage {
+    pub width: usize,
+    pub height: usize,
+    ///
+    /// 8-bit RGBA colors, straight alpha like `Canvas::to_u8`.
+    ///
+    pub palette: Vec<[u8; NUM_CHANNELS]>,
+    pub indices: Vec<u8>,
+    ///
+    /// The color space of the palette, see `CanvasDescription::color_space`.
+    ///
+    pub color_space: RgbSpace,
+}
+
+impl IndexedImage {
+    ///
+    /// The RGBA pixels of the image, in the layout of `Canvas::to_u8`.
+    ///
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.indices
+            .iter()
+            .flat_map(|index| self.palette[*index as usize])
+            .collect()
+    }
+}
+
+type Position = [f64; NUM_CHANNELS];
+
+///
+/// A distinct color of the image and the number of pixels having it.
+///
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    position: Position,
+    weight: f64,
+}
+
+///
+/// The OKLab position of a pixel encoded in `space`.
+///
+fn to_position(rgba: [u8; NUM_CHANNELS], space: RgbSpace) -> Position {
+    let [r, g, b, a] = rgba.map(|v| v as f64 / u8::MAX as f64);
+    let lab = Oklab::from(space.to_srgb(&Color { r, g, b, a }));
+
+    [lab.l, lab.a, lab.b, lab.alpha]
+}
+
+fn to_rgba(position: &Position, space: RgbSpace) -> [u8; NUM_CHANNELS] {
+    let color = space
+        .from_srgb(&Color::from(Oklab {
+            l: position[0],
+            a: position[1],
+            b: position[2],
+            alpha: position[3],
+        }))
+        .clamp();
+
+    [color.r, color.g, color.b, color.a].map(|v| (v * u8::MAX as f64).round() as u8)
+}
+
+fn distance(a: &Position, b: &Position) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+fn closest(palette: &[Position], position: &Position) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a, position).total_cmp(&distance(b, position)))
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+fn average(samples: &[Sample]) -> Position {
+    let mut sum = [0.0; NUM_CHANNELS];
+    let mut weight = 0.0;
+
+    for sample in samples {
+        (0..NUM_CHANNELS).for_each(|i| sum[i] += sample.position[i] * sample.weight);
+        weight += sample.weight;
+    }
+
+    sum.map(|v| v / weight)
+}
+
+///
+/// The axis along which `samples` are the most spread out, and how much.
+///
+fn longest_axis(samples: &[Sample]) -> (usize, f64) {
+    (0..NUM_CHANNELS)
+        .map(|axis| {
+            let values = samples.iter().map(|sample| sample.position[axis]);
+            let min = values.clone().fold(f64::MAX, f64::min);
+            let max = values.fold(f64::MIN, f64::max);
+            (axis, max - min)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+}
+
+fn median_cut(samples: &mut [Sample], num_colors: usize) -> Vec<Position> {
+    let mut boxes = Vec::<Range<usize>>::with_capacity(num_colors);
+    boxes.push(0..samples.len());
+
+    while boxes.len() < num_colors {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| range.len() > 1)
+            .map(|(i, range)| (i, longest_axis(&samples[range.clone()])))
+            .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1));
+
+        let (index, (axis, _)) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+        let range = boxes[index].clone();
+        let colors = &mut samples[range.clone()];
+        colors.sort_by(|a, b| a.position[axis].total_cmp(&b.position[axis]));
+
+        let half = colors.iter().map(|sample| sample.weight).sum::<f64>() / 2.0;
+        let mut accumulated = 0.0;
+        let median = colors
+            .iter()
+            .position(|sample| {
+                accumulated += sample.weight;
+                accumulated >= half
+            })
+            .unwrap_or(0);
+        // Both halves keep at least one color.
+        let split = range.start + (median + 1).clamp(1, colors.len() - 1);
+
+        boxes[index] = range.start..split;
+        boxes.push(split..range.end);
+    }
+
+    boxes
+        .into_iter()
+        .map(|range| average(&samples[range]))
+        .collect()
+}
+
+fn k_means(samples: &[Sample], mut centroids: Vec<Position>, iterations: usize) -> Vec<Position> {
+    for _ in 0..iterations {
+        let mut sums = vec![[0.0; NUM_CHANNELS]; centroids.len()];
+        let mut weights = vec![0.0; centroids.len()];
+
+        for sample in samples {
+            let i = closest(&centroids, &sample.position);
+            (0..NUM_CHANNELS).for_each(|c| sums[i][c] += sample.position[c] * sample.weight);
+            weights[i] += sample.weight;
+        }
+
+        let updated = centroids
+            .iter()
+            .zip(sums.iter().zip(&weights))
+            .map(|(centroid, (sum, weight))| {
+                if *weight > 0.0 {
+                    sum.map(|v| v / weight)
+                } else {
+                    *centroid
+                }
+            })
+            .collect::<Vec<Position>>();
+
+        if updated == centroids {
+            break;
+        }
+
+        centroids = updated;
+    }
+
+    centroids
+}
+
+///
+/// The index of the closest palette color of every pixel.
+///
+fn map_to_palette(
+    pixels: &[[u8; NUM_CHANNELS]],
+    width: usize,
+    palette: &[[u8; NUM_CHANNELS]],
+    dithering: Dithering,
+    space: RgbSpace,
+) -> Vec<u8> {
+    let positions = palette
+        .iter()
+        .map(|rgba| to_position(*rgba, space))
+        .collect::<Vec<_>>();
+    let closest_rgba = |rgba: &[f64; NUM_CHANNELS]| {
+        closest(
+            &positions,
+            &to_position(rgba.map(|v| v.round() as u8), space),
+        )
+    };
+
+    if let Some((thresholds, size)) = threshold_matrix(dithering) {
+        // Roughly the distance between palette colors along each channel.
+        let spread = u8::MAX as f64 / (palette.len() as f64).cbrt();
+
+        return pixels
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                let (x, y) = (i % width, i / width);
+                let offset = (thresholds[(y % size) * size + x % size] - 0.5) * spread;
+                let rgba = pixel.map(|v| (v as f64 + offset).clamp(0.0, u8::MAX as f64));
+
+                closest_rgba(&rgba) as u8
+            })
+            .collect();
+    }
+
+    match dithering {
+        Dithering::FloydSteinberg => {
+            let pixels = pixels.iter().map(|pixel| pixel.map(|v| v as f64)).collect();
+
+            diffuse_errors(pixels, width, |pixel| {
+                let index = closest_rgba(pixel);
+                (index as u8, palette[index].map(|v| v as f64))
+            })
+        }
+        _ => {
+            let mut cache = HashMap::<[u8; NUM_CHANNELS], u8>::new();
+
+            pixels
+                .iter()
+                .map(|pixel| {
+                    *cache
+                        .entry(*pixel)
+                        .or_insert_with(|| closest(&positions, &to_position(*pixel, space)) as u8)
+                })
+                .collect()
+        }
+    }
+}
+
+impl Canvas {
+    ///
+    /// The pixels given by `to_u8` without dithering, with invisible pixels all having the same
+    /// color. The pixels are dithered against the palette instead.
+    ///
+    fn visible_pixels(&self) -> Vec<[u8; NUM_CHANNELS]> {
+        self.to_u8_dithered(Dithering::None)
+            .chunks(NUM_CHANNELS)
+            .map(|pixel| match pixel[3] {
+                0 => [0; NUM_CHANNELS],
+                _ => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            })
+            .collect()
+    }
+
+    ///
+    /// Reduces the pixels given by `to_u8`, before dithering, to a palette of `options.num_colors`
+    /// colors.
+    ///
+    pub fn quantize(&self, options: &QuantizeOptions) -> IndexedImage {
+        assert!(
+            (1..=MAX_COLORS).contains(&options.num_colors),
+            "A palette has between 1 and {} colors, not {}",
+            MAX_COLORS,
+            options.num_colors
+        );
+
+        let space = self.desc.color_space;
+        let pixels = self.visible_pixels();
+        let mut counts = HashMap::<[u8; NUM_CHANNELS], usize>::new();
+        pixels
+            .iter()
+            .for_each(|pixel| *counts.entry(*pixel).or_default() += 1);
+        let mut distinct = counts.into_iter().collect::<Vec<_>>();
+        // The map is unordered, sorting keeps the palette deterministic.
+        distinct.sort_unstable();
+
+        let palette = if distinct.len() <= options.num_colors {
+            distinct.iter().map(|(rgba, _)| *rgba).collect::<Vec<_>>()
+        } else {
+            let mut samples = distinct
+                .iter()
+                .map(|(rgba, count)| Sample {
+                    position: to_position(*rgba, space),
+                    weight: *count as f64,
+                })
+                .collect::<Vec<Sample>>();
+            let centroids = median_cut(&mut samples, options.num_colors);
+            let centroids = match options.quantizer {
+                Quantizer::MedianCut => centroids,
+                Quantizer::KMeans { iterations } => k_means(&samples, centroids, iterations),
+            };
+
+            centroids
+                .iter()
+                .map(|position| to_rgba(position, space))
+                .collect()
+        };
+
+        IndexedImage {
+            width: self.desc.width,
+            height: self.desc.height,
+            indices: map_to_palette(&pixels, self.desc.width, &palette, options.dithering, space),
+            palette,
+            color_space: self.desc.color_space,
+        }
+    }
+
+    ///
+    /// Maps the pixels given by `to_u8`, before dithering, to a fixed palette, like the colors
+    /// of a display. The palette is in the color space of the canvas.
+    ///
+    pub fn quantize_to(
+        &self,
+        palette: &[[u8; NUM_CHANNELS]],
+        dithering: Dithering,
+    ) -> IndexedImage {
+        assert!(
+            (1..=MAX_COLORS).contains(&palette.len()),
+            "A palette has between 1 and {} colors, not {}",
+            MAX_COLORS,
+            palette.len()
+        );
+
+        IndexedImage {
+            width: self.desc.width,
+            height: self.desc.height,
+            indices: map_to_palette(
+                &self.visible_pixels(),
+                self.desc.width,
+                palette,
+                dithering,
+                self.desc.color_space,
+            ),
+            palette: palette.to_vec(),
+            color_space: self.desc.color_space,
+        }
+    }
+}
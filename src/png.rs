@@ -0,0 +1,309 @@
+//!
+//! PNG export of a canvas or an indexed image. The color space of the canvas is embedded with a
+//! cICP chunk, which recent decoders use, and an sRGB or iCCP chunk for the others.
+//! See https://www.w3.org/TR/png-3/
+//!
+use crate::{canvas::Canvas, color::RgbSpace, palette::IndexedImage, renderer::NUM_CHANNELS};
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use std::{fs, io, path::Path};
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const COMPRESSION_LEVEL: u8 = 6;
+
+const COLOR_TYPE_INDEXED: u8 = 3;
+const COLOR_TYPE_RGBA: u8 = 6;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+
+        table[n] = c;
+        n += 1;
+    }
+
+    table
+};
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let crc = parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(!0, |crc, byte| {
+            CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+        });
+
+    !crc
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    png.extend(crc32(&[kind, data]).to_be_bytes());
+}
+
+///
+/// The coding-independent code points of ITU-T H.273: color primaries, transfer
+/// characteristics, matrix coefficients (none for RGB) and full range.
+///
+fn cicp(space: RgbSpace) -> [u8; 4] {
+    let primaries = match space.linear() {
+        RgbSpace::LinearSrgb => 1,
+        RgbSpace::LinearDisplayP3 => 12,
+        _ => 9,
+    };
+    let transfer = match space {
+        RgbSpace::Srgb | RgbSpace::DisplayP3 => 13,
+        // BT.2020 uses the transfer function of BT.709
+        RgbSpace::Rec2020 => 1,
+        _ => 8,
+    };
+
+    [primaries, transfer, 0, 1]
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let p = left as i16 + up as i16 - up_left as i16;
+    let (pa, pb, pc) = (
+        (p - left as i16).abs(),
+        (p - up as i16).abs(),
+        (p - up_left as i16).abs(),
+    );
+
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+///
+/// Prefixes every row with the filter that makes it the most compressible according to the
+/// heuristic suggested by the specification: the smallest sum of the filtered bytes taken as
+/// signed values.
+///
+fn filter_rows(pixels: &[u8], row_size: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity(pixels.len() + pixels.len() / row_size.max(1));
+    let empty_row = vec![0; row_size];
+    let mut candidate = vec![0; row_size];
+    let mut best = vec![0; row_size];
+
+    // Empty canvases have no rows to filter.
+    for (y, row) in pixels.chunks(row_size.max(1)).enumerate() {
+        let previous = match y {
+            0 => &empty_row[..],
+            _ => &pixels[(y - 1) * row_size..y * row_size],
+        };
+        let mut best_filter = 0;
+        let mut best_cost = u64::MAX;
+
+        for filter in 0..5_u8 {
+            for i in 0..row_size {
+                let left = if i >= bytes_per_pixel {
+                    row[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let up_left = if i >= bytes_per_pixel {
+                    previous[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => previous[i],
+                    3 => ((left as u16 + previous[i] as u16) / 2) as u8,
+                    _ => paeth(left, previous[i], up_left),
+                };
+
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+
+            let cost = candidate
+                .iter()
+                .map(|byte| (*byte as i8).unsigned_abs() as u64)
+                .sum::<u64>();
+
+            if cost < best_cost {
+                best_cost = cost;
+                best_filter = filter;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+
+        filtered.push(best_filter);
+        filtered.extend(&best);
+    }
+
+    filtered
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    channels: usize,
+}
+
+impl Header {
+    fn bits_per_pixel(&self) -> usize {
+        self.bit_depth as usize * self.channels
+    }
+
+    fn row_size(&self) -> usize {
+        (self.width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13);
+
+        bytes.extend((self.width as u32).to_be_bytes());
+        bytes.extend((self.height as u32).to_be_bytes());
+        // Compression, filtering and interlacing methods follow.
+        bytes.extend([self.bit_depth, self.color_type, 0, 0, 0]);
+        bytes
+    }
+}
+
+///
+/// Encodes rows of `header.row_size()` bytes tagged as being in `space`, with `chunks` (like
+/// the palette) placed before the image data.
+///
+fn encode(
+    header: &Header,
+    space: RgbSpace,
+    chunks: &[(&[u8; 4], Vec<u8>)],
+    rows: &[u8],
+) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    write_chunk(&mut png, b"IHDR", &header.to_bytes());
+    write_chunk(&mut png, b"cICP", &cicp(space));
+
+    if space == RgbSpace::Srgb {
+        // Perceptual rendering intent
+        write_chunk(&mut png, b"sRGB", &[0]);
+    } else {
+        let mut profile = space.name().as_bytes().to_vec();
+        // Null separator and zlib compression method
+        profile.extend([0, 0]);
+        profile.extend(compress_to_vec_zlib(
+            &space.icc_profile(),
+            COMPRESSION_LEVEL,
+        ));
+        write_chunk(&mut png, b"iCCP", &profile);
+    }
+
+    for (kind, data) in chunks {
+        write_chunk(&mut png, kind, data);
+    }
+
+    // Filters work on whole bytes, pixels smaller than a byte are compared with the previous byte.
+    let bytes_per_pixel = (header.bits_per_pixel() / 8).max(1);
+    let filtered = filter_rows(rows, header.row_size(), bytes_per_pixel);
+    write_chunk(
+        &mut png,
+        b"IDAT",
+        &compress_to_vec_zlib(&filtered, COMPRESSION_LEVEL),
+    );
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+impl Canvas {
+    ///
+    /// Encodes the canvas as an 8-bit RGBA PNG, tagged with `CanvasDescription::color_space`.
+    ///
+    pub fn to_png(&self) -> Vec<u8> {
+        let header = Header {
+            width: self.desc.width,
+            height: self.desc.height,
+            bit_depth: 8,
+            color_type: COLOR_TYPE_RGBA,
+            channels: NUM_CHANNELS,
+        };
+
+        encode(&header, self.desc.color_space, &[], &self.to_u8())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+}
+
+impl IndexedImage {
+    ///
+    /// Encodes the image as an indexed PNG, using the smallest bit depth that fits the palette.
+    ///
+    pub fn to_png(&self) -> Vec<u8> {
+        let bit_depth = match self.palette.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        let header = Header {
+            width: self.width,
+            height: self.height,
+            bit_depth,
+            color_type: COLOR_TYPE_INDEXED,
+            channels: 1,
+        };
+
+        let mut rows = vec![0; header.row_size() * self.height];
+        let pixels_per_byte = 8 / bit_depth as usize;
+
+        for (y, row) in self.indices.chunks(self.width.max(1)).enumerate() {
+            for (x, index) in row.iter().enumerate() {
+                // The first pixel goes in the most significant bits.
+                let shift = (pixels_per_byte - 1 - x % pixels_per_byte) * bit_depth as usize;
+                rows[y * header.row_size() + x / pixels_per_byte] |= index << shift;
+            }
+        }
+
+        let colors = self
+            .palette
+            .iter()
+            .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+            .collect::<Vec<u8>>();
+        let mut chunks = vec![(b"PLTE", colors)];
+        // Entries missing from the end of tRNS are opaque.
+        let num_transparent = self
+            .palette
+            .iter()
+            .rposition(|rgba| rgba[3] != u8::MAX)
+            .map_or(0, |i| i + 1);
+
+        if num_transparent > 0 {
+            let alphas = self.palette[..num_transparent]
+                .iter()
+                .map(|rgba| rgba[3])
+                .collect();
+            chunks.push((b"tRNS", alphas));
+        }
+
+        encode(&header, self.color_space, &chunks, &rows)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+}
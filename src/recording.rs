@@ -0,0 +1,298 @@
+//!
+//! Recording the shapes drawn on a canvas to export them as vector documents. Once
+//! `Canvas::start_recording` is called every `Canvas::draw_shape` is kept as a `DrawCommand`, on
+//! top of being drawn: the path with the transform of the call applied, its paint laid out the
+//! way the renderer lays it out, the fill mode and the blending function. `Recording::to_svg` and
+//! `Recording::to_pdf` write them as documents without rasterizing them.
+//!
+//! Text is recorded as the outlines of its glyphs, also when drawn from a `GlyphAtlas`. Each paint
+//! of a color glyph becomes a command filling its outline, or its innermost clip, with the layers
+//! and their composite modes left out. SVG documents are filled from their coverage without going
+//! through `draw_shape` and aren't recorded.
+//!
+use crate::{
+    canvas::CanvasDescription,
+    color::{Color, FillMode, FillStyle},
+    geometry::{segments, BoundingBox, Path, PathOps, Point, Segment},
+    math::{map_viewbox, Angle},
+    renderer::{blend_func, BlendFunc},
+};
+
+mod pdf;
+mod svg;
+
+///
+/// Numbers rounded to 6 decimals, plenty for pixels, without trailing zeros.
+///
+fn number(value: f64) -> String {
+    format!("{}", (value * 1e6).round() / 1e6 + 0.0)
+}
+
+///
+/// The functions of `blend_func` as values, set with `Canvas::set_blend_mode` so recorded
+/// documents can express the ones they support.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    SourceOver,
+    DestinationOver,
+    SourceOut,
+    DestinationOut,
+    SourceIn,
+    DestinationIn,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    Clear,
+    Source,
+    Destination,
+    Additive,
+}
+
+impl BlendMode {
+    ///
+    /// The function of `blend_func` the mode stands for.
+    ///
+    pub fn func(self) -> BlendFunc {
+        match self {
+            BlendMode::SourceOver => blend_func::source_over,
+            BlendMode::DestinationOver => blend_func::destination_over,
+            BlendMode::SourceOut => blend_func::source_out,
+            BlendMode::DestinationOut => blend_func::destination_out,
+            BlendMode::SourceIn => blend_func::source_in,
+            BlendMode::DestinationIn => blend_func::destination_in,
+            BlendMode::SourceAtop => blend_func::source_atop,
+            BlendMode::DestinationAtop => blend_func::destination_atop,
+            BlendMode::Xor => blend_func::xor,
+            BlendMode::Clear => blend_func::clear,
+            BlendMode::Source => blend_func::source,
+            BlendMode::Destination => blend_func::destination,
+            BlendMode::Additive => blend_func::additive,
+        }
+    }
+}
+
+///
+/// A `FillStyle` with its stops owned and its gradient placed in canvas pixels. The renderer
+/// samples gradients at the corner of pixels, the points here are moved by half a pixel so
+/// sampling at the center of pixels gives the same colors. Like the renderer, gradients pad and
+/// the opacity of their stops is left out.
+///
+#[derive(Debug, Clone)]
+pub enum Paint {
+    Color(Color),
+    ///
+    /// Goes from the first stop at `start` to the last one at `end`.
+    ///
+    LinearGradient {
+        stops: Vec<(Color, f64)>,
+        start: Point,
+        end: Point,
+    },
+    RadialGradient {
+        stops: Vec<(Color, f64)>,
+        center: Point,
+        radius: f64,
+    },
+    ///
+    /// The stops are angles counterclockwise from the right of `center`, mirrored below it.
+    ///
+    ConicGradient {
+        stops: Vec<(Color, Angle)>,
+        center: Point,
+    },
+}
+
+impl Paint {
+    ///
+    /// `fill_style` laid out over `bounds` like the renderer does.
+    ///
+    pub fn new(fill_style: FillStyle, bounds: &BoundingBox) -> Paint {
+        let (min_x, max_x) = (bounds.min_x as f64, bounds.max_x as f64);
+        let (min_y, max_y) = (bounds.min_y as f64, bounds.max_y as f64);
+        let half_width = (max_x - min_x) / 2.0;
+        let center = Point {
+            x: (min_x + max_x) / 2.0 + 0.5,
+            y: (min_y + max_y) / 2.0 + 0.5,
+        };
+        let translated = |translation: Point| Point {
+            x: center.x - translation.x,
+            y: center.y - translation.y,
+        };
+
+        match fill_style {
+            FillStyle::Plain(color) => Paint::Color(color),
+            FillStyle::LinearGradient { stops, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                Paint::LinearGradient {
+                    stops: stops.to_vec(),
+                    start: Point {
+                        x: center.x + half_width * cos,
+                        y: center.y + half_width * sin,
+                    },
+                    end: Point {
+                        x: center.x - half_width * cos,
+                        y: center.y - half_width * sin,
+                    },
+                }
+            }
+            FillStyle::RadialGradient { stops, translation } => Paint::RadialGradient {
+                stops: stops.to_vec(),
+                center: translated(translation),
+                radius: half_width,
+            },
+            FillStyle::ConicGradient { stops, translation } => Paint::ConicGradient {
+                stops: stops.to_vec(),
+                center: translated(translation),
+            },
+        }
+    }
+}
+
+///
+/// A shape drawn with `Canvas::draw_shape`.
+///
+#[derive(Clone)]
+pub struct DrawCommand {
+    ///
+    /// The path in viewbox units, with absolute commands and the transform of the call applied.
+    ///
+    pub path: Vec<PathOps>,
+    pub paint: Paint,
+    pub fill_mode: FillMode,
+    ///
+    /// `None` for functions set with `Canvas::set_blending_function`, which documents can't
+    /// express and write like source over.
+    ///
+    pub blend: Option<BlendMode>,
+    ///
+    /// The pixels the shape is blended over, every one of them goes through the blending
+    /// function, covered or not.
+    ///
+    pub bounds: BoundingBox,
+}
+
+///
+/// The shapes drawn on a canvas since `Canvas::start_recording`, in the order they were drawn.
+///
+#[derive(Clone)]
+pub struct Recording {
+    pub desc: CanvasDescription,
+    pub commands: Vec<DrawCommand>,
+}
+
+impl Recording {
+    pub fn new(desc: CanvasDescription) -> Recording {
+        Recording {
+            desc,
+            commands: vec![],
+        }
+    }
+
+    ///
+    /// How viewbox units map to canvas pixels, as the scale and the offset of both axes.
+    ///
+    pub fn viewbox_transform(&self) -> (Point, Point) {
+        let origin = map_viewbox(&self.desc, &Point { x: 0.0, y: 0.0 });
+        let unit = map_viewbox(&self.desc, &Point { x: 1.0, y: 1.0 });
+
+        (
+            Point {
+                x: unit.x - origin.x,
+                y: unit.y - origin.y,
+            },
+            origin,
+        )
+    }
+
+    ///
+    /// Canvas pixels back to viewbox units.
+    ///
+    #[cfg(feature = "text")]
+    pub(crate) fn device_to_viewbox(&self) -> impl Fn(&Point) -> Point {
+        let (scale, offset) = self.viewbox_transform();
+
+        move |p| Point {
+            x: (p.x - offset.x) / scale.x,
+            y: (p.y - offset.y) / scale.y,
+        }
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        path: Path,
+        fill_style: FillStyle,
+        fill_mode: FillMode,
+        blend: Option<BlendMode>,
+        transform: impl Fn(&Point) -> Point,
+    ) {
+        self.record_laid_out(path, fill_style, None, fill_mode, blend, transform)
+    }
+
+    ///
+    /// Same as `record` with the paint laid out over `paint_bounds`, in canvas pixels, instead of
+    /// the bounds of the path.
+    ///
+    pub(crate) fn record_laid_out(
+        &mut self,
+        path: Path,
+        fill_style: FillStyle,
+        paint_bounds: Option<&BoundingBox>,
+        fill_mode: FillMode,
+        blend: Option<BlendMode>,
+        transform: impl Fn(&Point) -> Point,
+    ) {
+        let mut bounds = BoundingBox::default();
+        let mut update_bounds = |p: &Point| {
+            let p = map_viewbox(&self.desc, p);
+            bounds.min_x = bounds.min_x.min(p.x.floor() as i64);
+            bounds.min_y = bounds.min_y.min(p.y.floor() as i64);
+            bounds.max_x = bounds.max_x.max(p.x.floor() as i64);
+            bounds.max_y = bounds.max_y.max(p.y.floor() as i64);
+        };
+        let path = segments(path, transform)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::MoveTo(p) => {
+                    update_bounds(&p);
+                    PathOps::MoveTo { x: p.x, y: p.y }
+                }
+                Segment::Line(_, p) => {
+                    update_bounds(&p);
+                    PathOps::LineTo { x: p.x, y: p.y }
+                }
+                Segment::Quad(curve) => {
+                    let [_, p1, p2] = curve.points();
+                    [p1, p2].iter().for_each(&mut update_bounds);
+                    PathOps::QuadTo {
+                        x1: p1.x,
+                        y1: p1.y,
+                        x2: p2.x,
+                        y2: p2.y,
+                    }
+                }
+                Segment::Cubic(curve) => {
+                    let [_, p1, p2, p3] = curve.points();
+                    [p1, p2, p3].iter().for_each(&mut update_bounds);
+                    PathOps::CubicTo {
+                        x1: p1.x,
+                        y1: p1.y,
+                        x2: p2.x,
+                        y2: p2.y,
+                        x3: p3.x,
+                        y3: p3.y,
+                    }
+                }
+                Segment::Close(..) => PathOps::Close,
+            })
+            .collect::<Vec<PathOps>>();
+
+        self.commands.push(DrawCommand {
+            path,
+            paint: Paint::new(fill_style, paint_bounds.unwrap_or(&bounds)),
+            fill_mode,
+            blend,
+            bounds,
+        });
+    }
+}
@@ -0,0 +1,474 @@
+//!
+//! Writing a recording as a PDF document of a single page, the size of the canvas with a point
+//! per pixel. Shapes stay vectors: paths become path operators filled with their rule, gradients
+//! become axial and radial shadings clipped to their path, the opacity of colors goes in graphics
+//! states and what blending functions erase goes in soft masks.
+//! See https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf
+//!
+use crate::{
+    color::{Color, FillRule},
+    geometry::{segments, BoundingBox, Path, Point, Segment},
+    math::map_viewbox,
+    recording::{number, BlendMode, DrawCommand, Paint, Recording},
+};
+use std::{fs, io};
+
+// The objects written first, the others are numbered after them.
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+const PAGE: usize = 3;
+const RESOURCES: usize = 4;
+const CONTENTS: usize = 5;
+
+fn rgb(color: &Color) -> String {
+    format!(
+        "{} {} {}",
+        number(color.r.clamp(0.0, 1.0)),
+        number(color.g.clamp(0.0, 1.0)),
+        number(color.b.clamp(0.0, 1.0))
+    )
+}
+
+///
+/// The path operators of `path` with its points mapped by `to_pixels`. PDF has no quadratic
+/// curves, they are raised to cubic ones.
+///
+fn path_operators(path: Path, to_pixels: impl Fn(&Point) -> Point) -> String {
+    let point = |p: &Point| format!("{} {}", number(p.x), number(p.y));
+    let two_thirds = |from: &Point, to: &Point| Point {
+        x: from.x + (to.x - from.x) * 2.0 / 3.0,
+        y: from.y + (to.y - from.y) * 2.0 / 3.0,
+    };
+
+    segments(path, to_pixels)
+        .iter()
+        .map(|segment| match segment {
+            Segment::MoveTo(p) => format!("{} m", point(p)),
+            Segment::Line(_, p) => format!("{} l", point(p)),
+            Segment::Quad(curve) => {
+                let [p0, p1, p2] = curve.points();
+                format!(
+                    "{} {} {} c",
+                    point(&two_thirds(&p0, &p1)),
+                    point(&two_thirds(&p2, &p1)),
+                    point(&p2)
+                )
+            }
+            Segment::Cubic(curve) => {
+                let [_, p1, p2, p3] = curve.points();
+                format!("{} {} {} c", point(&p1), point(&p2), point(&p3))
+            }
+            Segment::Close(..) => "h".to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+///
+/// The colors of `stops` along [0, 1] as a shading function: the first and last colors pad the
+/// ends and consecutive stops are joined by linear interpolations.
+///
+fn stops_function(stops: &[(Color, f64)]) -> String {
+    let mut points = stops
+        .iter()
+        .map(|(color, offset)| (*color, offset.clamp(0.0, 1.0)))
+        .collect::<Vec<(Color, f64)>>();
+
+    for i in 1..points.len() {
+        points[i].1 = points[i].1.max(points[i - 1].1);
+    }
+    if points[0].1 > 0.0 {
+        points.insert(0, (points[0].0, 0.0));
+    }
+    if points[points.len() - 1].1 < 1.0 {
+        points.push((points[points.len() - 1].0, 1.0));
+    }
+
+    let functions = points
+        .windows(2)
+        .map(|pair| {
+            format!(
+                "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+                rgb(&pair[0].0),
+                rgb(&pair[1].0)
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if functions.len() == 1 {
+        return functions[0].clone();
+    }
+
+    let bounds = points[1..points.len() - 1]
+        .iter()
+        .map(|(_, offset)| number(*offset))
+        .collect::<Vec<String>>();
+
+    format!(
+        "<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+        functions.join(" "),
+        bounds.join(" "),
+        vec!["0 1"; functions.len()].join(" ")
+    )
+}
+
+///
+/// A stream object, `entries` being the ones of its dictionary besides its length.
+///
+fn stream(entries: &str, data: &str) -> String {
+    format!(
+        "<< {}/Length {} >>\nstream\n{}\nendstream",
+        entries,
+        data.len(),
+        data
+    )
+}
+
+struct Writer<'a> {
+    recording: &'a Recording,
+    ///
+    /// The body of every object, object `n` being at `n - 1`.
+    ///
+    objects: Vec<String>,
+    ///
+    /// The names and dictionaries of the resources of the page and its forms.
+    ///
+    graphics_states: Vec<(String, String)>,
+    shadings: Vec<(String, String)>,
+    forms: Vec<(String, String)>,
+    content: Vec<String>,
+}
+
+impl Writer<'_> {
+    fn graphics_state(&mut self, dictionary: String) -> String {
+        if let Some((name, _)) = self.graphics_states.iter().find(|(_, d)| *d == dictionary) {
+            return name.clone();
+        }
+
+        let name = format!("/GS{}", self.graphics_states.len());
+        self.graphics_states.push((name.clone(), dictionary));
+        name
+    }
+
+    ///
+    /// The operator setting the fill opacity to `alpha`, nothing for opaque fills.
+    ///
+    fn opacity(&mut self, alpha: f64) -> String {
+        let alpha = alpha.clamp(0.0, 1.0);
+
+        if alpha < 1.0 {
+            format!(
+                "{} gs ",
+                self.graphics_state(format!("<< /ca {} >>", number(alpha)))
+            )
+        } else {
+            String::new()
+        }
+    }
+
+    ///
+    /// Adds a transparency group drawing `content` in the pixels of the canvas, returning its
+    /// name and object number.
+    ///
+    fn form(&mut self, content: &str) -> (String, usize) {
+        let desc = &self.recording.desc;
+        let dictionary = format!(
+            "/Type /XObject /Subtype /Form /BBox [0 0 {} {}] /Group << /S /Transparency /CS /DeviceRGB >> /Resources {} 0 R ",
+            desc.width, desc.height, RESOURCES
+        );
+        self.objects.push(stream(&dictionary, content));
+
+        let (name, id) = (format!("/Fm{}", self.forms.len()), self.objects.len());
+        self.forms.push((name.clone(), format!("{} 0 R", id)));
+        (name, id)
+    }
+
+    fn to_pixels(&self, path: Path) -> String {
+        path_operators(path, |p| map_viewbox(&self.recording.desc, p))
+    }
+
+    fn plain(&mut self, command: &DrawCommand, color: &Color) -> String {
+        let fill = match command.fill_mode.fill_rule {
+            FillRule::NonZero => "f",
+            FillRule::EvenOdd => "f*",
+        };
+
+        let opacity = self.opacity(color.a);
+
+        format!(
+            "q {}{} rg\n{}\n{}\nQ",
+            opacity,
+            rgb(color),
+            self.to_pixels(&command.path),
+            fill
+        )
+    }
+
+    fn shading(&mut self, command: &DrawCommand, shading: String) -> String {
+        let clip = match command.fill_mode.fill_rule {
+            FillRule::NonZero => "W n",
+            FillRule::EvenOdd => "W* n",
+        };
+        let name = format!("/Sh{}", self.shadings.len());
+        self.shadings.push((name.clone(), shading));
+
+        format!(
+            "q\n{}\n{}\n{} sh\nQ",
+            self.to_pixels(&command.path),
+            clip,
+            name
+        )
+    }
+
+    ///
+    /// The operators filling the shape of `command`, nothing when it has no paint.
+    ///
+    fn fill(&mut self, command: &DrawCommand) -> Option<String> {
+        match &command.paint {
+            Paint::Color(color) => Some(self.plain(command, color)),
+            // PDF has no conic shadings.
+            Paint::ConicGradient { stops, .. } => {
+                let color = stops.first()?.0;
+                Some(self.plain(command, &color))
+            }
+            Paint::LinearGradient { stops, .. } | Paint::RadialGradient { stops, .. }
+                if stops.is_empty() =>
+            {
+                None
+            }
+            Paint::LinearGradient { stops, start, end } => {
+                if start.distance_to(end) == 0.0 {
+                    return Some(self.plain(command, &stops[0].0));
+                }
+
+                let shading = format!(
+                    "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] /Function {} /Extend [true true] >>",
+                    number(start.x),
+                    number(start.y),
+                    number(end.x),
+                    number(end.y),
+                    stops_function(stops)
+                );
+                Some(self.shading(command, shading))
+            }
+            Paint::RadialGradient {
+                stops,
+                center,
+                radius,
+            } => {
+                if *radius <= 0.0 {
+                    return Some(self.plain(command, &stops[0].0));
+                }
+
+                let shading = format!(
+                    "<< /ShadingType 3 /ColorSpace /DeviceRGB /Coords [{} {} 0 {} {} {}] /Function {} /Extend [true true] >>",
+                    number(center.x),
+                    number(center.y),
+                    number(center.x),
+                    number(center.y),
+                    number(*radius),
+                    stops_function(stops)
+                );
+                Some(self.shading(command, shading))
+            }
+        }
+    }
+
+    ///
+    /// The shape of `command` filled with `gray` and the opacity the shape has in the pixels it
+    /// covers fully, for soft masks. Gradients are opaque since the renderer leaves out the
+    /// opacity of stops.
+    ///
+    fn mask_fill(&mut self, command: &DrawCommand, gray: f64) -> String {
+        let alpha = match &command.paint {
+            Paint::Color(color) => color.a,
+            _ => 1.0,
+        };
+
+        self.plain(
+            command,
+            &Color {
+                r: gray,
+                g: gray,
+                b: gray,
+                a: alpha,
+            },
+        )
+    }
+
+    fn rect(bounds: &BoundingBox, gray: f64) -> String {
+        format!(
+            "q {} g {} {} {} {} re f Q",
+            number(gray),
+            bounds.min_x,
+            bounds.min_y,
+            bounds.max_x - bounds.min_x + 1,
+            bounds.max_y - bounds.min_y + 1
+        )
+    }
+
+    ///
+    /// Masks everything drawn so far with a luminosity soft mask, `operators` being drawn over
+    /// white.
+    ///
+    fn mask(&mut self, operators: &[String]) {
+        let desc = &self.recording.desc;
+        let mask = format!(
+            "1 g 0 0 {} {} re f\n{}",
+            desc.width,
+            desc.height,
+            operators.join("\n")
+        );
+        let content = self.content.join("\n");
+        let (_, mask_id) = self.form(&mask);
+        let (content, _) = self.form(&content);
+        let state = self.graphics_state(format!(
+            "<< /SMask << /Type /Mask /S /Luminosity /G {} 0 R >> >>",
+            mask_id
+        ));
+
+        self.content = vec![format!("q {} gs {} Do Q", state, content)];
+    }
+
+    fn draw(&mut self, command: &DrawCommand) {
+        match command.blend {
+            Some(BlendMode::Destination) => {}
+            Some(BlendMode::DestinationOver) => {
+                if let Some(fill) = self.fill(command) {
+                    self.content.insert(0, fill);
+                }
+            }
+            Some(BlendMode::DestinationOut) => {
+                let mask = [self.mask_fill(command, 0.0)];
+                self.mask(&mask);
+            }
+            Some(BlendMode::DestinationIn) => {
+                let mask = [
+                    Writer::rect(&command.bounds, 0.0),
+                    self.mask_fill(command, 1.0),
+                ];
+                self.mask(&mask);
+            }
+            Some(BlendMode::Clear) => self.mask(&[Writer::rect(&command.bounds, 0.0)]),
+            Some(BlendMode::Source) => {
+                self.mask(&[Writer::rect(&command.bounds, 0.0)]);
+
+                let fill = self.fill(command);
+                self.content.extend(fill);
+            }
+            _ => {
+                let fill = self.fill(command);
+                self.content.extend(fill);
+            }
+        }
+    }
+
+    fn resources(&self) -> String {
+        let dictionary = |key: &str, entries: &[(String, String)]| {
+            if entries.is_empty() {
+                return String::new();
+            }
+
+            let entries = entries
+                .iter()
+                .map(|(name, value)| format!("{} {}", name, value))
+                .collect::<Vec<String>>();
+            format!(" /{} << {} >>", key, entries.join(" "))
+        };
+
+        format!(
+            "<<{}{}{} >>",
+            dictionary("ExtGState", &self.graphics_states),
+            dictionary("Shading", &self.shadings),
+            dictionary("XObject", &self.forms)
+        )
+    }
+}
+
+impl Recording {
+    ///
+    /// The recorded shapes as a PDF document, on top of the background color of the canvas.
+    /// Colors are written in sRGB as `DeviceRGB` whatever the compositing and color space of the
+    /// canvas.
+    ///
+    /// Blending functions are mapped like `Recording::to_svg` does: `destination_over` draws
+    /// behind the shapes drawn before, `destination` draws nothing, and `destination_out`,
+    /// `destination_in`, `clear` and `source` mask what's been drawn so far with a soft mask.
+    /// The others, `additive` included since PDF has no such blend mode, and custom functions
+    /// are drawn normally. Conic gradients have no PDF shading and are drawn with the color of
+    /// their first stop.
+    ///
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let desc = &self.desc;
+        let mut writer = Writer {
+            recording: self,
+            objects: vec![String::new(); CONTENTS],
+            graphics_states: vec![],
+            shadings: vec![],
+            forms: vec![],
+            content: vec![],
+        };
+
+        if desc.background_color.a > 0.0 {
+            let background = format!(
+                "q {}{} rg 0 0 {} {} re f Q",
+                writer.opacity(desc.background_color.a),
+                rgb(&desc.background_color),
+                desc.width,
+                desc.height
+            );
+            writer.content.push(background);
+        }
+        for command in &self.commands {
+            if !command.bounds.is_empty() {
+                writer.draw(command);
+            }
+        }
+
+        // The y axis of PDF goes up, the one of the canvas goes down.
+        let contents = format!(
+            "1 0 0 -1 0 {} cm\n{}",
+            desc.height,
+            writer.content.join("\n")
+        );
+
+        writer.objects[CATALOG - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES);
+        writer.objects[PAGES - 1] = format!("<< /Type /Pages /Kids [{} 0 R] /Count 1 >>", PAGE);
+        writer.objects[PAGE - 1] = format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} 0 R /Contents {} 0 R /Group << /S /Transparency /CS /DeviceRGB >> >>",
+            PAGES, desc.width, desc.height, RESOURCES, CONTENTS
+        );
+        writer.objects[RESOURCES - 1] = writer.resources();
+        writer.objects[CONTENTS - 1] = stream("", &contents);
+
+        // The binary comment tells tools the file isn't plain text.
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = vec![];
+
+        for (i, object) in writer.objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+                writer.objects.len() + 1,
+                CATALOG,
+                xref
+            )
+            .bytes(),
+        );
+
+        pdf
+    }
+
+    pub fn save_pdf(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        fs::write(path, self.to_pdf())
+    }
+}
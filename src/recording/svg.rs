@@ -0,0 +1,370 @@
+//!
+//! Writing a recording as an SVG document. The paths keep their viewbox units and go through the
+//! transform of the viewbox, gradients and the pixels blending functions touch are placed in
+//! canvas pixels.
+//!
+use crate::{
+    color::{Antialiasing, Color, FillRule},
+    geometry::{BoundingBox, Path, PathOps},
+    recording::{number, BlendMode, DrawCommand, Paint, Recording},
+};
+
+fn color(color: &Color) -> String {
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+fn path_data(path: Path) -> String {
+    let n = number;
+
+    path.iter()
+        .map(|op| match *op {
+            PathOps::MoveTo { x, y } => format!("M{} {}", n(x), n(y)),
+            PathOps::MoveToRel { x, y } => format!("m{} {}", n(x), n(y)),
+            PathOps::LineTo { x, y } => format!("L{} {}", n(x), n(y)),
+            PathOps::LineToRel { x, y } => format!("l{} {}", n(x), n(y)),
+            PathOps::QuadTo { x1, y1, x2, y2 } => {
+                format!("Q{} {} {} {}", n(x1), n(y1), n(x2), n(y2))
+            }
+            PathOps::QuadToRel { x1, y1, x2, y2 } => {
+                format!("q{} {} {} {}", n(x1), n(y1), n(x2), n(y2))
+            }
+            PathOps::CubicTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            } => format!(
+                "C{} {} {} {} {} {}",
+                n(x1),
+                n(y1),
+                n(x2),
+                n(y2),
+                n(x3),
+                n(y3)
+            ),
+            PathOps::CubicToRel {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            } => format!(
+                "c{} {} {} {} {} {}",
+                n(x1),
+                n(y1),
+                n(x2),
+                n(y2),
+                n(x3),
+                n(y3)
+            ),
+            PathOps::Close => "Z".to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn stops(stops: &[(Color, f64)]) -> String {
+    stops
+        .iter()
+        .map(|(c, offset)| {
+            format!(
+                r#"<stop offset="{}" stop-color="{}"/>"#,
+                number(*offset),
+                color(c)
+            )
+        })
+        .collect()
+}
+
+struct Writer<'a> {
+    recording: &'a Recording,
+    ///
+    /// The `transform` attribute of paths, empty when viewbox units are pixels.
+    ///
+    to_pixels: String,
+    ///
+    /// The `gradientTransform` attribute of gradients, the inverse of `to_pixels`.
+    ///
+    from_pixels: String,
+    defs: Vec<String>,
+    content: Vec<String>,
+}
+
+impl Writer<'_> {
+    fn id(&self, prefix: &str) -> String {
+        format!("{}{}", prefix, self.defs.len())
+    }
+
+    ///
+    /// The fill attributes of `paint`, gradients are added to the definitions.
+    ///
+    fn fill(&mut self, paint: &Paint) -> String {
+        let plain = |c: &Color| {
+            let opacity = c.a.clamp(0.0, 1.0);
+            if opacity < 1.0 {
+                format!(r#" fill="{}" fill-opacity="{}""#, color(c), number(opacity))
+            } else {
+                format!(r#" fill="{}""#, color(c))
+            }
+        };
+
+        match paint {
+            Paint::Color(c) => plain(c),
+            // SVG has no conic gradients.
+            Paint::ConicGradient { stops: s, .. } => s
+                .first()
+                .map_or(r#" fill="none""#.to_string(), |stop| plain(&stop.0)),
+            Paint::LinearGradient { stops: s, .. } | Paint::RadialGradient { stops: s, .. }
+                if s.is_empty() =>
+            {
+                r#" fill="none""#.to_string()
+            }
+            Paint::LinearGradient {
+                stops: s,
+                start,
+                end,
+            } => {
+                if start.distance_to(end) == 0.0 {
+                    return plain(&s[0].0);
+                }
+
+                let id = self.id("gradient");
+                self.defs.push(format!(
+                    r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}"{}>{}</linearGradient>"#,
+                    id,
+                    number(start.x),
+                    number(start.y),
+                    number(end.x),
+                    number(end.y),
+                    self.from_pixels,
+                    stops(s)
+                ));
+                format!(r#" fill="url(#{})""#, id)
+            }
+            Paint::RadialGradient {
+                stops: s,
+                center,
+                radius,
+            } => {
+                if *radius <= 0.0 {
+                    return plain(&s[0].0);
+                }
+
+                let id = self.id("gradient");
+                self.defs.push(format!(
+                    r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}"{}>{}</radialGradient>"#,
+                    id,
+                    number(center.x),
+                    number(center.y),
+                    number(*radius),
+                    self.from_pixels,
+                    stops(s)
+                ));
+                format!(r#" fill="url(#{})""#, id)
+            }
+        }
+    }
+
+    fn path(&self, command: &DrawCommand, fill: &str, extra: &str) -> String {
+        let fill_rule = match command.fill_mode.fill_rule {
+            FillRule::EvenOdd => r#" fill-rule="evenodd""#,
+            FillRule::NonZero => "",
+        };
+        let rendering = match command.fill_mode.antialiasing {
+            Antialiasing::None => r#" shape-rendering="crispEdges""#,
+            _ => "",
+        };
+
+        format!(
+            r#"<path{} d="{}"{}{}{}{}/>"#,
+            self.to_pixels,
+            path_data(&command.path),
+            fill,
+            fill_rule,
+            rendering,
+            extra
+        )
+    }
+
+    ///
+    /// `<path>` filled with `color` and the opacity the shape has in the pixels it covers
+    /// fully, for masks. Gradients are opaque since the renderer leaves out the opacity of stops.
+    ///
+    fn mask_path(&self, command: &DrawCommand, color: &str) -> String {
+        let opacity = match &command.paint {
+            Paint::Color(c) => c.a.clamp(0.0, 1.0),
+            _ => 1.0,
+        };
+
+        self.path(
+            command,
+            &format!(r#" fill="{}" fill-opacity="{}""#, color, number(opacity)),
+            "",
+        )
+    }
+
+    fn rect(&self, bounds: &BoundingBox, fill: &str) -> String {
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            bounds.min_x,
+            bounds.min_y,
+            bounds.max_x - bounds.min_x + 1,
+            bounds.max_y - bounds.min_y + 1,
+            fill
+        )
+    }
+
+    ///
+    /// Masks everything drawn so far, `elements` being drawn over a white mask.
+    ///
+    fn mask(&mut self, elements: &[String]) {
+        let desc = &self.recording.desc;
+        let id = self.id("mask");
+
+        self.defs.push(format!(
+            r#"<mask id="{}" maskUnits="userSpaceOnUse" x="0" y="0" width="{}" height="{}"><rect width="{}" height="{}" fill="white"/>{}</mask>"#,
+            id,
+            desc.width,
+            desc.height,
+            desc.width,
+            desc.height,
+            elements.concat()
+        ));
+        self.content = vec![format!(
+            "<g mask=\"url(#{})\">\n{}\n</g>",
+            id,
+            self.content.join("\n")
+        )];
+    }
+
+    fn draw(&mut self, command: &DrawCommand) {
+        match command.blend {
+            Some(BlendMode::Destination) => {}
+            Some(BlendMode::DestinationOver) => {
+                let fill = self.fill(&command.paint);
+                let path = self.path(command, &fill, "");
+                self.content.insert(0, path);
+            }
+            Some(BlendMode::Additive) => {
+                let fill = self.fill(&command.paint);
+                let path = self.path(command, &fill, r#" style="mix-blend-mode:plus-lighter""#);
+                self.content.push(path);
+            }
+            Some(BlendMode::DestinationOut) => {
+                let mask = [self.mask_path(command, "black")];
+                self.mask(&mask);
+            }
+            Some(BlendMode::DestinationIn) => {
+                let mask = [
+                    self.rect(&command.bounds, "black"),
+                    self.mask_path(command, "white"),
+                ];
+                self.mask(&mask);
+            }
+            Some(BlendMode::Clear) => {
+                let mask = [self.rect(&command.bounds, "black")];
+                self.mask(&mask);
+            }
+            Some(BlendMode::Source) => {
+                let mask = [self.rect(&command.bounds, "black")];
+                self.mask(&mask);
+
+                let fill = self.fill(&command.paint);
+                let path = self.path(command, &fill, "");
+                self.content.push(path);
+            }
+            _ => {
+                let fill = self.fill(&command.paint);
+                let path = self.path(command, &fill, "");
+                self.content.push(path);
+            }
+        }
+    }
+}
+
+impl Recording {
+    ///
+    /// The recorded shapes as an SVG document the size of the canvas, on top of its background
+    /// color. Colors are written in sRGB whatever the compositing and color space of the canvas.
+    ///
+    /// Blending functions are mapped to what SVG can express: `destination_over` draws behind
+    /// the shapes drawn before, `additive` uses the `plus-lighter` blend mode, `destination`
+    /// draws nothing, and `destination_out`, `destination_in`, `clear` and `source` mask what's
+    /// been drawn so far. The others, which need the opacity of what's under the shape, and
+    /// custom functions are drawn like `source_over`. Conic gradients have no SVG equivalent and
+    /// are drawn with the color of their first stop.
+    ///
+    pub fn to_svg(&self) -> String {
+        let (scale, offset) = self.viewbox_transform();
+        let identity = scale.x == 1.0 && scale.y == 1.0 && offset.x == 0.0 && offset.y == 0.0;
+        let (to_pixels, from_pixels) = if identity {
+            (String::new(), String::new())
+        } else {
+            (
+                format!(
+                    r#" transform="matrix({} 0 0 {} {} {})""#,
+                    number(scale.x),
+                    number(scale.y),
+                    number(offset.x),
+                    number(offset.y)
+                ),
+                format!(
+                    r#" gradientTransform="matrix({} 0 0 {} {} {})""#,
+                    number(1.0 / scale.x),
+                    number(1.0 / scale.y),
+                    number(-offset.x / scale.x),
+                    number(-offset.y / scale.y)
+                ),
+            )
+        };
+        let desc = &self.desc;
+        let mut writer = Writer {
+            recording: self,
+            to_pixels,
+            from_pixels,
+            defs: vec![],
+            content: vec![],
+        };
+
+        if desc.background_color.a > 0.0 {
+            let background = Paint::Color(desc.background_color);
+            let fill = writer.fill(&background);
+            writer.content.push(format!(
+                r#"<rect width="{}" height="{}"{}/>"#,
+                desc.width, desc.height, fill
+            ));
+        }
+
+        for command in &self.commands {
+            if !command.bounds.is_empty() {
+                writer.draw(command);
+            }
+        }
+
+        let mut result = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            desc.width, desc.height, desc.width, desc.height
+        );
+
+        if !writer.defs.is_empty() {
+            result += &format!("<defs>\n{}\n</defs>\n", writer.defs.join("\n"));
+        }
+        for element in &writer.content {
+            result += element;
+            result += "\n";
+        }
+        result += "</svg>\n";
+
+        result
+    }
+}
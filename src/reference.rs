@@ -0,0 +1,236 @@
+//!
+//! A slow rasterizer that samples every pixel on a regular grid instead of accumulating areas.
+//! It's only meant to check the output of `Canvas::draw_shape`, see `ImageDiff`.
+//!
+use crate::{
+    canvas::Canvas,
+    color::{clamp, FillRule, FillStyle},
+    geometry::{segments, BoundingBox, Path, Point, Segment},
+    math::map_viewbox,
+    renderer::{blend_pixel, decode_fill_style, get_color_at, DecodedStops, NUM_CHANNELS},
+};
+use std::cmp::Ordering;
+use std::fmt;
+
+///
+/// Each pixel is sampled `SAMPLES_PER_AXIS * SAMPLES_PER_AXIS` times, which bounds the error
+/// of a pixel crossed by a single edge to `1 / SAMPLES_PER_AXIS`.
+///
+pub const SAMPLES_PER_AXIS: usize = 16;
+
+///
+/// Curves are flattened way below what's visible, see `QuadraticBezier::subdivide` and
+/// `CubicBezier::subdivide` for the meaning of the values.
+///
+const QUADRATIC_TOLERANCE: f64 = 0.005;
+const CUBIC_TOLERANCE: f64 = 16.0 * 0.005 * 0.005;
+
+///
+/// Equivalent of `Canvas::draw_shape`: the shape has the same bounds, colors and is blended with
+/// the canvas' blending function, only the coverage of the pixels is computed differently.
+///
+/// Like `Canvas::draw_shape` subpaths are not closed implicitly.
+///
+pub fn draw_shape(
+    canvas: &mut Canvas,
+    path: Path,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    transform: impl Fn(&Point) -> Point,
+) {
+    let desc = canvas.desc;
+    let mut edges = Vec::<(Point, Point)>::new();
+    let mut bounds = BoundingBox::default();
+    let mut bottom = f64::MIN;
+    let mut update_bounds = |p: &Point| {
+        bounds.min_x = i64::min(bounds.min_x, p.x.floor() as i64);
+        bounds.min_y = i64::min(bounds.min_y, p.y.floor() as i64);
+        bounds.max_x = i64::max(bounds.max_x, p.x.floor() as i64);
+        bounds.max_y = i64::max(bounds.max_y, p.y.floor() as i64);
+    };
+
+    for segment in segments(path, |p| map_viewbox(&desc, &transform(p))) {
+        let points = match segment {
+            Segment::MoveTo(p) => {
+                update_bounds(&p);
+                continue;
+            }
+            Segment::Line(p0, p1) => {
+                update_bounds(&p1);
+                vec![p0, p1]
+            }
+            Segment::Close(p0, p1) => vec![p0, p1],
+            Segment::Quad(curve) => {
+                curve.points().iter().for_each(&mut update_bounds);
+                curve
+                    .subdivide(QUADRATIC_TOLERANCE)
+                    .iter()
+                    .map(|t| curve.eval(*t))
+                    .collect()
+            }
+            Segment::Cubic(curve) => {
+                curve.points().iter().for_each(&mut update_bounds);
+                curve.subdivide(CUBIC_TOLERANCE)
+            }
+        };
+
+        for line in points.windows(2) {
+            bottom = bottom.max(line[0].y).max(line[1].y);
+            edges.push((line[0], line[1]));
+        }
+    }
+
+    let (width, height) = (desc.width as i64, desc.height as i64);
+
+    if width == 0
+        || height == 0
+        || bounds.is_empty()
+        || bounds.max_y < 0
+        || bounds.min_y >= height
+        || bounds.max_x < 0
+        || bounds.min_x >= width
+    {
+        return;
+    }
+
+    let min_x = clamp(bounds.min_x, 0, width - 1) as usize;
+    let max_x = clamp(bounds.max_x, 0, width - 1) as usize;
+    let min_y = bounds.min_y.max(0) as usize;
+    let mut max_y = bounds.max_y.min(height - 1) as usize;
+
+    // Same as `fill_path`, a shape ending exactly on a row boundary doesn't cover that row.
+    if max_y > min_y && bottom <= max_y as f64 {
+        max_y -= 1;
+    }
+
+    let inside = |winding: i32| match fill_rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    };
+    let mut decoded_stops = DecodedStops::default();
+    let fill_style = decode_fill_style(fill_style, &desc, &mut decoded_stops);
+    let step = 1.0 / SAMPLES_PER_AXIS as f64;
+    let mut coverage = vec![0_usize; max_x - min_x + 1];
+    let mut crossings = Vec::<(f64, i32)>::with_capacity(edges.len());
+
+    for y in min_y..=max_y {
+        coverage.iter_mut().for_each(|c| *c = 0);
+
+        for sample_y in 0..SAMPLES_PER_AXIS {
+            let sy = y as f64 + (sample_y as f64 + 0.5) * step;
+
+            crossings.clear();
+            crossings.extend(
+                edges
+                    .iter()
+                    .filter(|(p0, p1)| (p0.y <= sy) != (p1.y <= sy))
+                    .map(|(p0, p1)| {
+                        let x = p0.x + (sy - p0.y) * (p1.x - p0.x) / (p1.y - p0.y);
+                        (x, if p0.y < p1.y { 1 } else { -1 })
+                    }),
+            );
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+            let mut winding = 0;
+            let mut next = 0;
+
+            for (i, c) in coverage.iter_mut().enumerate() {
+                for sample_x in 0..SAMPLES_PER_AXIS {
+                    let sx = (min_x + i) as f64 + (sample_x as f64 + 0.5) * step;
+
+                    while next < crossings.len() && crossings[next].0 < sx {
+                        winding += crossings[next].1;
+                        next += 1;
+                    }
+
+                    *c += inside(winding) as usize;
+                }
+            }
+        }
+
+        for (i, c) in coverage.iter().enumerate() {
+            let x = min_x + i;
+            let alpha = *c as f32 / (SAMPLES_PER_AXIS * SAMPLES_PER_AXIS) as f32;
+            let src = get_color_at(x, y, &bounds, fill_style, alpha);
+            let pixel_offset = (y * desc.width + x) * NUM_CHANNELS;
+
+            blend_pixel(&mut canvas.buffer[pixel_offset..], &src, canvas.blend);
+        }
+    }
+}
+
+///
+/// Per channel difference between two canvases of the same size.
+///
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDiff {
+    pub max: [f64; NUM_CHANNELS],
+    pub mean: [f64; NUM_CHANNELS],
+}
+
+impl ImageDiff {
+    pub fn between(expected: &Canvas, actual: &Canvas) -> ImageDiff {
+        assert_eq!(
+            (expected.desc.width, expected.desc.height),
+            (actual.desc.width, actual.desc.height),
+            "Can't compare canvases of different sizes"
+        );
+
+        let mut result = ImageDiff {
+            max: [0.0; NUM_CHANNELS],
+            mean: [0.0; NUM_CHANNELS],
+        };
+        let pixels = expected.buffer.chunks(NUM_CHANNELS);
+
+        for (expected, actual) in pixels.zip(actual.buffer.chunks(NUM_CHANNELS)) {
+            for channel in 0..NUM_CHANNELS {
+                let error = (expected[channel] - actual[channel]).abs();
+
+                result.max[channel] = result.max[channel].max(error);
+                result.mean[channel] += error;
+            }
+        }
+
+        let num_pixels = (expected.desc.width * expected.desc.height).max(1) as f64;
+        result.mean.iter_mut().for_each(|mean| *mean /= num_pixels);
+
+        result
+    }
+
+    pub fn max_error(&self) -> f64 {
+        self.max.iter().cloned().fold(0.0, f64::max)
+    }
+
+    ///
+    /// The average error of the channel that differs the most on average.
+    ///
+    pub fn max_channel_mean_error(&self) -> f64 {
+        self.mean.iter().cloned().fold(0.0, f64::max)
+    }
+
+    ///
+    /// Whether no channel differs by more than `max` and the average error of every channel is
+    /// at most `mean`.
+    ///
+    pub fn is_within(&self, max: f64, mean: f64) -> bool {
+        self.max_error() <= max && self.max_channel_mean_error() <= mean
+    }
+}
+
+impl fmt::Display for ImageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max error (r: {:.4}, g: {:.4}, b: {:.4}, a: {:.4}), \
+             mean error (r: {:.6}, g: {:.6}, b: {:.6}, a: {:.6})",
+            self.max[0],
+            self.max[1],
+            self.max[2],
+            self.max[3],
+            self.mean[0],
+            self.mean[1],
+            self.mean[2],
+            self.mean[3],
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    canvas::{AccumulationCell, Canvas},
-    color::{clamp, Color, FillRule, FillStyle},
-    geometry::{BoundingBox, CubicBezier, Path, PathOps, Point, QuadraticBezier},
+    canvas::{AccumulationCell, Canvas, CanvasDescription, Compositing, CurveRendering},
+    color::{clamp, Color, FillRule, FillStyle, RgbSpace, SubpixelOrder},
+    geometry::{segments, BoundingBox, CubicBezier, Path, Point, QuadraticBezier, Segment},
     math::{map_viewbox, rotate_around, translate, Angle},
 };
 use std::cmp::Ordering;
@@ -134,124 +134,211 @@ pub mod blend_func {
 
 pub const NUM_CHANNELS: usize = 4;
 
-fn update_cell(area: f32, cell: &mut AccumulationCell, id: i32) {
-    cell.area += area;
-    cell.id = id;
+///
+/// What the rasterizer needs to accumulate the area of a path: the description of the canvas it
+/// renders to and its accumulation buffer.
+///
+pub struct RenderState<'a> {
+    pub desc: &'a CanvasDescription,
+    pub accumulation_buffer: &'a mut [AccumulationCell],
 }
 
-pub struct RenderState<'a> {
-    pub canvas: &'a mut Canvas,
-    pub id: i32,
+///
+/// Adds `area` to the cell at (`row`, `col`), taking care of cells that are outside the canvas:
+/// cells to the left are folded into the first column so that their contribution still reaches
+/// the pixels to their right, cells to the right or above/below the canvas can't affect any
+/// visible pixel and are dropped.
+///
+fn accumulate(state: &mut RenderState, row: i64, col: i64, area: f64) {
+    let (width, height) = (state.desc.width as i64, state.desc.height as i64);
+
+    if row < 0 || row >= height || col >= width {
+        return;
+    }
+
+    let index = (row * width + i64::max(col, 0)) as usize;
+    state.accumulation_buffer[index].area += area as f32;
 }
 
 ///
 /// Line drawing algorithm taken from here:
 /// - https://medium.com/@raphlinus/inside-the-fastest-font-renderer-in-the-world-75ae5270c445
 ///
-/// id: A number that should differentiate dfferent segments that are part of the same `Path`.
+/// The line is clipped to the canvas first: the parts to the left of the canvas are projected
+/// onto its left side so that they still contribute to the winding of the pixels to their right,
+/// the parts to the right of the canvas are dropped.
 ///
 pub fn draw_line(state: &mut RenderState, start: &Point, end: &Point) {
-    let p0 = start;
-    let p1 = end;
-    let (width, height) = (state.canvas.desc.width, state.canvas.desc.height);
-    let accumulation_buffer = &mut state.canvas.accumulation_buffer;
-    let id = state.id;
+    if (start.y - end.y).abs() <= f64::EPSILON {
+        return;
+    }
+
+    let width = state.desc.width as f64;
+    let mut splits = vec![0.0_f64, 1.0];
+
+    for side in [0.0, width] {
+        let t = (side - start.x) / (end.x - start.x);
+
+        if t > 0.0 && t < 1.0 {
+            splits.push(t);
+        }
+    }
+
+    splits.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
 
-    if (p0.y - p1.y).abs() <= f64::EPSILON {
+    let lerp = |t: f64| Point {
+        x: start.x + (end.x - start.x) * t,
+        y: start.y + (end.y - start.y) * t,
+    };
+
+    for range in splits.windows(2) {
+        let (a, b) = (lerp(range[0]), lerp(range[1]));
+        let middle = 0.5 * (a.x + b.x);
+
+        if middle >= width {
+            continue;
+        } else if middle < 0.0 {
+            draw_clipped_line(state, &Point { x: 0.0, y: a.y }, &Point { x: 0.0, y: b.y });
+        } else {
+            draw_clipped_line(state, &a, &b);
+        }
+    }
+}
+
+///
+/// Draws a line which doesn't extend horizontally past the canvas.
+///
+fn draw_clipped_line(state: &mut RenderState, start: &Point, end: &Point) {
+    let height = state.desc.height as f64;
+
+    if (start.y - end.y).abs() <= f64::EPSILON {
         return;
     }
-    let (dir, p0, p1) = if p0.y < p1.y {
-        (1.0, p0, p1)
+    let (dir, p0, p1) = if start.y < end.y {
+        (1.0, start, end)
     } else {
-        (-1.0, p1, p0)
+        (-1.0, end, start)
     };
     let dxdy = (p1.x - p0.x) / (p1.y - p0.y);
-    let mut x = p0.x;
-    let y0 = p0.y as usize;
+    let (y_start, y_end) = (p0.y.max(0.0), p1.y.min(height));
+
+    if y_start >= y_end {
+        return;
+    }
 
-    for y in y0..height.min(p1.y.ceil() as usize) {
-        let linestart = y * width;
+    let mut x = p0.x + dxdy * (y_start - p0.y);
+
+    for y in (y_start as i64)..(y_end.ceil() as i64) {
         let dy = ((y + 1) as f64).min(p1.y) - (y as f64).max(p0.y);
         let xnext = x + dxdy * dy;
         let d = dy * dir;
         let (x0, x1) = if x < xnext { (x, xnext) } else { (xnext, x) };
         let x0floor = x0.floor();
-        let x0i = x0floor as i32;
+        let x0i = x0floor as i64;
         let x1ceil = x1.ceil();
-        let x1i = x1ceil as i32;
+        let x1i = x1ceil as i64;
         if x1i <= x0i + 1 {
             let xmf = 0.5 * (x + xnext) - x0floor;
-            let linestart_x0i = linestart as isize + x0i as isize;
-            if linestart_x0i < 0 {
-                continue; // oob index
-            }
-            update_cell(
-                (d - d * xmf) as f32,
-                &mut accumulation_buffer[linestart_x0i as usize],
-                id,
-            );
-            update_cell(
-                (d * xmf) as f32,
-                &mut accumulation_buffer[linestart_x0i as usize + 1],
-                id,
-            );
+            accumulate(state, y, x0i, d - d * xmf);
+            accumulate(state, y, x0i + 1, d * xmf);
         } else {
             let s = (x1 - x0).recip();
             let x0f = x0 - x0floor;
             let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
             let x1f = x1 - x1ceil + 1.0;
             let am = 0.5 * s * x1f * x1f;
-            let linestart_x0i = linestart as isize + x0i as isize;
-            if linestart_x0i < 0 {
-                continue; // oob index
-            }
-            update_cell(
-                (d * a0) as f32,
-                &mut accumulation_buffer[linestart_x0i as usize],
-                id,
-            );
+            accumulate(state, y, x0i, d * a0);
 
             if x1i == x0i + 2 {
-                update_cell(
-                    (d * (1.0 - a0 - am)) as f32,
-                    &mut accumulation_buffer[linestart_x0i as usize + 1],
-                    id,
-                );
+                accumulate(state, y, x0i + 1, d * (1.0 - a0 - am));
             } else {
                 let a1 = s * (1.5 - x0f);
-                update_cell(
-                    (d * (a1 - a0)) as f32,
-                    &mut accumulation_buffer[linestart_x0i as usize + 1],
-                    id,
-                );
+                accumulate(state, y, x0i + 1, d * (a1 - a0));
 
                 for xi in x0i + 2..x1i - 1 {
-                    update_cell(
-                        (d * s) as f32,
-                        &mut accumulation_buffer[linestart + xi as usize],
-                        id,
-                    );
+                    accumulate(state, y, xi, d * s);
                 }
                 let a2 = a1 + (x1i - x0i - 3) as f64 * s;
-                update_cell(
-                    (d * (1.0 - a2 - am)) as f32,
-                    &mut accumulation_buffer[linestart + (x1i - 1) as usize],
-                    id,
-                );
+                accumulate(state, y, x1i - 1, d * (1.0 - a2 - am));
             }
-            update_cell(
-                (d * am) as f32,
-                &mut accumulation_buffer[linestart + x1i as usize],
-                id,
-            );
+            accumulate(state, y, x1i, d * am);
         }
         x = xnext;
     }
 }
 
+///
+/// Computes the exact area covered by a quadratic curve. The curve is split wherever it changes
+/// direction or crosses a pixel boundary, each resulting piece being fully contained inside a
+/// single cell. The piece contributes `∫(x - left side of the cell) dy` to the cell to its
+/// right and the rest of its height to the cell it's in, the same way `draw_line` does it for
+/// straight lines.
+///
+fn draw_quad_bezier_analytic(state: &mut RenderState, curve: &QuadraticBezier) {
+    let mut monotonic = vec![0.0_f64];
+    monotonic.extend(curve.extrema());
+    monotonic.push(1.0);
+    monotonic.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let mut splits = monotonic.clone();
+
+    for range in monotonic.windows(2) {
+        let (t0, t1) = (range[0], range[1]);
+        let (start, end) = (curve.eval(t0), curve.eval(t1));
+
+        for (vertical, a, b) in [(false, start.x, end.x), (true, start.y, end.y)] {
+            let (low, high) = if a < b { (a, b) } else { (b, a) };
+            let mut boundary = low.floor() + 1.0;
+
+            while boundary < high {
+                splits.push(curve.solve_monotonic(vertical, boundary, t0, t1));
+                boundary += 1.0;
+            }
+        }
+    }
+
+    splits.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    for range in splits.windows(2) {
+        if range[1] - range[0] <= f64::EPSILON {
+            continue;
+        }
+
+        let piece = curve.subsegment(range[0], range[1]);
+        let [start, _, end] = piece.points();
+        let dy = end.y - start.y;
+
+        if dy == 0.0 {
+            continue;
+        }
+
+        let middle = curve.eval(0.5 * (range[0] + range[1]));
+        let (row, col) = (middle.y.floor(), middle.x.floor());
+        let area = piece.area_from(col);
+
+        if col < 0.0 {
+            accumulate(state, row as i64, 0, dy);
+        } else {
+            accumulate(state, row as i64, col as i64, dy - area);
+            accumulate(state, row as i64, col as i64 + 1, area);
+        }
+    }
+}
+
+///
+/// Maximum distance in pixels between a cubic curve and the quadratic curves approximating it
+/// when using `CurveRendering::Analytic`.
+///
+const ANALYTIC_CUBIC_TOLERANCE: f64 = 1.0 / 64.0;
+
 pub fn draw_quad_bezier(state: &mut RenderState, curve: &QuadraticBezier) {
+    if state.desc.curve_rendering == CurveRendering::Analytic {
+        draw_quad_bezier_analytic(state, curve);
+        return;
+    }
+
     let points = curve
-        .subdivide(state.canvas.desc.tolerance)
+        .subdivide(state.desc.tolerance)
         .iter()
         .map(|t: &f64| curve.eval(*t))
         .collect::<Vec<Point>>();
@@ -262,7 +349,15 @@ pub fn draw_quad_bezier(state: &mut RenderState, curve: &QuadraticBezier) {
 }
 
 pub fn draw_cubic_bezier(state: &mut RenderState, curve: &CubicBezier) {
-    let points = curve.subdivide(state.canvas.desc.tolerance);
+    if state.desc.curve_rendering == CurveRendering::Analytic {
+        for quad in curve.to_quadratics(ANALYTIC_CUBIC_TOLERANCE) {
+            draw_quad_bezier_analytic(state, &quad);
+        }
+
+        return;
+    }
+
+    let points = curve.subdivide(state.desc.tolerance);
 
     points.windows(2).for_each(|p: &[Point]| {
         draw_line(state, &p[0], &p[1]);
@@ -274,265 +369,70 @@ pub fn render_path(
     path: Path,
     transform: impl Fn(&Point) -> Point,
 ) -> BoundingBox {
-    let desc = state.canvas.desc;
-    state.id = 0;
+    let desc = *state.desc;
 
+    render_device_path(state, path, |p| map_viewbox(&desc, &transform(p)))
+}
+
+///
+/// Same as `render_path` but `to_device` maps the points of the path straight to the pixels of
+/// the accumulation buffer, without going through the viewbox.
+///
+pub fn render_device_path(
+    state: &mut RenderState,
+    path: Path,
+    to_device: impl Fn(&Point) -> Point,
+) -> BoundingBox {
     let mut result = BoundingBox::default();
-    let mut update_bounds = |x: f64, y: f64| {
-        let x = x as usize;
-        let y = y as usize;
+    let mut update_bounds = |p: &Point| {
+        let x = p.x.floor() as i64;
+        let y = p.y.floor() as i64;
 
-        result.min_x = usize::min(result.min_x, x);
-        result.min_y = usize::min(result.min_y, y);
+        result.min_x = i64::min(result.min_x, x);
+        result.min_y = i64::min(result.min_y, y);
 
-        result.max_x = usize::max(result.max_x, x);
-        result.max_y = usize::max(result.max_y, y);
+        result.max_x = i64::max(result.max_x, x);
+        result.max_y = i64::max(result.max_y, y);
     };
 
-    let mut start_point = Point { x: 0.0, y: 0.0 };
-    let mut start_point_unmaped = Point { x: 0.0, y: 0.0 };
-    let mut currently_at = Point { x: 0.0, y: 0.0 };
-    let mut currently_at_unmaped = Point { x: 0.0, y: 0.0 };
-
-    for op in path.iter() {
-        match op {
-            PathOps::MoveTo { x, y } => {
-                let p = transform(&Point { x: *x, y: *y });
-                let p = map_viewbox(&desc, &p);
-
-                currently_at.x = p.x;
-                currently_at.y = p.y;
-
-                currently_at_unmaped.x = *x;
-                currently_at_unmaped.y = *y;
-
-                start_point.x = p.x;
-                start_point.y = p.y;
-
-                start_point_unmaped.x = *x;
-                start_point_unmaped.y = *y;
-
-                update_bounds(p.x, p.y);
-            }
-            PathOps::MoveToRel { x, y } => {
-                let p = transform(&Point {
-                    x: currently_at_unmaped.x + *x,
-                    y: currently_at_unmaped.y + *y,
-                });
-                let p = map_viewbox(&desc, &p);
-
-                currently_at.x = p.x;
-                currently_at.y = p.y;
-
-                currently_at_unmaped.x += *x;
-                currently_at_unmaped.y += *y;
-
-                start_point.x = currently_at.x;
-                start_point.y = currently_at.y;
-
-                start_point_unmaped.x += *x;
-                start_point_unmaped.y += *y;
-
-                update_bounds(currently_at.x, currently_at.y);
-            }
-            PathOps::LineTo { x, y } => {
-                let p = transform(&Point { x: *x, y: *y });
-                let p = map_viewbox(&desc, &p);
-
-                state.id += 1;
-                draw_line(state, &currently_at, &p);
-
-                currently_at.x = p.x;
-                currently_at.y = p.y;
-
-                currently_at_unmaped.x = *x;
-                currently_at_unmaped.y = *y;
-
-                update_bounds(p.x, p.y);
-            }
-            PathOps::LineToRel { x, y } => {
-                let p = transform(&Point {
-                    x: currently_at_unmaped.x + *x,
-                    y: currently_at_unmaped.y + *y,
-                });
-                let p = map_viewbox(&desc, &p);
-
-                state.id += 1;
-                draw_line(state, &currently_at, &p);
-
-                currently_at.x = p.x;
-                currently_at.y = p.y;
-
-                currently_at_unmaped.x += *x;
-                currently_at_unmaped.y += *y;
-
-                update_bounds(currently_at.x, currently_at.y);
-            }
-            PathOps::QuadTo { x1, y1, x2, y2 } => {
-                let p1 = transform(&Point { x: *x1, y: *y1 });
-                let p2 = transform(&Point { x: *x2, y: *y2 });
-
-                let p1 = map_viewbox(&desc, &p1);
-                let p2 = map_viewbox(&desc, &p2);
-
-                state.id += 1;
-                draw_quad_bezier(state, &QuadraticBezier::new(currently_at, p1, p2));
-
-                currently_at.x = p2.x;
-                currently_at.y = p2.y;
-
-                currently_at_unmaped.x = *x2;
-                currently_at_unmaped.y = *y2;
-
-                update_bounds(currently_at.x, currently_at.y);
-                update_bounds(p1.x, p1.y);
-                update_bounds(p2.x, p2.y);
-            }
-            PathOps::QuadToRel { x1, y1, x2, y2 } => {
-                let p1 = transform(&Point {
-                    x: currently_at_unmaped.x + *x1,
-                    y: currently_at_unmaped.y + *y1,
-                });
-                let p2 = transform(&Point {
-                    x: currently_at_unmaped.x + *x2,
-                    y: currently_at_unmaped.y + *y2,
-                });
-
-                let p1 = map_viewbox(&desc, &p1);
-                let p2 = map_viewbox(&desc, &p2);
-
-                state.id += 1;
-                draw_quad_bezier(state, &QuadraticBezier::new(currently_at, p1, p2));
-
-                currently_at.x = p2.x;
-                currently_at.y = p2.y;
-
-                currently_at_unmaped.x = *x2;
-                currently_at_unmaped.y = *y2;
-
-                update_bounds(currently_at.x, currently_at.y);
-                update_bounds(p1.x, p1.y);
-                update_bounds(p2.x, p2.y);
+    for segment in segments(path, to_device) {
+        match segment {
+            Segment::MoveTo(p) => update_bounds(&p),
+            Segment::Line(p0, p1) => {
+                draw_line(state, &p0, &p1);
+                update_bounds(&p1);
             }
-            PathOps::CubicTo {
-                x1,
-                y1,
-                x2,
-                y2,
-                x3,
-                y3,
-            } => {
-                let p1 = transform(&Point { x: *x1, y: *y1 });
-                let p2 = transform(&Point { x: *x2, y: *y2 });
-                let p3 = transform(&Point { x: *x3, y: *y3 });
-
-                let p1 = map_viewbox(&desc, &p1);
-                let p2 = map_viewbox(&desc, &p2);
-                let p3 = map_viewbox(&desc, &p3);
-
-                state.id += 1;
-                draw_cubic_bezier(state, &CubicBezier::new(currently_at, p1, p2, p3));
-
-                currently_at.x = p3.x;
-                currently_at.y = p3.y;
-
-                currently_at_unmaped.x = *x3;
-                currently_at_unmaped.y = *y3;
-
-                update_bounds(currently_at.x, currently_at.y);
-                update_bounds(p1.x, p1.y);
-                update_bounds(p2.x, p2.y);
-                update_bounds(p3.x, p3.y);
+            Segment::Quad(curve) => {
+                draw_quad_bezier(state, &curve);
+                curve.points().iter().for_each(&mut update_bounds);
             }
-            PathOps::CubicToRel {
-                x1,
-                y1,
-                x2,
-                y2,
-                x3,
-                y3,
-            } => {
-                let p1 = transform(&Point {
-                    x: currently_at_unmaped.x + *x1,
-                    y: currently_at_unmaped.y + *y1,
-                });
-                let p2 = transform(&Point {
-                    x: currently_at_unmaped.x + *x2,
-                    y: currently_at_unmaped.y + *y2,
-                });
-                let p3 = transform(&Point {
-                    x: currently_at_unmaped.x + *x3,
-                    y: currently_at_unmaped.y + *y3,
-                });
-
-                let p1 = map_viewbox(&desc, &p1);
-                let p2 = map_viewbox(&desc, &p2);
-                let p3 = map_viewbox(&desc, &p3);
-
-                state.id += 1;
-                draw_cubic_bezier(state, &CubicBezier::new(currently_at, p1, p2, p3));
-
-                currently_at.x = p3.x;
-                currently_at.y = p3.y;
-
-                currently_at_unmaped.x = *x3;
-                currently_at_unmaped.y = *y3;
-
-                update_bounds(currently_at.x, currently_at.y);
-                update_bounds(p1.x, p1.y);
-                update_bounds(p2.x, p2.y);
-                update_bounds(p3.x, p3.y);
-            }
-            PathOps::Close => {
-                state.id += 1;
-                draw_line(state, &currently_at, &start_point);
-
-                currently_at.x = start_point.x;
-                currently_at.y = start_point.y;
-
-                currently_at_unmaped.x = start_point_unmaped.x;
-                currently_at_unmaped.y = start_point_unmaped.y;
+            Segment::Cubic(curve) => {
+                draw_cubic_bezier(state, &curve);
+                curve.points().iter().for_each(&mut update_bounds);
             }
+            Segment::Close(p0, p1) => draw_line(state, &p0, &p1),
         }
     }
 
     result
 }
 
-fn alpha_fill_even_odd(
-    cell: &AccumulationCell,
-    prev_cell: &mut AccumulationCell,
-    acc: &mut f32,
-    filling: &mut f32,
-) -> f32 {
-    if cell.id > 0 && cell.id != prev_cell.id {
-        prev_cell.id = cell.id;
-        *filling = -(*filling);
-    }
-
-    if cell.id == prev_cell.id {
-        *acc += *filling * cell.area.abs();
+///
+/// `winding` is the sum of the signed areas accumulated so far on the current row, for pixels
+/// that aren't crossed by any edge it's exactly the winding number.
+///
+fn alpha_fill_even_odd(winding: f32) -> f32 {
+    let winding = winding.abs() % 2.0;
 
-        if *acc < 0.0 || *acc > 1.0 {
-            let is_filling = filling.partial_cmp(&&mut 0.0_f32) != Some(Ordering::Greater);
-            *acc = (is_filling as i32) as f32;
-        }
+    if winding > 1.0 {
+        2.0 - winding
     } else {
-        let is_filling = filling.partial_cmp(&&mut 0.0_f32) == Some(Ordering::Greater);
-        *acc = (is_filling as i32) as f32;
+        winding
     }
-
-    clamp(acc.abs(), 0.0, 1.0)
 }
 
-fn alpha_fill_non_zero(
-    cell: &AccumulationCell,
-    _prev_cell: &mut AccumulationCell,
-    acc: &mut f32,
-    _filling: &mut f32,
-) -> f32 {
-    *acc += cell.area;
-    acc.abs()
+fn alpha_fill_non_zero(winding: f32) -> f32 {
+    winding.abs().min(1.0)
 }
 
 fn get_linear_gradient_color_at(
@@ -711,61 +611,519 @@ fn get_conic_gradient_color_at(
     }
 }
 
-pub fn fill_path(
-    state: &mut RenderState,
+///
+/// Color of `fill_style` at the given pixel, `alpha` being the coverage of the pixel.
+///
+pub(crate) fn get_color_at(
+    x: usize,
+    y: usize,
+    bounds: &BoundingBox,
     fill_style: FillStyle,
+    alpha: f32,
+) -> Color {
+    match fill_style {
+        FillStyle::Plain(Color { r, g, b, a }) => Color {
+            r,
+            g,
+            b,
+            a: f64::min(alpha as f64, a),
+        },
+        FillStyle::LinearGradient { stops, angle } => {
+            get_linear_gradient_color_at(x, y, bounds, stops, angle, alpha)
+        }
+        FillStyle::RadialGradient { stops, translation } => {
+            get_radial_gradient_color_at(x, y, bounds, stops, translation, alpha)
+        }
+        FillStyle::ConicGradient { stops, translation } => {
+            get_conic_gradient_color_at(x, y, bounds, stops, translation, alpha)
+        }
+    }
+}
+
+///
+/// Storage for the gradient stops of a fill style converted by `decode_fill_style`.
+///
+#[derive(Default)]
+pub(crate) struct DecodedStops {
+    offsets: Vec<(Color, f64)>,
+    angles: Vec<(Color, Angle)>,
+}
+
+///
+/// Converts the colors of `fill_style` to the values stored in the canvas, so they're only
+/// converted once per shape and gradients are interpolated in the values the canvas stores.
+///
+pub(crate) fn decode_fill_style<'a>(
+    fill_style: FillStyle<'a>,
+    desc: &CanvasDescription,
+    stops: &'a mut DecodedStops,
+) -> FillStyle<'a> {
+    if desc.compositing == Compositing::Encoded && desc.color_space == RgbSpace::Srgb {
+        return fill_style;
+    }
+
+    let decode = |color: &Color| desc.compositing.decode(desc.color_space, color);
+
+    let decode_offsets = |offsets: &[(Color, f64)], storage: &'a mut Vec<(Color, f64)>| {
+        storage.extend(
+            offsets
+                .iter()
+                .map(|(color, offset)| (decode(color), *offset)),
+        );
+        &storage[..]
+    };
+
+    match fill_style {
+        FillStyle::Plain(color) => FillStyle::Plain(decode(&color)),
+        FillStyle::LinearGradient { stops: s, angle } => FillStyle::LinearGradient {
+            stops: decode_offsets(s, &mut stops.offsets),
+            angle,
+        },
+        FillStyle::RadialGradient {
+            stops: s,
+            translation,
+        } => FillStyle::RadialGradient {
+            stops: decode_offsets(s, &mut stops.offsets),
+            translation,
+        },
+        FillStyle::ConicGradient {
+            stops: s,
+            translation,
+        } => {
+            stops
+                .angles
+                .extend(s.iter().map(|(color, angle)| (decode(color), *angle)));
+            FillStyle::ConicGradient {
+                stops: &stops.angles[..],
+                translation,
+            }
+        }
+    }
+}
+
+///
+/// Blends `src` over the pixel stored at the beginning of `pixel`.
+///
+pub(crate) fn blend_pixel(pixel: &mut [f64], src: &Color, blend: BlendFunc) {
+    let dest = Color {
+        r: pixel[0],
+        g: pixel[1],
+        b: pixel[2],
+        a: pixel[3],
+    };
+    let resulting_color = blend(src, &dest);
+
+    pixel[0] = resulting_color.r;
+    pixel[1] = resulting_color.g;
+    pixel[2] = resulting_color.b;
+    pixel[3] = resulting_color.a;
+}
+
+///
+/// Turns the areas accumulated by `render_path` into the coverage of the pixels inside `bounds`,
+/// calling `pixel(x, y, coverage)` for each of them. The cells are cleared along the way so the
+/// accumulation buffer is ready for the next path.
+///
+pub fn sweep_coverage(
+    state: &mut RenderState,
     fill_rule: FillRule,
     bounds: &BoundingBox,
+    mut pixel: impl FnMut(usize, usize, f32),
 ) {
-    let accumulation_buffer = &mut state.canvas.accumulation_buffer;
-    let desc = &state.canvas.desc;
-    let color_buffer = &mut state.canvas.buffer;
-    let blend = state.canvas.blend;
+    let accumulation_buffer = &mut state.accumulation_buffer;
+    let desc = state.desc;
+    let (width, height) = (desc.width as i64, desc.height as i64);
+
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    if bounds.is_empty() || bounds.max_y < 0 || bounds.min_y >= height {
+        return;
+    }
 
-    for y in bounds.min_y..bounds.max_y {
+    // Rows outside the canvas are never accumulated and columns to the left are folded into the
+    // first one. The cells right after the shape can still hold the area of its rightmost edges
+    // without affecting any pixel, they only need to be cleared.
+    let (min_y, max_y) = (
+        bounds.min_y.max(0) as usize,
+        bounds.max_y.min(height - 1) as usize,
+    );
+    let min_x = clamp(bounds.min_x, 0, width - 1) as usize;
+    let max_x = clamp(bounds.max_x, 0, width - 1) as usize;
+    let clear_x = clamp(bounds.max_x + 1, 0, width - 1) as usize;
+
+    if bounds.max_x < 0 || bounds.min_x >= width {
+        for y in min_y..=max_y {
+            accumulation_buffer[y * desc.width + min_x].area = 0.0;
+        }
+
+        return;
+    }
+
+    // `max_y` is rounded down, when the shape ends exactly on a row boundary that row isn't
+    // covered and painting it would still affect it with some of the blending functions.
+    let reaches_last_row = accumulation_buffer[max_y * desc.width..]
+        .iter()
+        .take(clear_x + 1)
+        .skip(min_x)
+        .any(|cell| cell.area != 0.0);
+    let max_y = if reaches_last_row || max_y == min_y {
+        max_y
+    } else {
+        max_y - 1
+    };
+    let get_alpha = match fill_rule {
+        FillRule::NonZero => alpha_fill_non_zero,
+        FillRule::EvenOdd => alpha_fill_even_odd,
+    };
+
+    for y in min_y..=max_y {
         let mut acc = 0.0_f32;
-        let mut filling = -1.0_f32;
-        let mut prev_cell = AccumulationCell { area: 0.0, id: 0 };
-        let get_alpha = match fill_rule {
-            FillRule::NonZero => alpha_fill_non_zero,
-            FillRule::EvenOdd => alpha_fill_even_odd,
-        };
 
-        for x in bounds.min_x..=bounds.max_x {
+        for x in min_x..=max_x {
             let cell = &mut accumulation_buffer[y * desc.width + x];
-            let alpha = get_alpha(cell, &mut prev_cell, &mut acc, &mut filling);
+            acc += cell.area;
             cell.area = 0.0;
-            let pixel_offset: usize = y * desc.width * NUM_CHANNELS + x * NUM_CHANNELS;
-            let dest = Color {
-                r: color_buffer[pixel_offset],
-                g: color_buffer[pixel_offset + 1],
-                b: color_buffer[pixel_offset + 2],
-                a: color_buffer[pixel_offset + 3],
-            };
-            let src = match fill_style {
-                FillStyle::Plain(Color { r, g, b, a }) => Color {
-                    r,
-                    g,
-                    b,
-                    a: f64::min(alpha as f64, a),
-                },
-                FillStyle::LinearGradient { stops, angle } => {
-                    get_linear_gradient_color_at(x, y, bounds, stops, angle, alpha)
-                }
-                FillStyle::RadialGradient { stops, translation } => {
-                    get_radial_gradient_color_at(x, y, bounds, stops, translation, alpha)
-                }
-                FillStyle::ConicGradient { stops, translation } => {
-                    get_conic_gradient_color_at(x, y, bounds, stops, translation, alpha)
-                }
-            };
+            pixel(x, y, get_alpha(acc));
+        }
+
+        accumulation_buffer[y * desc.width + clear_x].area = 0.0;
+    }
+}
+
+pub fn fill_path(
+    canvas: &mut Canvas,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    bounds: &BoundingBox,
+) {
+    fill_path_adjusted(canvas, fill_style, fill_rule, bounds, |alpha| alpha)
+}
+
+///
+/// Same as `fill_path` with the coverage of each pixel going through `adjust` first, text uses it
+/// for its gamma.
+///
+pub fn fill_path_adjusted(
+    canvas: &mut Canvas,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    bounds: &BoundingBox,
+    adjust: impl Fn(f32) -> f32,
+) {
+    fill_region_adjusted(canvas, fill_style, fill_rule, bounds, bounds, adjust)
+}
+
+///
+/// Same as `fill_path_adjusted` with the fill style laid out over `bounds` and the path swept over
+/// `region`, the bounds returned when it was rendered. Only the pixels of `region` are blended,
+/// like one glyph of a longer text.
+///
+pub fn fill_region_adjusted(
+    canvas: &mut Canvas,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    bounds: &BoundingBox,
+    region: &BoundingBox,
+    adjust: impl Fn(f32) -> f32,
+) {
+    let desc = canvas.desc;
+    let mut decoded_stops = DecodedStops::default();
+    let fill_style = decode_fill_style(fill_style, &desc, &mut decoded_stops);
+    let color_buffer = &mut canvas.buffer;
+    let blend = canvas.blend;
+    let mut state = RenderState {
+        desc: &desc,
+        accumulation_buffer: &mut canvas.accumulation_buffer,
+    };
+
+    sweep_coverage(&mut state, fill_rule, region, |x, y, alpha| {
+        let pixel_offset: usize = y * desc.width * NUM_CHANNELS + x * NUM_CHANNELS;
+        let src = get_color_at(x, y, bounds, fill_style, adjust(alpha));
+
+        blend_pixel(&mut color_buffer[pixel_offset..], &src, blend);
+    });
+}
+
+///
+/// Same as `fill_path` with a coverage computed beforehand, like glyphs cached in a mask. The
+/// fill style is laid out over `bounds` and `coverage(x, y)` is blended for the pixels of `region`
+/// inside the canvas.
+///
+pub fn fill_coverage(
+    canvas: &mut Canvas,
+    fill_style: FillStyle,
+    bounds: &BoundingBox,
+    region: &BoundingBox,
+    coverage: impl Fn(usize, usize) -> f32,
+) {
+    let desc = canvas.desc;
+    let (width, height) = (desc.width as i64, desc.height as i64);
+
+    if width == 0
+        || height == 0
+        || region.is_empty()
+        || region.max_x < 0
+        || region.max_y < 0
+        || region.min_x >= width
+        || region.min_y >= height
+    {
+        return;
+    }
+
+    let mut decoded_stops = DecodedStops::default();
+    let fill_style = decode_fill_style(fill_style, &desc, &mut decoded_stops);
+
+    for y in region.min_y.max(0)..=region.max_y.min(height - 1) {
+        for x in region.min_x.max(0)..=region.max_x.min(width - 1) {
+            let (x, y) = (x as usize, y as usize);
+            let pixel_offset = y * desc.width * NUM_CHANNELS + x * NUM_CHANNELS;
+            let src = get_color_at(x, y, bounds, fill_style, coverage(x, y));
+
+            blend_pixel(&mut canvas.buffer[pixel_offset..], &src, canvas.blend);
+        }
+    }
+}
+
+///
+/// Number of subpixels in a pixel when rendering with `Antialiasing::Subpixel`.
+///
+pub const SUBPIXELS: usize = 3;
+
+///
+/// FreeType's default LCD filter, it spreads the coverage of a subpixel over its neighbours to
+/// reduce color fringes. The weights add up to 1 so the overall coverage is kept.
+///
+const LCD_FILTER: [f32; 5] = [
+    8.0 / 256.0,
+    77.0 / 256.0,
+    86.0 / 256.0,
+    77.0 / 256.0,
+    8.0 / 256.0,
+];
+
+///
+/// Same as `fill_path` for a path rendered by `render_device_path` into
+/// `Canvas::subpixel_accumulation_buffer`, `bounds` being in subpixels horizontally.
+///
+pub fn fill_path_subpixel(
+    canvas: &mut Canvas,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    order: SubpixelOrder,
+    bounds: &BoundingBox,
+) {
+    let desc = canvas.desc;
+    let (width, height) = (desc.width as i64, desc.height as i64);
+    let subpixel_desc = CanvasDescription {
+        width: desc.width * SUBPIXELS,
+        ..desc
+    };
+    let mut state = RenderState {
+        desc: &subpixel_desc,
+        accumulation_buffer: &mut canvas.subpixel_accumulation_buffer,
+    };
+    let (min_y, max_y) = (bounds.min_y.max(0), bounds.max_y.min(height - 1));
+    let subpixels = SUBPIXELS as i64;
+
+    let outside = bounds.max_x < 0 || bounds.min_x >= width * subpixels;
+
+    if bounds.is_empty() || min_y > max_y || width == 0 || height == 0 || outside {
+        // Still clears the cells the path was accumulated in.
+        sweep_coverage(&mut state, fill_rule, bounds, |_, _, _| {});
+        return;
+    }
+
+    // The filter spreads the coverage to one more pixel on each side.
+    let first = (clamp(bounds.min_x, 0, width * subpixels - 1) / subpixels - 1).max(0) as usize;
+    let last =
+        (clamp(bounds.max_x, 0, width * subpixels - 1) / subpixels + 1).min(width - 1) as usize;
+    let row_size = (last - first + 1) * SUBPIXELS;
+    let mut coverage = vec![0.0_f32; (max_y - min_y + 1) as usize * row_size];
+    // Like `fill_path`, the pixels `sweep_coverage` skips are only blended when the filter spreads
+    // some coverage to them.
+    let mut swept = vec![false; (max_y - min_y + 1) as usize * (last - first + 1)];
+
+    sweep_coverage(&mut state, fill_rule, bounds, |x, y, alpha| {
+        coverage[(y - min_y as usize) * row_size + x - first * SUBPIXELS] = alpha;
+        swept[(y - min_y as usize) * (last - first + 1) + x / SUBPIXELS - first] = true;
+    });
+
+    let mut decoded_stops = DecodedStops::default();
+    let fill_style = decode_fill_style(fill_style, &desc, &mut decoded_stops);
+    // Gradients are laid out over the bounds of the shape in pixels.
+    let pixel_bounds = BoundingBox {
+        min_x: bounds.min_x.div_euclid(subpixels),
+        max_x: bounds.max_x.div_euclid(subpixels),
+        ..*bounds
+    };
+    let filtered = |row: &[f32], subpixel: usize| {
+        LCD_FILTER
+            .iter()
+            .enumerate()
+            .filter_map(|(i, weight)| {
+                (subpixel + i)
+                    .checked_sub(2)
+                    .and_then(|s| row.get(s))
+                    .map(|alpha| alpha * weight)
+            })
+            .sum::<f32>()
+    };
+
+    for y in min_y as usize..=max_y as usize {
+        let row = &coverage[(y - min_y as usize) * row_size..][..row_size];
+
+        for x in first..=last {
+            let offset = (x - first) * SUBPIXELS;
+            let mut alphas = [0, 1, 2].map(|i| filtered(row, offset + i));
+
+            if !swept[(y - min_y as usize) * (last - first + 1) + x - first]
+                && alphas.iter().all(|alpha| *alpha == 0.0)
+            {
+                continue;
+            }
+
+            if order == SubpixelOrder::Bgr {
+                alphas.reverse();
+            }
+
+            let pixel = &mut canvas.buffer[(y * desc.width + x) * NUM_CHANNELS..][..NUM_CHANNELS];
+            let dest = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            // Every channel is blended with its own coverage, alpha with their average.
+            let blended = [
+                alphas[0],
+                alphas[1],
+                alphas[2],
+                alphas.iter().sum::<f32>() / 3.0,
+            ]
+            .map(|alpha| {
+                let mut result = dest;
+                let src = get_color_at(x, y, &pixel_bounds, fill_style, alpha);
+                blend_pixel(&mut result, &src, canvas.blend);
+                result
+            });
+
+            for channel in 0..NUM_CHANNELS {
+                pixel[channel] = blended[channel][channel];
+            }
+        }
+    }
+}
+
+///
+/// Fills `path` with `Antialiasing::None`: the pixels whose center is inside the shape are
+/// painted with full coverage. The centers of a row are compared against where the flattened
+/// edges cross it, edges include their top end but not their bottom one and the pixels of a span
+/// start at the crossing, which gives the top-left rule.
+///
+pub fn fill_path_aliased(
+    canvas: &mut Canvas,
+    path: Path,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
+    transform: impl Fn(&Point) -> Point,
+) {
+    let desc = canvas.desc;
+    let mut bounds = BoundingBox::default();
+    // Every edge goes downwards, with the direction it originally had.
+    let mut edges = Vec::<(Point, Point, i32)>::new();
+    let mut add_line = |a: Point, b: Point| {
+        if a.y < b.y {
+            edges.push((a, b, 1));
+        } else if a.y > b.y {
+            edges.push((b, a, -1));
+        }
+    };
+
+    for segment in segments(path, |p| map_viewbox(&desc, &transform(p))) {
+        let points = match segment {
+            Segment::MoveTo(p) => vec![p],
+            Segment::Line(a, b) | Segment::Close(a, b) => vec![a, b],
+            Segment::Quad(curve) => curve
+                .subdivide(desc.tolerance)
+                .iter()
+                .map(|t| curve.eval(*t))
+                .collect(),
+            Segment::Cubic(curve) => curve.subdivide(desc.tolerance),
+        };
+        let control_points = match segment {
+            Segment::Quad(curve) => curve.points().to_vec(),
+            Segment::Cubic(curve) => curve.points().to_vec(),
+            _ => points.clone(),
+        };
+
+        // Same bounds as `render_path`, gradients are laid out over them.
+        for p in &control_points {
+            bounds.min_x = bounds.min_x.min(p.x.floor() as i64);
+            bounds.min_y = bounds.min_y.min(p.y.floor() as i64);
+            bounds.max_x = bounds.max_x.max(p.x.floor() as i64);
+            bounds.max_y = bounds.max_y.max(p.y.floor() as i64);
+        }
+
+        points
+            .windows(2)
+            .for_each(|line| add_line(line[0], line[1]));
+    }
+
+    let (width, height) = (desc.width as i64, desc.height as i64);
+
+    if edges.is_empty() || width == 0 || height == 0 {
+        return;
+    }
+
+    let top = edges.iter().map(|(a, _, _)| a.y).fold(f64::MAX, f64::min);
+    let bottom = edges.iter().map(|(_, b, _)| b.y).fold(f64::MIN, f64::max);
+    // Rows whose center is in [top, bottom).
+    let min_y = ((top - 0.5).ceil() as i64).max(0);
+    let max_y = (((bottom - 0.5).ceil() as i64) - 1).min(height - 1);
+    let min_x = clamp(bounds.min_x, 0, width - 1) as usize;
+    let max_x = clamp(bounds.max_x, 0, width - 1) as usize;
+
+    if min_y > max_y || bounds.max_x < 0 || bounds.min_x >= width {
+        return;
+    }
+
+    let mut decoded_stops = DecodedStops::default();
+    let fill_style = decode_fill_style(fill_style, &desc, &mut decoded_stops);
+    let get_alpha = match fill_rule {
+        FillRule::NonZero => alpha_fill_non_zero,
+        FillRule::EvenOdd => alpha_fill_even_odd,
+    };
+    let mut crossings = Vec::<(f64, i32)>::new();
+
+    for y in min_y as usize..=max_y as usize {
+        let center = y as f64 + 0.5;
+
+        crossings.clear();
+        // The same edge always crosses at the same place, whichever shape it belongs to.
+        crossings.extend(
+            edges
+                .iter()
+                .filter(|(a, b, _)| a.y <= center && center < b.y)
+                .map(|(a, b, direction)| {
+                    let x = a.x + (center - a.y) * (b.x - a.x) / (b.y - a.y);
+                    (x, *direction)
+                }),
+        );
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut next = 0;
+
+        for x in min_x..=max_x {
+            let center = x as f64 + 0.5;
+
+            while next < crossings.len() && crossings[next].0 <= center {
+                winding += crossings[next].1;
+                next += 1;
+            }
 
-            let resulting_color = blend(&src, &dest);
+            let alpha = get_alpha(winding as f32);
+            let pixel_offset = (y * desc.width + x) * NUM_CHANNELS;
+            let src = get_color_at(x, y, &bounds, fill_style, alpha);
 
-            color_buffer[pixel_offset] = resulting_color.r;
-            color_buffer[pixel_offset + 1] = resulting_color.g;
-            color_buffer[pixel_offset + 2] = resulting_color.b;
-            color_buffer[pixel_offset + 3] = resulting_color.a;
+            blend_pixel(&mut canvas.buffer[pixel_offset..], &src, canvas.blend);
         }
     }
 }
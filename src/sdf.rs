@@ -0,0 +1,687 @@
+//!
+//! Signed distance fields generated from paths, for renderers that scale shapes on the GPU.
+//! Distances are exact: the closest point of lines and quadratic curves is solved analytically
+//! and the one of cubic curves is refined with Newton's method.
+//!
+//! Multi-channel fields follow Chlumský's MSDF: edges are split between the red, green and blue
+//! channels so that the median of the three keeps sharp corners sharp.
+//! See https://github.com/Chlumsky/msdfgen
+//!
+use crate::{
+    canvas::CanvasDescription,
+    color::FillRule,
+    geometry::{segments, CubicBezier, Path, Point, QuadraticBezier, Segment},
+    math::map_viewbox,
+};
+use std::f64::consts::PI;
+
+///
+/// Maximum distance in pixels between the curves and the lines used to find which pixels are
+/// inside the shape.
+///
+const FLATTENING_TOLERANCE: f64 = 1e-3;
+
+///
+/// How far from the middle of an edge its sides are probed to find which one is inside.
+///
+const SIDE_OFFSET: f64 = 1e-2;
+
+///
+/// Edges meeting at an angle sharper than this (in radians) form a corner whose sides get
+/// different channels in multi-channel fields.
+///
+const CORNER_ANGLE: f64 = 3.0;
+
+const CUBIC_SEARCH_STARTS: usize = 8;
+const CUBIC_SEARCH_STEPS: usize = 8;
+
+// Channels an edge contributes to in a multi-channel field.
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+///
+/// A distance field with `channels` values per pixel, row by row.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceField {
+    pub width: usize,
+    pub height: usize,
+    ///
+    /// 1 for a signed distance field, 3 (red, green, blue) for a multi-channel one.
+    ///
+    pub channels: usize,
+    ///
+    /// Signed distances in pixels from the center of each pixel to the outline, positive inside
+    /// the shape.
+    ///
+    pub distances: Vec<f32>,
+}
+
+impl DistanceField {
+    ///
+    /// The distance a shader would reconstruct at the pixel: the value of a signed distance
+    /// field or the median of the channels of a multi-channel one.
+    ///
+    pub fn distance(&self, x: usize, y: usize) -> f32 {
+        let offset = (y * self.width + x) * self.channels;
+
+        match self.channels {
+            3 => median(
+                self.distances[offset],
+                self.distances[offset + 1],
+                self.distances[offset + 2],
+            ),
+            _ => self.distances[offset],
+        }
+    }
+
+    ///
+    /// Maps the distances to 8 bits, `range` pixels outside the outline being 0 and `range`
+    /// pixels inside being 255. The outline is at 127.5.
+    ///
+    pub fn to_u8(&self, range: f64) -> Vec<u8> {
+        self.distances
+            .iter()
+            .map(|d| {
+                let value = 0.5 + 0.5 * *d as f64 / range;
+                (value.clamp(0.0, 1.0) * u8::MAX as f64).round() as u8
+            })
+            .collect()
+    }
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+fn sub(a: &Point, b: &Point) -> Point {
+    Point {
+        x: a.x - b.x,
+        y: a.y - b.y,
+    }
+}
+
+fn dot(a: &Point, b: &Point) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn cross(a: &Point, b: &Point) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn normalize(p: &Point) -> Point {
+    let length = f64::hypot(p.x, p.y);
+
+    if length == 0.0 {
+        return Point { x: 0.0, y: 0.0 };
+    }
+
+    Point {
+        x: p.x / length,
+        y: p.y / length,
+    }
+}
+
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-14 {
+        if b.abs() < 1e-14 {
+            return vec![];
+        }
+
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+    } else if discriminant == 0.0 {
+        vec![-b / (2.0 * a)]
+    } else {
+        vec![]
+    }
+}
+
+///
+/// Real roots of `a * x^3 + b * x^2 + c * x + d`, with Cardano's method or the trigonometric one
+/// when there are three roots.
+///
+fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // A tiny leading coefficient makes the normalized cubic overflow, the curve is then close
+    // enough to a quadratic.
+    if a == 0.0 || (b / a).abs() >= 1e6 {
+        return solve_quadratic(b, c, d);
+    }
+
+    let (a, b, c) = (b / a, c / a, d / a);
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (a * (2.0 * a * a - 9.0 * b) + 27.0 * c) / 54.0;
+    let (r2, q3) = (r * r, q * q * q);
+    let offset = a / 3.0;
+
+    if r2 < q3 {
+        let angle = (r / q3.sqrt()).clamp(-1.0, 1.0).acos();
+        let scale = -2.0 * q.sqrt();
+
+        return [0.0, 2.0 * PI, -2.0 * PI]
+            .iter()
+            .map(|shift| scale * ((angle + shift) / 3.0).cos() - offset)
+            .collect();
+    }
+
+    let u = -r.signum() * (r.abs() + (r2 - q3).sqrt()).cbrt();
+    let v = if u == 0.0 { 0.0 } else { q / u };
+
+    vec![u + v - offset]
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Edge {
+    Line(Point, Point),
+    Quad(QuadraticBezier),
+    Cubic(CubicBezier),
+}
+
+///
+/// The closest point of an edge to some point.
+///
+#[derive(Debug, Clone, Copy)]
+struct EdgePoint {
+    distance: f64,
+    t: f64,
+}
+
+impl Edge {
+    fn eval(&self, t: f64) -> Point {
+        match self {
+            Edge::Line(a, b) => Point {
+                x: a.x + (b.x - a.x) * t,
+                y: a.y + (b.y - a.y) * t,
+            },
+            Edge::Quad(curve) => curve.eval(t),
+            Edge::Cubic(curve) => curve.eval(t),
+        }
+    }
+
+    ///
+    /// The direction the edge goes in at `t`, even where the derivative vanishes because a
+    /// control point is on an end point.
+    ///
+    fn direction(&self, t: f64) -> Point {
+        let derivative = match self {
+            Edge::Line(a, b) => sub(b, a),
+            Edge::Quad(curve) => curve.derivative(t),
+            Edge::Cubic(curve) => curve.derivative(t),
+        };
+
+        if f64::hypot(derivative.x, derivative.y) > 1e-12 {
+            return derivative;
+        }
+
+        let delta = 1e-3;
+        sub(
+            &self.eval((t + delta).min(1.0)),
+            &self.eval((t - delta).max(0.0)),
+        )
+    }
+
+    fn closest(&self, p: &Point) -> EdgePoint {
+        let candidates = match self {
+            Edge::Line(a, b) => {
+                let ab = sub(b, a);
+                let length = dot(&ab, &ab);
+                let t = if length > 0.0 {
+                    dot(&sub(p, a), &ab) / length
+                } else {
+                    0.0
+                };
+                vec![t]
+            }
+            Edge::Quad(curve) => {
+                // Where (B(t) - p) . B'(t) = 0, a cubic in t.
+                let [p0, p1, p2] = curve.points();
+                let q = sub(&p0, p);
+                let a = sub(&p1, &p0);
+                let b = Point {
+                    x: p2.x - 2.0 * p1.x + p0.x,
+                    y: p2.y - 2.0 * p1.y + p0.y,
+                };
+                let mut roots = solve_cubic(
+                    dot(&b, &b),
+                    3.0 * dot(&a, &b),
+                    2.0 * dot(&a, &a) + dot(&q, &b),
+                    dot(&q, &a),
+                );
+                roots.extend([0.0, 1.0]);
+                roots
+            }
+            Edge::Cubic(curve) => {
+                let [p0, p1, p2, p3] = curve.points();
+                // Second derivative at `t`.
+                let second = |t: f64| Point {
+                    x: 6.0 * (1.0 - t) * (p2.x - 2.0 * p1.x + p0.x)
+                        + 6.0 * t * (p3.x - 2.0 * p2.x + p1.x),
+                    y: 6.0 * (1.0 - t) * (p2.y - 2.0 * p1.y + p0.y)
+                        + 6.0 * t * (p3.y - 2.0 * p2.y + p1.y),
+                };
+                let mut candidates = vec![];
+
+                for start in 0..=CUBIC_SEARCH_STARTS {
+                    let mut t = start as f64 / CUBIC_SEARCH_STARTS as f64;
+
+                    for _ in 0..CUBIC_SEARCH_STEPS {
+                        let offset = sub(&curve.eval(t), p);
+                        let derivative = curve.derivative(t);
+                        let numerator = dot(&offset, &derivative);
+                        let denominator = dot(&derivative, &derivative) + dot(&offset, &second(t));
+
+                        if denominator == 0.0 {
+                            break;
+                        }
+
+                        let next = (t - numerator / denominator).clamp(0.0, 1.0);
+                        candidates.push(next);
+
+                        if (next - t).abs() < 1e-12 {
+                            break;
+                        }
+                        t = next;
+                    }
+                    candidates.push(start as f64 / CUBIC_SEARCH_STARTS as f64);
+                }
+
+                candidates
+            }
+        };
+
+        candidates
+            .into_iter()
+            .filter(|t| t.is_finite())
+            .map(|t| {
+                let t = t.clamp(0.0, 1.0);
+                EdgePoint {
+                    distance: self.eval(t).distance_to(p),
+                    t,
+                }
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+            .unwrap()
+    }
+
+    ///
+    /// How perpendicular the edge is to the direction of `p` at its closest point, 0 being
+    /// perpendicular. Breaks ties between edges sharing a corner.
+    ///
+    fn obliqueness(&self, p: &Point, closest: &EdgePoint) -> f64 {
+        let direction = normalize(&self.direction(closest.t));
+        let towards = normalize(&sub(p, &self.eval(closest.t)));
+
+        dot(&direction, &towards).abs()
+    }
+
+    ///
+    /// Distance to the edge, positive on its left (in the direction of `cross`). Past the end
+    /// points it's the distance to the tangent line there, which keeps corners sharp in
+    /// multi-channel fields.
+    ///
+    fn pseudo_distance(&self, p: &Point, closest: &EdgePoint) -> f64 {
+        let at = self.eval(closest.t);
+        let direction = normalize(&self.direction(closest.t));
+        let offset = sub(p, &at);
+        let side = if cross(&direction, &offset) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let past_start = closest.t == 0.0 && dot(&offset, &direction) < 0.0;
+        let past_end = closest.t == 1.0 && dot(&offset, &direction) > 0.0;
+
+        if past_start || past_end {
+            let pseudo = cross(&direction, &offset);
+
+            if pseudo.abs() <= closest.distance {
+                return pseudo;
+            }
+        }
+
+        side * closest.distance
+    }
+}
+
+///
+/// The outline of a path in device space, with the lines used to tell inside from outside.
+///
+struct Shape {
+    contours: Vec<Vec<Edge>>,
+    lines: Vec<(Point, Point)>,
+    fill_rule: FillRule,
+}
+
+impl Shape {
+    fn new(
+        desc: &CanvasDescription,
+        path: Path,
+        fill_rule: FillRule,
+        transform: impl Fn(&Point) -> Point,
+    ) -> Shape {
+        let mut contours = Vec::<Vec<Edge>>::new();
+        let mut lines = Vec::<(Point, Point)>::new();
+
+        for segment in segments(path, |p| map_viewbox(desc, &transform(p))) {
+            let edge = match segment {
+                Segment::MoveTo(_) => {
+                    contours.push(vec![]);
+                    continue;
+                }
+                Segment::Line(a, b) | Segment::Close(a, b) => {
+                    if a.distance_to(&b) == 0.0 {
+                        continue;
+                    }
+                    lines.push((a, b));
+                    Edge::Line(a, b)
+                }
+                Segment::Quad(curve) => {
+                    let points = curve
+                        .subdivide(FLATTENING_TOLERANCE)
+                        .iter()
+                        .map(|t| curve.eval(*t))
+                        .collect::<Vec<Point>>();
+                    lines.extend(points.windows(2).map(|p| (p[0], p[1])));
+                    Edge::Quad(curve)
+                }
+                Segment::Cubic(curve) => {
+                    // See `CubicBezier::is_sufficiently_flat` for the meaning of the tolerance.
+                    let points = curve.subdivide(16.0 * FLATTENING_TOLERANCE.powi(2));
+                    lines.extend(points.windows(2).map(|p| (p[0], p[1])));
+                    Edge::Cubic(curve)
+                }
+            };
+
+            match contours.last_mut() {
+                Some(contour) => contour.push(edge),
+                None => contours.push(vec![edge]),
+            }
+        }
+
+        contours.retain(|contour| !contour.is_empty());
+
+        Shape {
+            contours,
+            lines,
+            fill_rule,
+        }
+    }
+
+    ///
+    /// Whether `p` is filled, the winding number counts the edges to the left of `p` like the
+    /// accumulation buffer does.
+    ///
+    fn is_inside(&self, p: &Point) -> bool {
+        let mut winding = 0;
+
+        for (a, b) in &self.lines {
+            let (top, bottom, direction) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+
+            if p.y < top.y || p.y >= bottom.y {
+                continue;
+            }
+
+            let x = top.x + (p.y - top.y) / (bottom.y - top.y) * (bottom.x - top.x);
+
+            if x < p.x {
+                winding += direction;
+            }
+        }
+
+        match self.fill_rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    ///
+    /// 1 if the inside is on the left of the edge, -1 if it's on its right and 0 when both sides
+    /// are the same, like for an edge inside an overlapping contour.
+    ///
+    fn orientation(&self, edge: &Edge) -> f64 {
+        let middle = edge.eval(0.5);
+        let direction = normalize(&edge.direction(0.5));
+        let normal = Point {
+            x: -direction.y * SIDE_OFFSET,
+            y: direction.x * SIDE_OFFSET,
+        };
+        let left = self.is_inside(&Point {
+            x: middle.x + normal.x,
+            y: middle.y + normal.y,
+        });
+        let right = self.is_inside(&Point {
+            x: middle.x - normal.x,
+            y: middle.y - normal.y,
+        });
+
+        match (left, right) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+fn pixel_center(x: usize, y: usize) -> Point {
+    Point {
+        x: x as f64 + 0.5,
+        y: y as f64 + 0.5,
+    }
+}
+
+///
+/// Signed distance to the closest edge of `shape`.
+///
+fn signed_distance(shape: &Shape, p: &Point) -> f64 {
+    let distance = shape
+        .contours
+        .iter()
+        .flatten()
+        .map(|edge| edge.closest(p).distance)
+        .fold(f64::MAX, f64::min);
+
+    if shape.is_inside(p) {
+        distance
+    } else {
+        -distance
+    }
+}
+
+///
+/// The signed distance from the center of every pixel to the outline of `path`, inside being
+/// decided by `fill_rule`. Only the size and viewbox of `desc` are used. Like
+/// `Canvas::draw_shape` subpaths are not closed implicitly.
+///
+pub fn signed_distance_field(
+    desc: &CanvasDescription,
+    path: Path,
+    fill_rule: FillRule,
+    transform: impl Fn(&Point) -> Point,
+) -> DistanceField {
+    let shape = Shape::new(desc, path, fill_rule, transform);
+    let distances = (0..desc.height)
+        .flat_map(|y| (0..desc.width).map(move |x| pixel_center(x, y)))
+        .map(|p| signed_distance(&shape, &p) as f32)
+        .collect();
+
+    DistanceField {
+        width: desc.width,
+        height: desc.height,
+        channels: 1,
+        distances,
+    }
+}
+
+///
+/// The next color in cyan, magenta, yellow, avoiding the channel shared with `banned` when
+/// possible.
+///
+fn switch_color(color: u8, banned: u8) -> u8 {
+    let shared = color & banned;
+
+    if shared == RED || shared == GREEN || shared == BLUE {
+        return shared ^ WHITE;
+    }
+    if color == WHITE {
+        return GREEN | BLUE;
+    }
+
+    let shifted = color << 1;
+    (shifted | shifted >> 3) & WHITE
+}
+
+fn is_corner(incoming: &Point, outgoing: &Point) -> bool {
+    let (a, b) = (normalize(incoming), normalize(outgoing));
+
+    dot(&a, &b) <= 0.0 || cross(&a, &b).abs() > CORNER_ANGLE.sin()
+}
+
+///
+/// Assigns channels to the edges of a contour so that the two edges of every corner have only
+/// one channel in common, see msdfgen's `edgeColoringSimple`.
+///
+fn color_edges(contour: &[Edge]) -> Vec<u8> {
+    let count = contour.len();
+    let corners = (0..count)
+        .filter(|i| {
+            let previous = &contour[(i + count - 1) % count];
+            is_corner(&previous.direction(1.0), &contour[*i].direction(0.0))
+        })
+        .collect::<Vec<usize>>();
+    let mut colors = vec![WHITE; count];
+
+    match corners.len() {
+        // Smooth contours don't need to be split.
+        0 => {}
+        // A teardrop, the edges are spread over three colors around the corner. With fewer edges
+        // there's nothing to split and the contour falls back to a plain distance.
+        1 if count >= 3 => {
+            let first = switch_color(WHITE, 0);
+            let choices = [first, WHITE, switch_color(first, 0)];
+
+            for i in 0..count {
+                let third = (3.0 + 2.875 * i as f64 / (count - 1) as f64 - 1.4375 + 0.5) as usize;
+                colors[(corners[0] + i) % count] = choices[third - 2];
+            }
+        }
+        1 => {}
+        _ => {
+            let mut spline = 0;
+            let initial = switch_color(WHITE, 0);
+            let mut color = initial;
+
+            for i in 0..count {
+                let index = (corners[0] + i) % count;
+
+                if spline + 1 < corners.len() && corners[spline + 1] == index {
+                    spline += 1;
+                    let banned = if spline == corners.len() - 1 {
+                        initial
+                    } else {
+                        0
+                    };
+                    color = switch_color(color, banned);
+                }
+
+                colors[index] = color;
+            }
+        }
+    }
+
+    colors
+}
+
+///
+/// A multi-channel signed distance field of `path`, see `signed_distance_field` for the
+/// parameters. Each channel holds the distance to the closest edge assigned to it, pixels where
+/// the median of the channels would end up on the wrong side of the outline get the plain
+/// signed distance in every channel.
+///
+pub fn multi_channel_distance_field(
+    desc: &CanvasDescription,
+    path: Path,
+    fill_rule: FillRule,
+    transform: impl Fn(&Point) -> Point,
+) -> DistanceField {
+    let shape = Shape::new(desc, path, fill_rule, transform);
+    let edges = shape
+        .contours
+        .iter()
+        .flat_map(|contour| contour.iter().zip(color_edges(contour)))
+        .map(|(edge, color)| (*edge, color, shape.orientation(edge)))
+        .collect::<Vec<(Edge, u8, f64)>>();
+    let mut distances = Vec::with_capacity(desc.width * desc.height * 3);
+
+    for y in 0..desc.height {
+        for x in 0..desc.width {
+            let p = pixel_center(x, y);
+            let inside = shape.is_inside(&p);
+            let sign = if inside { 1.0 } else { -1.0 };
+            // The closest edge of each channel, with its obliqueness to break ties.
+            let mut closest = [None::<(usize, EdgePoint, f64)>; 3];
+            let mut true_distance = f64::MAX;
+
+            for (i, (edge, color, _)) in edges.iter().enumerate() {
+                let point = edge.closest(&p);
+                let obliqueness = edge.obliqueness(&p, &point);
+                true_distance = true_distance.min(point.distance);
+
+                for (channel, best) in closest.iter_mut().enumerate() {
+                    if color & (1 << channel) == 0 {
+                        continue;
+                    }
+
+                    let is_closer = match best {
+                        None => true,
+                        Some((_, other, other_obliqueness)) => {
+                            let difference = point.distance - other.distance;
+                            difference < -1e-9
+                                || (difference.abs() <= 1e-9 && obliqueness < *other_obliqueness)
+                        }
+                    };
+
+                    if is_closer {
+                        *best = Some((i, point, obliqueness));
+                    }
+                }
+            }
+
+            let true_distance = sign * true_distance;
+            let channels = closest.map(|best| match best {
+                Some((i, point, _)) => {
+                    let (edge, _, orientation) = &edges[i];
+                    let pseudo = edge.pseudo_distance(&p, &point);
+
+                    if *orientation == 0.0 {
+                        sign * pseudo.abs()
+                    } else {
+                        orientation * pseudo
+                    }
+                }
+                None => true_distance,
+            });
+            let median = median(channels[0] as f32, channels[1] as f32, channels[2] as f32) as f64;
+
+            if median * sign > 0.0 || true_distance == 0.0 {
+                distances.extend(channels.map(|d| d as f32));
+            } else {
+                distances.extend([true_distance as f32; 3]);
+            }
+        }
+    }
+
+    DistanceField {
+        width: desc.width,
+        height: desc.height,
+        channels: 3,
+        distances,
+    }
+}
@@ -0,0 +1,212 @@
+//!
+//! Rendering SVG documents with the primitives of the library. A practical subset of SVG 1.1 and
+//! 2 is supported: paths and basic shapes, groups with transforms, `use` and `defs`, fills and
+//! strokes with their opacity, linear and radial gradients, and the `viewBox` with
+//! `preserveAspectRatio` of the root.
+//!
+//! Parsing resolves the tree into a flat list of shapes with their transform, paint and opacity,
+//! `Canvas::draw_svg` then fills them one after the other. Text, clipping, masks, filters,
+//! patterns, markers, dashes and CSS style sheets are left out.
+//!
+use crate::{canvas::ViewBox, color::Color, color::FillRule, geometry::PathOps};
+pub use path_data::parse_path;
+use std::{fmt, fs, io, path::Path};
+pub use stroke::{stroke, LineCap, LineJoin, StrokeStyle};
+pub use transform::Transform;
+
+mod parse;
+mod path_data;
+mod render;
+mod stroke;
+mod transform;
+
+#[derive(Debug)]
+pub enum SvgError {
+    Io(io::Error),
+    Xml(roxmltree::Error),
+    ///
+    /// The root element isn't `<svg>`.
+    ///
+    NotSvg,
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SvgError::Io(error) => write!(f, "cannot read the document: {}", error),
+            SvgError::Xml(error) => write!(f, "cannot parse the document: {}", error),
+            SvgError::NotSvg => write!(f, "the root element is not <svg>"),
+        }
+    }
+}
+
+impl std::error::Error for SvgError {}
+
+impl From<io::Error> for SvgError {
+    fn from(error: io::Error) -> Self {
+        SvgError::Io(error)
+    }
+}
+
+impl From<roxmltree::Error> for SvgError {
+    fn from(error: roxmltree::Error) -> Self {
+        SvgError::Xml(error)
+    }
+}
+
+///
+/// How the `viewBox` fits the viewport when their aspect ratios differ.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AspectRatio {
+    ///
+    /// The alignment of the `viewBox` in the viewport on both axes, 0 for the start, 0.5 for the
+    /// middle and 1 for the end. `None` stretches it to fill the viewport.
+    ///
+    pub align: Option<(f64, f64)>,
+    ///
+    /// Whether the `viewBox` covers the whole viewport (`slice`) instead of fitting in it
+    /// (`meet`).
+    ///
+    pub slice: bool,
+}
+
+impl Default for AspectRatio {
+    fn default() -> Self {
+        AspectRatio {
+            align: Some((0.5, 0.5)),
+            slice: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum GradientShape {
+    Linear {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    ///
+    /// The focal point is left out, radial gradients are concentric.
+    ///
+    Radial {
+        cx: f64,
+        cy: f64,
+        r: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Gradient {
+    pub shape: GradientShape,
+    ///
+    /// Whether the coordinates are fractions of the bounding box of the shape
+    /// (`objectBoundingBox`) rather than user units (`userSpaceOnUse`).
+    ///
+    pub bounding_box_units: bool,
+    pub transform: Transform,
+    ///
+    /// Stop colors with their opacity, the offsets increase from 0 to 1.
+    ///
+    pub stops: Vec<(Color, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Paint {
+    Color(Color),
+    Gradient(Gradient),
+}
+
+///
+/// A path with everything needed to draw it, resolved from the elements and their ancestors.
+///
+#[derive(Clone)]
+pub(crate) struct Shape {
+    pub path: Vec<PathOps>,
+    pub transform: Transform,
+    pub fill: Option<Paint>,
+    pub fill_rule: FillRule,
+    ///
+    /// The fill opacity with the opacity of the element and its groups.
+    ///
+    pub fill_opacity: f64,
+    pub stroke: Option<Paint>,
+    pub stroke_style: StrokeStyle,
+    pub stroke_opacity: f64,
+}
+
+pub struct SvgDocument {
+    ///
+    /// The size of the document in pixels, from the `width` and `height` of the root or its
+    /// `viewBox`.
+    ///
+    pub width: f64,
+    pub height: f64,
+    pub view_box: Option<ViewBox>,
+    pub aspect_ratio: AspectRatio,
+    shapes: Vec<Shape>,
+}
+
+impl SvgDocument {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<SvgDocument, SvgError> {
+        SvgDocument::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<SvgDocument, SvgError> {
+        parse::parse(text)
+    }
+
+    ///
+    /// The number of shapes drawn, elements with neither a fill nor a stroke don't count.
+    ///
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    ///
+    /// Maps the user units of the root to `viewport`, following the `viewBox` and
+    /// `preserveAspectRatio` of the document.
+    ///
+    pub fn viewport_transform(&self, viewport: &ViewBox) -> Transform {
+        let view_box = self.view_box.unwrap_or(ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: self.width,
+            height: self.height,
+        });
+
+        if view_box.width <= 0.0 || view_box.height <= 0.0 {
+            return Transform::translate(viewport.x, viewport.y);
+        }
+
+        let (mut sx, mut sy) = (
+            viewport.width / view_box.width,
+            viewport.height / view_box.height,
+        );
+        let (align_x, align_y) = match self.aspect_ratio.align {
+            Some(align) => {
+                let s = if self.aspect_ratio.slice {
+                    sx.max(sy)
+                } else {
+                    sx.min(sy)
+                };
+                (sx, sy) = (s, s);
+                align
+            }
+            None => (0.0, 0.0),
+        };
+
+        Transform::translate(
+            viewport.x + (viewport.width - view_box.width * sx) * align_x,
+            viewport.y + (viewport.height - view_box.height * sy) * align_y,
+        )
+        .then(&Transform::scale(sx, sy))
+        .then(&Transform::translate(-view_box.x, -view_box.y))
+    }
+}
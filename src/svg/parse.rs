@@ -0,0 +1,631 @@
+//!
+//! Resolving the XML tree of a document into shapes: presentation attributes and `style`
+//! declarations, their inheritance, lengths, references to gradients and `use` elements.
+//!
+use crate::{
+    canvas::ViewBox,
+    color::{Color, FillRule},
+    geometry::PathOps,
+    svg::{
+        path_data::{ellipse, leading_number, numbers, parse_path, rect},
+        AspectRatio, Gradient, GradientShape, LineCap, LineJoin, Paint, Shape, StrokeStyle,
+        SvgDocument, SvgError, Transform,
+    },
+};
+use roxmltree::Node;
+use std::collections::HashMap;
+
+const XLINK: &str = "http://www.w3.org/1999/xlink";
+///
+/// How deep `use` elements can reference each other, references beyond it are cycles.
+///
+const MAX_USE_DEPTH: usize = 16;
+///
+/// How many elements `use` elements can instantiate in total, each level of nested references
+/// multiplies them.
+///
+const MAX_USE_ELEMENTS: usize = 100_000;
+///
+/// The size of a document without any, like browsers.
+///
+const DEFAULT_SIZE: (f64, f64) = (300.0, 150.0);
+
+#[derive(Debug, Clone, PartialEq)]
+enum PaintSpec {
+    None,
+    Color(Color),
+    CurrentColor,
+    ///
+    /// A reference to a gradient, with the paint used when it doesn't exist.
+    ///
+    Url(String, Box<PaintSpec>),
+}
+
+///
+/// The inherited properties.
+///
+#[derive(Debug, Clone)]
+struct Style {
+    color: Color,
+    fill: PaintSpec,
+    fill_opacity: f64,
+    fill_rule: FillRule,
+    stroke: PaintSpec,
+    stroke_opacity: f64,
+    stroke_style: StrokeStyle,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: Color::black(),
+            fill: PaintSpec::Color(Color::black()),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: PaintSpec::None,
+            stroke_opacity: 1.0,
+            stroke_style: StrokeStyle::default(),
+        }
+    }
+}
+
+struct Context<'a, 'input> {
+    ids: HashMap<&'a str, Node<'a, 'input>>,
+    ///
+    /// The size of the viewport in user units, percentages are relative to it.
+    ///
+    viewport: (f64, f64),
+    shapes: Vec<Shape>,
+    ///
+    /// How many more elements `use` elements can instantiate.
+    ///
+    use_budget: usize,
+}
+
+///
+/// Which dimension of the viewport a percentage refers to.
+///
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+    Diagonal,
+}
+
+impl Context<'_, '_> {
+    fn percent_base(&self, axis: Axis) -> f64 {
+        let (width, height) = self.viewport;
+
+        match axis {
+            Axis::X => width,
+            Axis::Y => height,
+            Axis::Diagonal => f64::hypot(width, height) / 2.0_f64.sqrt(),
+        }
+    }
+}
+
+///
+/// A length in user units, `None` when it isn't one. Font relative units assume a 16px font.
+///
+fn length(text: &str, percent_base: f64) -> Option<f64> {
+    let (number, unit) = leading_number(text.trim())?;
+    let factor = match unit.trim_start() {
+        "" | "px" => 1.0,
+        "%" => percent_base / 100.0,
+        "pt" => 4.0 / 3.0,
+        "pc" => 16.0,
+        "mm" => 96.0 / 25.4,
+        "cm" => 96.0 / 2.54,
+        "in" => 96.0,
+        "em" => 16.0,
+        "ex" => 8.0,
+        _ => return None,
+    };
+
+    Some(number * factor)
+}
+
+///
+/// A property from the `style` attribute, which has precedence, or from the presentation
+/// attribute of the same name.
+///
+fn property<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let declared = node.attribute("style").and_then(|style| {
+        style
+            .rsplit(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim())
+    });
+
+    declared
+        .or_else(|| node.attribute(name))
+        .filter(|value| *value != "inherit")
+}
+
+fn opacity(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let value = match text.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().ok()? / 100.0,
+        None => text.parse().ok()?,
+    };
+
+    Some(value.clamp(0.0, 1.0))
+}
+
+fn paint(text: &str) -> Option<PaintSpec> {
+    let text = text.trim();
+
+    if let Some(rest) = text.strip_prefix("url(") {
+        let close = rest.find(')')?;
+        let id = rest[..close].trim().trim_matches(|c| c == '\'' || c == '"');
+        let fallback = match rest[close + 1..].trim() {
+            "" => PaintSpec::None,
+            fallback => paint(fallback)?,
+        };
+        return Some(PaintSpec::Url(
+            id.strip_prefix('#')?.to_string(),
+            Box::new(fallback),
+        ));
+    }
+
+    match text {
+        "none" => Some(PaintSpec::None),
+        "currentColor" => Some(PaintSpec::CurrentColor),
+        _ => Color::parse(text).ok().map(PaintSpec::Color),
+    }
+}
+
+///
+/// The style of `node`, inheriting what it doesn't set from `parent`. Invalid values are
+/// ignored, as if they weren't there.
+///
+fn style(node: &Node, parent: &Style, context: &Context) -> Style {
+    let mut style = parent.clone();
+    let get = |name| property(node, name);
+
+    if let Some(color) = get("color").and_then(|c| Color::parse(c).ok()) {
+        style.color = color;
+    }
+    if let Some(fill) = get("fill").and_then(paint) {
+        style.fill = fill;
+    }
+    if let Some(stroke) = get("stroke").and_then(paint) {
+        style.stroke = stroke;
+    }
+    if let Some(value) = get("fill-opacity").and_then(opacity) {
+        style.fill_opacity = value;
+    }
+    if let Some(value) = get("stroke-opacity").and_then(opacity) {
+        style.stroke_opacity = value;
+    }
+    match get("fill-rule") {
+        Some("nonzero") => style.fill_rule = FillRule::NonZero,
+        Some("evenodd") => style.fill_rule = FillRule::EvenOdd,
+        _ => {}
+    }
+    if let Some(width) = get("stroke-width")
+        .and_then(|width| length(width, context.percent_base(Axis::Diagonal)))
+        .filter(|width| *width >= 0.0)
+    {
+        style.stroke_style.width = width;
+    }
+    match get("stroke-linejoin") {
+        Some("miter") | Some("miter-clip") | Some("arcs") => {
+            style.stroke_style.join = LineJoin::Miter
+        }
+        Some("round") => style.stroke_style.join = LineJoin::Round,
+        Some("bevel") => style.stroke_style.join = LineJoin::Bevel,
+        _ => {}
+    }
+    match get("stroke-linecap") {
+        Some("butt") => style.stroke_style.cap = LineCap::Butt,
+        Some("round") => style.stroke_style.cap = LineCap::Round,
+        Some("square") => style.stroke_style.cap = LineCap::Square,
+        _ => {}
+    }
+    if let Some(limit) = get("stroke-miterlimit")
+        .and_then(|limit| limit.trim().parse::<f64>().ok())
+        .filter(|limit| *limit >= 1.0)
+    {
+        style.stroke_style.miter_limit = limit;
+    }
+
+    style
+}
+
+fn href<'a>(node: &Node<'a, '_>) -> Option<&'a str> {
+    node.attribute("href")
+        .or_else(|| node.attribute((XLINK, "href")))
+        .and_then(|href| href.trim().strip_prefix('#'))
+}
+
+///
+/// A gradient with the attributes and stops it inherits through `href`.
+///
+fn gradient(node: Node, context: &Context) -> Option<Gradient> {
+    // The gradient and the ones it references, closest first.
+    let mut chain = vec![node];
+    while let Some(next) = href(chain.last()?).and_then(|id| context.ids.get(id)) {
+        if chain.len() > MAX_USE_DEPTH || !next.tag_name().name().ends_with("Gradient") {
+            break;
+        }
+        chain.push(*next);
+    }
+    let attribute = |name| chain.iter().find_map(|node| node.attribute(name));
+
+    let bounding_box_units = attribute("gradientUnits") != Some("userSpaceOnUse");
+    let coordinate = |name, axis, default: f64| {
+        let base = if bounding_box_units {
+            1.0
+        } else {
+            context.percent_base(axis)
+        };
+        attribute(name)
+            .and_then(|value| length(value, base))
+            .unwrap_or(default * base)
+    };
+    let shape = if node.tag_name().name() == "linearGradient" {
+        GradientShape::Linear {
+            x1: coordinate("x1", Axis::X, 0.0),
+            y1: coordinate("y1", Axis::Y, 0.0),
+            x2: coordinate("x2", Axis::X, 1.0),
+            y2: coordinate("y2", Axis::Y, 0.0),
+        }
+    } else {
+        GradientShape::Radial {
+            cx: coordinate("cx", Axis::X, 0.5),
+            cy: coordinate("cy", Axis::Y, 0.5),
+            r: coordinate("r", Axis::Diagonal, 0.5),
+        }
+    };
+    let transform = attribute("gradientTransform")
+        .and_then(Transform::parse)
+        .unwrap_or_default();
+
+    // The stops come from the first gradient of the chain that has some.
+    let mut stops = vec![];
+    let with_stops = chain
+        .iter()
+        .find(|node| node.children().any(|child| child.has_tag_name("stop")))?;
+    for stop in with_stops
+        .children()
+        .filter(|child| child.has_tag_name("stop"))
+    {
+        let previous = stops.last().map_or(0.0, |(_, offset)| *offset);
+        let offset = stop
+            .attribute("offset")
+            .and_then(opacity)
+            .unwrap_or(0.0)
+            .max(previous);
+        let mut color = match property(&stop, "stop-color") {
+            Some("currentColor") => style(&stop, &Style::default(), context).color,
+            Some(color) => Color::parse(color).unwrap_or(Color::black()),
+            None => Color::black(),
+        };
+        color.a *= property(&stop, "stop-opacity")
+            .and_then(opacity)
+            .unwrap_or(1.0);
+        stops.push((color, offset));
+    }
+
+    Some(Gradient {
+        shape,
+        bounding_box_units,
+        transform,
+        stops,
+    })
+}
+
+fn resolve_paint(spec: &PaintSpec, style: &Style, context: &Context) -> Option<Paint> {
+    match spec {
+        PaintSpec::None => None,
+        PaintSpec::Color(color) => Some(Paint::Color(*color)),
+        PaintSpec::CurrentColor => Some(Paint::Color(style.color)),
+        PaintSpec::Url(id, fallback) => match context.ids.get(id.as_str()) {
+            Some(node) if node.tag_name().name().ends_with("Gradient") => {
+                let gradient = gradient(*node, context)?;
+                // Gradients without stops paint nothing and with a single one paint a color.
+                match gradient.stops.as_slice() {
+                    [] => None,
+                    [(color, _)] => Some(Paint::Color(*color)),
+                    _ => Some(Paint::Gradient(gradient)),
+                }
+            }
+            _ => resolve_paint(fallback, style, context),
+        },
+    }
+}
+
+///
+/// The outline of a shape element, `None` for other elements and for shapes that aren't drawn,
+/// like a rectangle without a width.
+///
+fn outline(node: &Node, context: &Context) -> Option<Vec<PathOps>> {
+    let get = |name, axis| {
+        node.attribute(name)
+            .and_then(|value| length(value, context.percent_base(axis)))
+    };
+    let zero = |name, axis| get(name, axis).unwrap_or(0.0);
+
+    let path = match node.tag_name().name() {
+        "path" => parse_path(node.attribute("d")?),
+        "rect" => {
+            let (width, height) = (get("width", Axis::X)?, get("height", Axis::Y)?);
+            if width <= 0.0 || height <= 0.0 {
+                return None;
+            }
+            // A missing radius takes the value of the other one.
+            let (rx, ry) = match (get("rx", Axis::X), get("ry", Axis::Y)) {
+                (Some(rx), Some(ry)) => (rx, ry),
+                (Some(r), None) | (None, Some(r)) => (r, r),
+                (None, None) => (0.0, 0.0),
+            };
+            rect(
+                zero("x", Axis::X),
+                zero("y", Axis::Y),
+                width,
+                height,
+                rx,
+                ry,
+            )
+        }
+        "circle" => {
+            let r = get("r", Axis::Diagonal).filter(|r| *r > 0.0)?;
+            ellipse(zero("cx", Axis::X), zero("cy", Axis::Y), r, r)
+        }
+        "ellipse" => {
+            let (rx, ry) = (get("rx", Axis::X)?, get("ry", Axis::Y)?);
+            if rx <= 0.0 || ry <= 0.0 {
+                return None;
+            }
+            ellipse(zero("cx", Axis::X), zero("cy", Axis::Y), rx, ry)
+        }
+        "line" => vec![
+            PathOps::MoveTo {
+                x: zero("x1", Axis::X),
+                y: zero("y1", Axis::Y),
+            },
+            PathOps::LineTo {
+                x: zero("x2", Axis::X),
+                y: zero("y2", Axis::Y),
+            },
+        ],
+        name @ ("polyline" | "polygon") => {
+            let mut coordinates = numbers(node.attribute("points")?).unwrap_or_default();
+            // An odd number is an error, the last coordinate is dropped.
+            coordinates.truncate(coordinates.len() / 2 * 2);
+            let mut path = coordinates
+                .chunks(2)
+                .enumerate()
+                .map(|(i, p)| match i {
+                    0 => PathOps::MoveTo { x: p[0], y: p[1] },
+                    _ => PathOps::LineTo { x: p[0], y: p[1] },
+                })
+                .collect::<Vec<PathOps>>();
+            if name == "polygon" && !path.is_empty() {
+                path.push(PathOps::Close);
+            }
+            path
+        }
+        _ => return None,
+    };
+
+    Some(path)
+}
+
+///
+/// Adds the shapes of `node` and its descendants.
+///
+fn walk(
+    node: Node,
+    parent: &Style,
+    transform: &Transform,
+    opacity_so_far: f64,
+    depth: usize,
+    context: &mut Context,
+) {
+    if !node.is_element()
+        || property(&node, "display") == Some("none")
+        || matches!(
+            node.tag_name().name(),
+            "defs"
+                | "linearGradient"
+                | "radialGradient"
+                | "symbol"
+                | "clipPath"
+                | "mask"
+                | "pattern"
+                | "marker"
+                | "filter"
+                | "style"
+                | "title"
+                | "desc"
+                | "metadata"
+                | "text"
+        )
+    {
+        return;
+    }
+
+    // Elements drawn through a `use` are copies, they're only made while the budget lasts.
+    if depth > 0 {
+        if context.use_budget == 0 {
+            return;
+        }
+        context.use_budget -= 1;
+    }
+
+    let style = style(&node, parent, context);
+    let opacity_so_far =
+        opacity_so_far * property(&node, "opacity").and_then(opacity).unwrap_or(1.0);
+    let mut transform = match node.attribute("transform").map(Transform::parse) {
+        Some(Some(own)) => transform.then(&own),
+        // An invalid transform disables the rendering of the element.
+        Some(None) => return,
+        None => *transform,
+    };
+
+    match node.tag_name().name() {
+        "use" => {
+            let target = match href(&node).and_then(|id| context.ids.get(id)) {
+                Some(target) if depth < MAX_USE_DEPTH => *target,
+                _ => return,
+            };
+            let offset = |name, axis| {
+                node.attribute(name)
+                    .and_then(|value| length(value, context.percent_base(axis)))
+                    .unwrap_or(0.0)
+            };
+            transform = transform.then(&Transform::translate(
+                offset("x", Axis::X),
+                offset("y", Axis::Y),
+            ));
+
+            // Symbols are drawn like groups when they're used.
+            if target.has_tag_name("symbol") {
+                for child in target.children() {
+                    walk(
+                        child,
+                        &style,
+                        &transform,
+                        opacity_so_far,
+                        depth + 1,
+                        context,
+                    );
+                }
+            } else {
+                walk(
+                    target,
+                    &style,
+                    &transform,
+                    opacity_so_far,
+                    depth + 1,
+                    context,
+                );
+            }
+        }
+        "svg" | "g" | "a" | "switch" => {
+            for child in node.children() {
+                walk(child, &style, &transform, opacity_so_far, depth, context);
+            }
+        }
+        _ => {
+            let path = match outline(&node, context) {
+                Some(path) if !path.is_empty() => path,
+                _ => return,
+            };
+            let fill = resolve_paint(&style.fill, &style, context);
+            let stroke = resolve_paint(&style.stroke, &style, context)
+                .filter(|_| style.stroke_style.width > 0.0);
+
+            if fill.is_none() && stroke.is_none() {
+                return;
+            }
+
+            context.shapes.push(Shape {
+                path,
+                transform,
+                fill,
+                fill_rule: style.fill_rule,
+                fill_opacity: style.fill_opacity * opacity_so_far,
+                stroke,
+                stroke_style: style.stroke_style,
+                stroke_opacity: style.stroke_opacity * opacity_so_far,
+            });
+        }
+    }
+}
+
+fn aspect_ratio(text: &str) -> AspectRatio {
+    let mut words = text.split_whitespace();
+    let align = match words.next() {
+        Some("none") => None,
+        Some(align) => {
+            let position = |text: &str| match text {
+                "Min" => Some(0.0),
+                "Mid" => Some(0.5),
+                "Max" => Some(1.0),
+                _ => None,
+            };
+            let (x, y) = match align
+                .strip_prefix('x')
+                .and_then(|rest| rest.split_once('Y'))
+            {
+                Some(positions) => positions,
+                None => return AspectRatio::default(),
+            };
+            match (position(x), position(y)) {
+                (Some(x), Some(y)) => Some((x, y)),
+                _ => return AspectRatio::default(),
+            }
+        }
+        _ => return AspectRatio::default(),
+    };
+
+    AspectRatio {
+        align,
+        slice: words.next() == Some("slice"),
+    }
+}
+
+pub(crate) fn parse(text: &str) -> Result<SvgDocument, SvgError> {
+    let document = roxmltree::Document::parse(text)?;
+    let root = document.root_element();
+
+    if !root.has_tag_name("svg") {
+        return Err(SvgError::NotSvg);
+    }
+
+    let view_box = root
+        .attribute("viewBox")
+        .and_then(numbers)
+        .and_then(|numbers| match numbers.as_slice() {
+            &[x, y, width, height] if width > 0.0 && height > 0.0 => Some(ViewBox {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => None,
+        });
+    // Percentages of the size fall back to the `viewBox`, there's no viewport around the document.
+    let size = |name, view_box_size: Option<f64>, default| {
+        let fallback = view_box_size.unwrap_or(default);
+        root.attribute(name)
+            .and_then(|value| length(value, fallback))
+            .filter(|size| *size > 0.0)
+            .unwrap_or(fallback)
+    };
+    let width = size("width", view_box.map(|v| v.width), DEFAULT_SIZE.0);
+    let height = size("height", view_box.map(|v| v.height), DEFAULT_SIZE.1);
+
+    let mut context = Context {
+        ids: document
+            .descendants()
+            .filter_map(|node| node.attribute("id").map(|id| (id, node)))
+            .collect(),
+        viewport: view_box.map_or((width, height), |v| (v.width, v.height)),
+        shapes: vec![],
+        use_budget: MAX_USE_ELEMENTS,
+    };
+    walk(
+        root,
+        &Style::default(),
+        &Transform::IDENTITY,
+        1.0,
+        0,
+        &mut context,
+    );
+
+    Ok(SvgDocument {
+        width,
+        height,
+        view_box,
+        aspect_ratio: root
+            .attribute("preserveAspectRatio")
+            .map(aspect_ratio)
+            .unwrap_or_default(),
+        shapes: context.shapes,
+    })
+}
@@ -0,0 +1,456 @@
+//!
+//! Path data of the `d` attribute and the outlines of the basic shapes, all turned into absolute
+//! `PathOps` with elliptical arcs approximated by cubic curves.
+//!
+use crate::geometry::{PathOps, Point};
+use std::f64::consts::PI;
+
+///
+/// Reads the numbers, flags and commands of path data, where separators are optional whenever
+/// the next token can't be mistaken for a part of the previous one, like in `M10-5.5.5`.
+///
+struct Scanner<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Scanner {
+            text: text.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.text.get(self.position) {
+            if c.is_ascii_whitespace() || *c == b',' {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.position >= self.text.len()
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.text.get(self.position).copied()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+
+        if c.is_ascii_alphabetic() {
+            self.position += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |scanner: &mut Scanner| {
+            let start = scanner.position;
+            while scanner
+                .text
+                .get(scanner.position)
+                .is_some_and(|c| c.is_ascii_digit())
+            {
+                scanner.position += 1;
+            }
+            scanner.position > start
+        };
+
+        if matches!(self.text.get(self.position), Some(b'+' | b'-')) {
+            self.position += 1;
+        }
+        let mut valid = digits(self);
+        if self.text.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            valid |= digits(self);
+        }
+        if valid && matches!(self.text.get(self.position), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.text.get(self.position), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            // An `e` that isn't followed by an exponent belongs to what comes next, like `em`.
+            if !digits(self) {
+                self.position = mantissa_end;
+            }
+        }
+
+        let number = std::str::from_utf8(&self.text[start..self.position])
+            .ok()
+            .filter(|_| valid)
+            .and_then(|text| text.parse().ok());
+        if number.is_none() {
+            self.position = start;
+        }
+        number
+    }
+
+    ///
+    /// The flags of arcs are single digits that can be written without separators.
+    ///
+    fn flag(&mut self) -> Option<bool> {
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+}
+
+///
+/// The numbers of a list separated by whitespace or commas, like the points of a polygon. `None`
+/// when something else is found.
+///
+pub(crate) fn numbers(text: &str) -> Option<Vec<f64>> {
+    let mut scanner = Scanner::new(text);
+    let mut result = vec![];
+
+    while !scanner.at_end() {
+        result.push(scanner.number()?);
+    }
+
+    Some(result)
+}
+
+///
+/// The number at the start of `text` and what follows it, like the unit of a length.
+///
+pub(crate) fn leading_number(text: &str) -> Option<(f64, &str)> {
+    let mut scanner = Scanner::new(text);
+    let number = scanner.number()?;
+
+    Some((number, &text[scanner.position..]))
+}
+
+///
+/// Parses path data, like SVG renderers it keeps the path up to the first error.
+///
+pub fn parse_path(d: &str) -> Vec<PathOps> {
+    let mut scanner = Scanner::new(d);
+    let mut path = vec![];
+    let mut current = Point { x: 0.0, y: 0.0 };
+    let mut start = current;
+    // The control point of the previous curve, reflected by the smooth curve commands.
+    let mut last_control: Option<(u8, Point)> = None;
+    let mut command = None;
+
+    while !scanner.at_end() {
+        let next = match scanner.command() {
+            Some(c) => c,
+            // Repeated arguments repeat the command, a move then draws lines.
+            None => match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(b'Z' | b'z') | None => break,
+                Some(c) => c,
+            },
+        };
+        command = Some(next);
+        let relative = next.is_ascii_lowercase();
+        let origin = if relative {
+            current
+        } else {
+            Point { x: 0.0, y: 0.0 }
+        };
+        let point = |scanner: &mut Scanner| {
+            let x = scanner.number()?;
+            let y = scanner.number()?;
+            Some(Point {
+                x: origin.x + x,
+                y: origin.y + y,
+            })
+        };
+        let reflected = |kind: &[u8]| match last_control {
+            Some((c, control)) if kind.contains(&c.to_ascii_uppercase()) => Point {
+                x: 2.0 * current.x - control.x,
+                y: 2.0 * current.y - control.y,
+            },
+            _ => current,
+        };
+
+        let mut control = None;
+        let ok = match next.to_ascii_uppercase() {
+            b'M' => point(&mut scanner).map(|p| {
+                path.push(PathOps::MoveTo { x: p.x, y: p.y });
+                current = p;
+                start = p;
+            }),
+            b'L' => point(&mut scanner).map(|p| {
+                path.push(PathOps::LineTo { x: p.x, y: p.y });
+                current = p;
+            }),
+            b'H' => scanner.number().map(|x| {
+                current.x = origin.x + x;
+                path.push(PathOps::LineTo {
+                    x: current.x,
+                    y: current.y,
+                });
+            }),
+            b'V' => scanner.number().map(|y| {
+                current.y = origin.y + y;
+                path.push(PathOps::LineTo {
+                    x: current.x,
+                    y: current.y,
+                });
+            }),
+            b'C' | b'S' => {
+                let p1 = if next.eq_ignore_ascii_case(&b'S') {
+                    Some(reflected(b"CS"))
+                } else {
+                    point(&mut scanner)
+                };
+                p1.zip(point(&mut scanner))
+                    .zip(point(&mut scanner))
+                    .map(|((p1, p2), p3)| {
+                        path.push(PathOps::CubicTo {
+                            x1: p1.x,
+                            y1: p1.y,
+                            x2: p2.x,
+                            y2: p2.y,
+                            x3: p3.x,
+                            y3: p3.y,
+                        });
+                        control = Some(p2);
+                        current = p3;
+                    })
+            }
+            b'Q' | b'T' => {
+                let p1 = if next.eq_ignore_ascii_case(&b'T') {
+                    Some(reflected(b"QT"))
+                } else {
+                    point(&mut scanner)
+                };
+                p1.zip(point(&mut scanner)).map(|(p1, p2)| {
+                    path.push(PathOps::QuadTo {
+                        x1: p1.x,
+                        y1: p1.y,
+                        x2: p2.x,
+                        y2: p2.y,
+                    });
+                    control = Some(p1);
+                    current = p2;
+                })
+            }
+            b'A' => (|| {
+                let (rx, ry) = (scanner.number()?, scanner.number()?);
+                let rotation = scanner.number()?;
+                let (large_arc, sweep) = (scanner.flag()?, scanner.flag()?);
+                let end = point(&mut scanner)?;
+                arc(&mut path, current, rx, ry, rotation, large_arc, sweep, end);
+                current = end;
+                Some(())
+            })(),
+            b'Z' => {
+                path.push(PathOps::Close);
+                current = start;
+                Some(())
+            }
+            _ => None,
+        };
+
+        if ok.is_none() {
+            break;
+        }
+        last_control = control.map(|control| (next, control));
+    }
+
+    path
+}
+
+///
+/// Appends the elliptical arc from `from` to `to` as cubic curves of at most a quarter turn,
+/// following the conversion of the SVG implementation notes.
+///
+#[allow(clippy::too_many_arguments)]
+fn arc(
+    path: &mut Vec<PathOps>,
+    from: Point,
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    to: Point,
+) {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+
+    if rx == 0.0 || ry == 0.0 || (from.x == to.x && from.y == to.y) {
+        if from.x != to.x || from.y != to.y {
+            path.push(PathOps::LineTo { x: to.x, y: to.y });
+        }
+        return;
+    }
+
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((from.x - to.x) / 2.0, (from.y - to.y) / 2.0);
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    // Radii too small to reach the end are scaled up.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut factor = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        factor = -factor;
+    }
+    let (cx1, cy1) = (factor * rx * y1 / ry, -factor * ry * x1 / rx);
+    let center = Point {
+        x: cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0,
+        y: sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0,
+    };
+
+    let angle = |ux: f64, uy: f64| f64::atan2(uy, ux);
+    let start = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start;
+    if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    } else if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    }
+
+    let count = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = delta / count as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    // A point of the ellipse and its derivative by the angle.
+    let at = |theta: f64| {
+        let (sin_t, cos_t) = theta.sin_cos();
+        let (x, y) = (rx * cos_t, ry * sin_t);
+        let (tx, ty) = (-rx * sin_t, ry * cos_t);
+        (
+            Point {
+                x: center.x + cos * x - sin * y,
+                y: center.y + sin * x + cos * y,
+            },
+            Point {
+                x: cos * tx - sin * ty,
+                y: sin * tx + cos * ty,
+            },
+        )
+    };
+
+    for i in 0..count {
+        let (p0, d0) = at(start + step * i as f64);
+        let (p3, d3) = at(start + step * (i + 1) as f64);
+        // The last point is exactly the end of the arc.
+        let p3 = if i + 1 == count { to } else { p3 };
+
+        path.push(PathOps::CubicTo {
+            x1: p0.x + k * d0.x,
+            y1: p0.y + k * d0.y,
+            x2: p3.x - k * d3.x,
+            y2: p3.y - k * d3.y,
+            x3: p3.x,
+            y3: p3.y,
+        });
+    }
+}
+
+///
+/// An ellipse made of 4 arcs, starting from its rightmost point.
+///
+pub(crate) fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<PathOps> {
+    let mut path = vec![PathOps::MoveTo { x: cx + rx, y: cy }];
+    let points = [
+        Point { x: cx, y: cy + ry },
+        Point { x: cx - rx, y: cy },
+        Point { x: cx, y: cy - ry },
+        Point { x: cx + rx, y: cy },
+    ];
+    let mut from = Point { x: cx + rx, y: cy };
+
+    for to in points {
+        arc(&mut path, from, rx, ry, 0.0, false, true, to);
+        from = to;
+    }
+    path.push(PathOps::Close);
+
+    path
+}
+
+///
+/// A rectangle with corners rounded by quarters of an ellipse, the radii are at most half the
+/// sides.
+///
+pub(crate) fn rect(x: f64, y: f64, width: f64, height: f64, rx: f64, ry: f64) -> Vec<PathOps> {
+    let (rx, ry) = (rx.min(width / 2.0), ry.min(height / 2.0));
+
+    if rx <= 0.0 || ry <= 0.0 {
+        return vec![
+            PathOps::MoveTo { x, y },
+            PathOps::LineTo { x: x + width, y },
+            PathOps::LineTo {
+                x: x + width,
+                y: y + height,
+            },
+            PathOps::LineTo { x, y: y + height },
+            PathOps::Close,
+        ];
+    }
+
+    let mut path = vec![PathOps::MoveTo { x: x + rx, y }];
+    let corners = [
+        (
+            Point {
+                x: x + width - rx,
+                y,
+            },
+            Point {
+                x: x + width,
+                y: y + ry,
+            },
+        ),
+        (
+            Point {
+                x: x + width,
+                y: y + height - ry,
+            },
+            Point {
+                x: x + width - rx,
+                y: y + height,
+            },
+        ),
+        (
+            Point {
+                x: x + rx,
+                y: y + height,
+            },
+            Point {
+                x,
+                y: y + height - ry,
+            },
+        ),
+        (Point { x, y: y + ry }, Point { x: x + rx, y }),
+    ];
+
+    for (line_end, arc_end) in corners {
+        path.push(PathOps::LineTo {
+            x: line_end.x,
+            y: line_end.y,
+        });
+        arc(&mut path, line_end, rx, ry, 0.0, false, true, arc_end);
+    }
+    path.push(PathOps::Close);
+
+    path
+}
@@ -0,0 +1,269 @@
+//!
+//! Drawing the shapes of a document: fills, strokes turned into fills, and gradients laid out in
+//! the pixels of the canvas.
+//!
+use crate::{
+    canvas::Canvas,
+    color::{Color, FillRule, FillStyle},
+    geometry::{BoundingBox, Path, PathOps, Point},
+    math::map_viewbox,
+    renderer::{fill_coverage, render_path, sweep_coverage, RenderState},
+    svg::{stroke, Gradient, GradientShape, Paint, SvgDocument, Transform},
+};
+
+///
+/// The bounding box of the points of a path in user units, the control points of curves
+/// included. `None` for a path without points.
+///
+fn user_bounds(path: Path) -> Option<(Point, Point)> {
+    let mut points = path.iter().flat_map(|op| match *op {
+        PathOps::MoveTo { x, y } | PathOps::LineTo { x, y } => vec![Point { x, y }],
+        PathOps::QuadTo { x1, y1, x2, y2 } => vec![Point { x: x1, y: y1 }, Point { x: x2, y: y2 }],
+        PathOps::CubicTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3,
+        } => vec![
+            Point { x: x1, y: y1 },
+            Point { x: x2, y: y2 },
+            Point { x: x3, y: y3 },
+        ],
+        _ => vec![],
+    });
+    let first = points.next()?;
+
+    Some(points.fold((first, first), |(min, max), p| {
+        (
+            Point {
+                x: min.x.min(p.x),
+                y: min.y.min(p.y),
+            },
+            Point {
+                x: max.x.max(p.x),
+                y: max.y.max(p.y),
+            },
+        )
+    }))
+}
+
+///
+/// `path` with its open subpaths closed, SVG fills every subpath as if it was closed.
+///
+fn close_subpaths(path: Path) -> Vec<PathOps> {
+    let mut result = Vec::with_capacity(path.len() + 1);
+    let mut open = false;
+
+    for op in path {
+        match op {
+            PathOps::MoveTo { .. } if open => result.push(PathOps::Close),
+            PathOps::Close => open = false,
+            PathOps::MoveTo { .. } => {}
+            _ => open = true,
+        }
+        result.push(*op);
+    }
+    if open {
+        result.push(PathOps::Close);
+    }
+
+    result
+}
+
+///
+/// The fill style of a gradient and the pixels it's laid out over. `to_device` maps user units to
+/// the pixels of the canvas. Like `FillStyle` gradients always pad, and a transform that skews
+/// the gradient only moves its ends. The renderer samples gradients at the corner of pixels and
+/// SVG at their center, the gradient is moved by half a pixel to make up for it.
+///
+fn gradient_fill_style<'a>(
+    gradient: &Gradient,
+    bounds: Option<(Point, Point)>,
+    to_device: impl Fn(&Point) -> Point,
+    stops: &'a mut Vec<(Color, f64)>,
+) -> Option<(FillStyle<'a>, BoundingBox)> {
+    let units = match (gradient.bounding_box_units, bounds) {
+        (false, _) => Transform::IDENTITY,
+        (true, Some((min, max))) if max.x > min.x && max.y > min.y => {
+            Transform::translate(min.x, min.y).then(&Transform::scale(max.x - min.x, max.y - min.y))
+        }
+        // Bounding box units need an area.
+        (true, _) => return None,
+    };
+    let to_gradient = units.then(&gradient.transform);
+    let at = |x: f64, y: f64| to_device(&to_gradient.apply(&Point { x, y }));
+
+    stops.extend(gradient.stops.iter().copied());
+
+    match gradient.shape {
+        GradientShape::Linear { x1, y1, x2, y2 } => {
+            let (start, end) = (at(x1, y1), at(x2, y2));
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let distance = f64::hypot(dx, dy);
+
+            if distance < 1e-6 {
+                return None;
+            }
+
+            let center = Point {
+                x: (start.x + end.x) / 2.0 - 0.5,
+                y: (start.y + end.y) / 2.0 - 0.5,
+            };
+            Some((
+                FillStyle::LinearGradient {
+                    stops,
+                    angle: crate::math::Angle::from_radians(f64::atan2(-dy, -dx)),
+                },
+                BoundingBox::square(center, distance / 2.0),
+            ))
+        }
+        GradientShape::Radial { cx, cy, r } => {
+            let center = at(cx, cy);
+            let (x, y) = (at(cx + r, cy), at(cx, cy + r));
+            // The radius in pixels, the average of both axes when they're scaled differently.
+            let radius = ((x.x - center.x) * (y.y - center.y)
+                - (x.y - center.y) * (y.x - center.x))
+                .abs()
+                .sqrt();
+
+            if radius < 1e-6 {
+                return None;
+            }
+
+            Some((
+                FillStyle::RadialGradient {
+                    stops,
+                    translation: Point { x: 0.0, y: 0.0 },
+                },
+                BoundingBox::square(
+                    Point {
+                        x: center.x - 0.5,
+                        y: center.y - 0.5,
+                    },
+                    radius,
+                ),
+            ))
+        }
+    }
+}
+
+///
+/// Fills `path` with `paint`. The coverage is computed first since gradients aren't laid out over
+/// the bounds of the shape, and scaled by the opacity.
+///
+#[allow(clippy::too_many_arguments)]
+fn fill(
+    canvas: &mut Canvas,
+    path: Path,
+    transform: &Transform,
+    paint: &Paint,
+    fill_rule: FillRule,
+    opacity: f64,
+    bounds: Option<(Point, Point)>,
+) {
+    let desc = canvas.desc;
+    let to_device = |p: &Point| map_viewbox(&desc, &transform.apply(p));
+    let mut stops = vec![];
+    let (fill_style, opacity, gradient_bounds) = match paint {
+        Paint::Color(color) => (
+            FillStyle::Plain(Color { a: 1.0, ..*color }),
+            opacity * color.a,
+            None,
+        ),
+        Paint::Gradient(gradient) => {
+            match gradient_fill_style(gradient, bounds, to_device, &mut stops) {
+                Some((fill_style, bounds)) => (fill_style, opacity, Some(bounds)),
+                // Gradients that can't be laid out paint their last color.
+                None => {
+                    let color = gradient
+                        .stops
+                        .last()
+                        .map_or(Color::default(), |stop| stop.0);
+                    (
+                        FillStyle::Plain(Color { a: 1.0, ..color }),
+                        opacity * color.a,
+                        None,
+                    )
+                }
+            }
+        }
+    };
+
+    let mut state = RenderState {
+        desc: &desc,
+        accumulation_buffer: &mut canvas.accumulation_buffer,
+    };
+    let region = render_path(&mut state, &close_subpaths(path), |p| transform.apply(p));
+    let (width, height) = (
+        (region.max_x - region.min_x + 1).max(0) as usize,
+        (region.max_y - region.min_y + 1).max(0) as usize,
+    );
+    let mut coverage = vec![0.0_f32; width * height];
+    sweep_coverage(&mut state, fill_rule, &region, |x, y, alpha| {
+        let (x, y) = (x as i64 - region.min_x, y as i64 - region.min_y);
+        coverage[y as usize * width + x as usize] = alpha;
+    });
+
+    let gradient_bounds = gradient_bounds.unwrap_or(BoundingBox {
+        min_x: region.min_x,
+        min_y: region.min_y,
+        max_x: region.max_x,
+        max_y: region.max_y,
+    });
+    fill_coverage(canvas, fill_style, &gradient_bounds, &region, |x, y| {
+        let (x, y) = (x as i64 - region.min_x, y as i64 - region.min_y);
+        coverage[y as usize * width + x as usize] * opacity as f32
+    });
+}
+
+impl Canvas {
+    ///
+    /// Draws `document` with its `viewBox` fitted to the viewbox of the canvas, so a canvas
+    /// whose viewbox is the size of the document draws it at its size.
+    ///
+    /// Opacity is applied to each shape rather than to groups as a whole, stop opacity is left
+    /// out and radial gradients are concentric around their center.
+    ///
+    pub fn draw_svg(&mut self, document: &SvgDocument) {
+        let viewport = document.viewport_transform(&self.desc.viewbox);
+        let pixels_per_unit = f64::sqrt(
+            (self.desc.width as f64 / self.desc.viewbox.width)
+                * (self.desc.height as f64 / self.desc.viewbox.height),
+        );
+
+        for shape in &document.shapes {
+            let transform = viewport.then(&shape.transform);
+            let bounds = user_bounds(&shape.path);
+
+            if let Some(paint) = &shape.fill {
+                fill(
+                    self,
+                    &shape.path,
+                    &transform,
+                    paint,
+                    shape.fill_rule,
+                    shape.fill_opacity,
+                    bounds,
+                );
+            }
+            if let Some(paint) = &shape.stroke {
+                // The stroke is flattened in user units, finely enough once it's scaled.
+                let scale = transform.scale_factor() * pixels_per_unit;
+                let tolerance = self.desc.tolerance / scale.max(1e-9);
+                let outline = stroke(&shape.path, &shape.stroke_style, tolerance);
+
+                fill(
+                    self,
+                    &outline,
+                    &transform,
+                    paint,
+                    FillRule::NonZero,
+                    shape.stroke_opacity,
+                    bounds,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//!
+//! Turns strokes into paths that are filled: the path is flattened and every line, join and cap
+//! becomes a small polygon. They all wind the same way, so filled with `FillRule::NonZero` they
+//! cover their union once.
+//!
+use crate::geometry::{flatten, Path, PathOps, Point};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f64,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    ///
+    /// The longest miter allowed, relative to the width, longer ones are beveled.
+    ///
+    pub miter_limit: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            width: 1.0,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            miter_limit: 4.0,
+        }
+    }
+}
+
+fn add(p: Point, v: Point, factor: f64) -> Point {
+    Point {
+        x: p.x + v.x * factor,
+        y: p.y + v.y * factor,
+    }
+}
+
+///
+/// Appends a closed polygon, reversed if needed so it winds clockwise on the canvas.
+///
+fn polygon(path: &mut Vec<PathOps>, points: &[Point]) {
+    let area = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(p0, p1)| p0.x * p1.y - p1.x * p0.y)
+        .sum::<f64>();
+
+    if area == 0.0 {
+        return;
+    }
+
+    let mut ordered = points.to_vec();
+    if area < 0.0 {
+        ordered.reverse();
+    }
+    path.push(PathOps::MoveTo {
+        x: ordered[0].x,
+        y: ordered[0].y,
+    });
+    path.extend(
+        ordered[1..]
+            .iter()
+            .map(|p| PathOps::LineTo { x: p.x, y: p.y }),
+    );
+    path.push(PathOps::Close);
+}
+
+///
+/// A circle as a polygon close enough to it for `tolerance`.
+///
+fn circle(path: &mut Vec<PathOps>, center: Point, radius: f64, tolerance: f64) {
+    let step = 2.0 * f64::acos((1.0 - tolerance / radius).clamp(-1.0, 1.0));
+    let count = ((2.0 * PI / step.max(1e-3)).ceil() as usize).clamp(8, 256);
+    let points = (0..count)
+        .map(|i| {
+            let (sin, cos) = (2.0 * PI * i as f64 / count as f64).sin_cos();
+            Point {
+                x: center.x + radius * cos,
+                y: center.y + radius * sin,
+            }
+        })
+        .collect::<Vec<Point>>();
+
+    polygon(path, &points);
+}
+
+///
+/// The outline of the stroke of `path`, with curves flattened within `tolerance`.
+///
+pub fn stroke(path: Path, style: &StrokeStyle, tolerance: f64) -> Vec<PathOps> {
+    let half = style.width / 2.0;
+    let mut result = vec![];
+
+    if half <= 0.0 {
+        return result;
+    }
+
+    for polyline in flatten(path, tolerance) {
+        let mut points = polyline.points;
+        points.dedup_by(|a, b| a.distance_to(b) < 1e-9);
+        if polyline.closed
+            && points.len() > 1
+            && points[0].distance_to(&points[points.len() - 1]) < 1e-9
+        {
+            points.pop();
+        }
+
+        // A subpath without length only shows its caps, as a dot.
+        if points.len() == 1 {
+            let p = points[0];
+            match style.cap {
+                LineCap::Butt => {}
+                LineCap::Round => circle(&mut result, p, half, tolerance),
+                LineCap::Square => polygon(
+                    &mut result,
+                    &[
+                        Point {
+                            x: p.x - half,
+                            y: p.y - half,
+                        },
+                        Point {
+                            x: p.x + half,
+                            y: p.y - half,
+                        },
+                        Point {
+                            x: p.x + half,
+                            y: p.y + half,
+                        },
+                        Point {
+                            x: p.x - half,
+                            y: p.y + half,
+                        },
+                    ],
+                ),
+            }
+            continue;
+        }
+
+        let closed = polyline.closed && points.len() > 2;
+        let count = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        let line = |i: usize| (points[i], points[(i + 1) % points.len()]);
+        let direction = |i: usize| {
+            let (p0, p1) = line(i);
+            let length = p0.distance_to(&p1);
+            Point {
+                x: (p1.x - p0.x) / length,
+                y: (p1.y - p0.y) / length,
+            }
+        };
+        let normal = |d: Point| Point { x: -d.y, y: d.x };
+
+        for i in 0..count {
+            let (p0, p1) = line(i);
+            let n = normal(direction(i));
+            polygon(
+                &mut result,
+                &[
+                    add(p0, n, half),
+                    add(p1, n, half),
+                    add(p1, n, -half),
+                    add(p0, n, -half),
+                ],
+            );
+        }
+
+        // The joins between consecutive lines.
+        let joins = if closed { 0..count } else { 1..count };
+        for i in joins {
+            let (before, after) = (direction((i + count - 1) % count), direction(i));
+            let p = points[i];
+            let cross = before.x * after.y - before.y * after.x;
+            let dot = before.x * after.x + before.y * after.y;
+
+            if cross.abs() < 1e-12 && dot > 0.0 {
+                continue;
+            }
+            // The outer side of the turn.
+            let side = if cross > 0.0 { -half } else { half };
+            let (n0, n1) = (normal(before), normal(after));
+            let (a, b) = (add(p, n0, side), add(p, n1, side));
+
+            match style.join {
+                LineJoin::Round => circle(&mut result, p, half, tolerance),
+                LineJoin::Bevel => polygon(&mut result, &[p, a, b]),
+                LineJoin::Miter => {
+                    // The miter length relative to the width is 1 / sin(theta / 2), theta being
+                    // the angle between the lines.
+                    let cos_theta = -dot;
+                    let sin_half = ((1.0 - cos_theta) / 2.0).max(0.0).sqrt();
+
+                    if sin_half > 1e-9 && 1.0 / sin_half <= style.miter_limit {
+                        let bisector = Point {
+                            x: n0.x + n1.x,
+                            y: n0.y + n1.y,
+                        };
+                        let length = f64::hypot(bisector.x, bisector.y);
+                        let tip = add(p, bisector, side / (sin_half * length));
+                        polygon(&mut result, &[p, a, tip, b]);
+                    } else {
+                        polygon(&mut result, &[p, a, b]);
+                    }
+                }
+            }
+        }
+
+        if !closed {
+            let ends = [
+                (points[0], direction(0), -1.0),
+                (points[points.len() - 1], direction(count - 1), 1.0),
+            ];
+
+            for (p, d, outward) in ends {
+                let n = normal(d);
+                match style.cap {
+                    LineCap::Butt => {}
+                    LineCap::Round => circle(&mut result, p, half, tolerance),
+                    LineCap::Square => {
+                        let q = add(p, d, outward * half);
+                        polygon(
+                            &mut result,
+                            &[
+                                add(p, n, half),
+                                add(q, n, half),
+                                add(q, n, -half),
+                                add(p, n, -half),
+                            ],
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,133 @@
+//!
+//! Affine transforms of SVG documents and the parser of the `transform` attribute.
+//!
+use crate::{geometry::Point, svg::path_data::numbers};
+
+///
+/// The matrix `[a c e; b d f; 0 0 1]`, in the order of the SVG `matrix()` function.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub fn translate(x: f64, y: f64) -> Transform {
+        Transform {
+            e: x,
+            f: y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    pub fn scale(x: f64, y: f64) -> Transform {
+        Transform {
+            a: x,
+            d: y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    ///
+    /// A rotation by `degrees`, clockwise since y points down.
+    ///
+    pub fn rotate(degrees: f64) -> Transform {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+
+        Transform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Transform::IDENTITY
+        }
+    }
+
+    ///
+    /// `other` applied first, then `self`.
+    ///
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    pub fn apply(&self, p: &Point) -> Point {
+        Point {
+            x: self.a * p.x + self.c * p.y + self.e,
+            y: self.b * p.x + self.d * p.y + self.f,
+        }
+    }
+
+    ///
+    /// How much the transform scales lengths, the geometric mean of both axes.
+    ///
+    pub fn scale_factor(&self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+
+    ///
+    /// Parses a transform list like `translate(10 20) rotate(45)`, `None` when it's malformed.
+    ///
+    pub fn parse(text: &str) -> Option<Transform> {
+        let mut result = Transform::IDENTITY;
+        let mut rest = text.trim();
+
+        while !rest.is_empty() {
+            let open = rest.find('(')?;
+            let close = open + rest[open..].find(')')?;
+            let name = rest[..open].trim();
+            let arguments = numbers(&rest[open + 1..close])?;
+            let transform = match (name, arguments.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => Transform { a, b, c, d, e, f },
+                ("translate", &[x]) => Transform::translate(x, 0.0),
+                ("translate", &[x, y]) => Transform::translate(x, y),
+                ("scale", &[s]) => Transform::scale(s, s),
+                ("scale", &[x, y]) => Transform::scale(x, y),
+                ("rotate", &[angle]) => Transform::rotate(angle),
+                ("rotate", &[angle, x, y]) => Transform::translate(x, y)
+                    .then(&Transform::rotate(angle))
+                    .then(&Transform::translate(-x, -y)),
+                ("skewX", &[angle]) => Transform {
+                    c: angle.to_radians().tan(),
+                    ..Transform::IDENTITY
+                },
+                ("skewY", &[angle]) => Transform {
+                    b: angle.to_radians().tan(),
+                    ..Transform::IDENTITY
+                },
+                _ => return None,
+            };
+
+            result = result.then(&transform);
+            rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        }
+
+        Some(result)
+    }
+}
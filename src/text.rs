@@ -0,0 +1,434 @@
+//!
+//! Loading TrueType and OpenType fonts: glyph outlines as paths, ready for `Canvas::draw_shape`,
+//! and the metrics needed to lay them out.
+//!
+//! Everything is in font units with y pointing down, like the canvas, and the origin of a glyph on
+//! the baseline. `Font::scale` gives the factor that turns font units into pixels for a font size.
+//!
+use crate::geometry::PathOps;
+pub use atlas::{AtlasEntry, GlyphAtlas, GlyphKey};
+pub use layout::{layout_text, Baseline, PositionedGlyph, TextAlign, TextLayout};
+pub use on_path::TextPath;
+pub use rendering::{stem_darkening_amount, TextRendering};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use ttf::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser as ttf;
+
+mod atlas;
+mod color;
+mod layout;
+mod on_path;
+mod rendering;
+
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    Parse(ttf::FaceParsingError),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(error) => write!(f, "cannot read the font: {}", error),
+            FontError::Parse(error) => write!(f, "cannot parse the font: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<io::Error> for FontError {
+    fn from(error: io::Error) -> Self {
+        FontError::Io(error)
+    }
+}
+
+impl From<ttf::FaceParsingError> for FontError {
+    fn from(error: ttf::FaceParsingError) -> Self {
+        FontError::Parse(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GlyphId(pub u16);
+
+///
+/// Metrics shared by all glyphs, in font units. The ascender is negative since y points down.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    pub units_per_em: f64,
+    pub ascender: f64,
+    pub descender: f64,
+    pub line_gap: f64,
+    ///
+    /// The distance between two baselines.
+    ///
+    pub line_height: f64,
+    pub x_height: Option<f64>,
+    pub cap_height: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphBounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphMetrics {
+    pub advance: f64,
+    pub left_side_bearing: f64,
+    ///
+    /// `None` for glyphs without an outline, like the space.
+    ///
+    pub bounds: Option<GlyphBounds>,
+}
+
+///
+/// An axis of a variable font, like the weight (`wght`) or the width (`wdth`), with its range in
+/// the units of the axis.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariationAxis {
+    pub tag: [u8; 4],
+    pub min: f64,
+    pub default: f64,
+    pub max: f64,
+    ///
+    /// Axes the font designer doesn't expect to be shown in a user interface.
+    ///
+    pub hidden: bool,
+}
+
+pub struct Font {
+    ///
+    /// Tells fonts apart in caches shared between them, like `GlyphAtlas`. It changes with the
+    /// variations since the glyphs change too.
+    ///
+    id: u64,
+    data: Vec<u8>,
+    index: u32,
+    ///
+    /// The axes set with `set_variation`, the others are at their default.
+    ///
+    variations: Vec<([u8; 4], f64)>,
+    ///
+    /// Outlines are converted the first time they are requested, glyphs without one are cached
+    /// as empty paths.
+    ///
+    outlines: HashMap<GlyphId, Vec<PathOps>>,
+}
+
+struct OutlineBuilder<'a> {
+    path_ops: &'a mut Vec<PathOps>,
+}
+
+impl ttf::OutlineBuilder for OutlineBuilder<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path_ops.push(PathOps::MoveTo {
+            x: x as f64,
+            y: -y as f64,
+        });
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path_ops.push(PathOps::LineTo {
+            x: x as f64,
+            y: -y as f64,
+        });
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.path_ops.push(PathOps::QuadTo {
+            x1: x1 as f64,
+            y1: -y1 as f64,
+            x2: x as f64,
+            y2: -y as f64,
+        });
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path_ops.push(PathOps::CubicTo {
+            x1: x1 as f64,
+            y1: -y1 as f64,
+            x2: x2 as f64,
+            y2: -y2 as f64,
+            x3: x as f64,
+            y3: -y as f64,
+        });
+    }
+
+    fn close(&mut self) {
+        self.path_ops.push(PathOps::Close);
+    }
+}
+
+impl Font {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Font, FontError> {
+        Font::from_bytes(fs::read(path)?, 0)
+    }
+
+    ///
+    /// `index` selects a font in a collection (.ttc), it's 0 for regular font files.
+    ///
+    pub fn from_bytes(data: Vec<u8>, index: u32) -> Result<Font, FontError> {
+        ttf::Face::parse(&data, index)?;
+
+        Ok(Font {
+            id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            data,
+            index,
+            variations: vec![],
+            outlines: HashMap::new(),
+        })
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    ///
+    /// Parsing only reads the table directory, so the face isn't kept around to avoid borrowing
+    /// `data` from the same struct.
+    ///
+    fn face(&self) -> ttf::Face<'_> {
+        let mut face =
+            ttf::Face::parse(&self.data, self.index).expect("the font was parsed when loaded");
+
+        for (tag, value) in &self.variations {
+            face.set_variation(ttf::Tag::from_bytes(tag), *value as f32);
+        }
+
+        face
+    }
+
+    ///
+    /// The axes of a variable font, empty for other fonts.
+    ///
+    pub fn variation_axes(&self) -> Vec<VariationAxis> {
+        self.face()
+            .variation_axes()
+            .into_iter()
+            .map(|axis| VariationAxis {
+                tag: axis.tag.to_bytes(),
+                min: axis.min_value as f64,
+                default: axis.def_value as f64,
+                max: axis.max_value as f64,
+                hidden: axis.hidden,
+            })
+            .collect()
+    }
+
+    ///
+    /// Moves a variation axis to `value`, clamped to its range. Outlines, advances, metrics and
+    /// color glyphs follow, so a weight can be animated with a single font. Returns `false` when
+    /// the font has no such axis.
+    ///
+    pub fn set_variation(&mut self, tag: &[u8; 4], value: f64) -> bool {
+        let axis = match self
+            .variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag == *tag)
+        {
+            Some(axis) => axis,
+            None => return false,
+        };
+        let value = value.clamp(axis.min, axis.max);
+
+        match self.variations.iter_mut().find(|(t, _)| t == tag) {
+            Some((_, v)) if *v == value => return true,
+            Some((_, v)) => *v = value,
+            None => self.variations.push((*tag, value)),
+        }
+
+        self.id = NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed);
+        self.outlines.clear();
+        true
+    }
+
+    ///
+    /// The value of a variation axis, `None` when the font has no such axis.
+    ///
+    pub fn variation(&self, tag: &[u8; 4]) -> Option<f64> {
+        let axis = self
+            .variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag == *tag)?;
+
+        Some(
+            self.variations
+                .iter()
+                .find(|(t, _)| t == tag)
+                .map_or(axis.default, |(_, value)| *value),
+        )
+    }
+
+    ///
+    /// The factor that converts font units to pixels for `size` pixels per em.
+    ///
+    pub fn scale(&self, size: f64) -> f64 {
+        size / self.face().units_per_em() as f64
+    }
+
+    pub fn metrics(&self) -> FontMetrics {
+        let face = self.face();
+        let (ascender, descender, line_gap) = (
+            face.ascender() as f64,
+            face.descender() as f64,
+            face.line_gap() as f64,
+        );
+
+        FontMetrics {
+            units_per_em: face.units_per_em() as f64,
+            ascender: -ascender,
+            descender: -descender,
+            line_gap,
+            line_height: ascender - descender + line_gap,
+            x_height: face.x_height().map(|height| -height as f64),
+            cap_height: face.capital_height().map(|height| -height as f64),
+        }
+    }
+
+    pub fn glyph_index(&self, c: char) -> Option<GlyphId> {
+        self.face().glyph_index(c).map(|glyph| GlyphId(glyph.0))
+    }
+
+    pub fn num_glyphs(&self) -> u16 {
+        self.face().number_of_glyphs()
+    }
+
+    pub fn glyph_metrics(&self, glyph: GlyphId) -> GlyphMetrics {
+        let face = self.face();
+        let id = ttf::GlyphId(glyph.0);
+
+        GlyphMetrics {
+            advance: face.glyph_hor_advance(id).unwrap_or(0) as f64,
+            left_side_bearing: face.glyph_hor_side_bearing(id).unwrap_or(0) as f64,
+            bounds: face.glyph_bounding_box(id).map(|rect| GlyphBounds {
+                min_x: rect.x_min as f64,
+                min_y: -rect.y_max as f64,
+                max_x: rect.x_max as f64,
+                max_y: -rect.y_min as f64,
+            }),
+        }
+    }
+
+    ///
+    /// The horizontal adjustment between two glyphs, from the pair adjustments of the GPOS `kern`
+    /// feature or else from the legacy `kern` table.
+    ///
+    pub fn kerning(&self, left: GlyphId, right: GlyphId) -> f64 {
+        let face = self.face();
+        let (left, right) = (ttf::GlyphId(left.0), ttf::GlyphId(right.0));
+
+        gpos_kerning(&face, left, right)
+            .or_else(|| kern_table_kerning(&face, left, right))
+            .unwrap_or(0) as f64
+    }
+
+    ///
+    /// The outline of a glyph, empty for glyphs that have none. Quadratic curves of TrueType
+    /// outlines are kept as `QuadTo`, CFF outlines are made of `CubicTo`.
+    ///
+    pub fn outline(&mut self, glyph: GlyphId) -> &[PathOps] {
+        if !self.outlines.contains_key(&glyph) {
+            let mut path_ops = vec![];
+            self.face().outline_glyph(
+                ttf::GlyphId(glyph.0),
+                &mut OutlineBuilder {
+                    path_ops: &mut path_ops,
+                },
+            );
+            self.outlines.insert(glyph, path_ops);
+        }
+
+        &self.outlines[&glyph]
+    }
+
+    ///
+    /// Whether the glyph is painted with colored layers from the `COLR` table, like emoji.
+    /// `Canvas::draw_text` draws these glyphs in their colors instead of the fill style.
+    ///
+    pub fn is_color_glyph(&self, glyph: GlyphId) -> bool {
+        self.face().is_color_glyph(ttf::GlyphId(glyph.0))
+    }
+
+    ///
+    /// The number of `CPAL` palettes color glyphs can be drawn with, 0 for fonts without color
+    /// glyphs.
+    ///
+    pub fn color_palettes(&self) -> u16 {
+        self.face()
+            .color_palettes()
+            .map_or(0, |palettes| palettes.get())
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.outlines.clear();
+    }
+}
+
+///
+/// Lookups of the `kern` feature add up, within a lookup the first subtable covering the pair is
+/// used.
+///
+fn gpos_kerning(face: &ttf::Face, left: ttf::GlyphId, right: ttf::GlyphId) -> Option<i16> {
+    let gpos = face.tables().gpos?;
+    let mut lookups = gpos
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == ttf::Tag::from_bytes(b"kern"))
+        .flat_map(|feature| feature.lookup_indices)
+        .collect::<Vec<u16>>();
+    lookups.sort_unstable();
+    lookups.dedup();
+
+    let adjustments = lookups
+        .into_iter()
+        .filter_map(|index| gpos.lookups.get(index))
+        .filter_map(|lookup| {
+            lookup
+                .subtables
+                .into_iter::<PositioningSubtable>()
+                .find_map(|subtable| match subtable {
+                    PositioningSubtable::Pair(PairAdjustment::Format1 { coverage, sets }) => {
+                        let set = sets.get(coverage.get(left)?)?;
+                        set.get(right).map(|(first, _)| first.x_advance)
+                    }
+                    PositioningSubtable::Pair(PairAdjustment::Format2 {
+                        coverage,
+                        classes,
+                        matrix,
+                    }) if coverage.contains(left) => matrix
+                        .get((classes.0.get(left), classes.1.get(right)))
+                        .map(|(first, _)| first.x_advance),
+                    _ => None,
+                })
+        })
+        .collect::<Vec<i16>>();
+
+    if adjustments.is_empty() {
+        None
+    } else {
+        Some(adjustments.iter().sum())
+    }
+}
+
+fn kern_table_kerning(face: &ttf::Face, left: ttf::GlyphId, right: ttf::GlyphId) -> Option<i16> {
+    face.tables()
+        .kern?
+        .subtables
+        .into_iter()
+        .filter(|subtable| subtable.horizontal && !subtable.variable && !subtable.has_cross_stream)
+        .find_map(|subtable| subtable.glyphs_kerning(left, right))
+}
@@ -0,0 +1,387 @@
+//!
+//! A cache of glyph coverage, so text drawn over and over, like a user interface redrawn every
+//! frame, is only flattened and rasterized once. The coverage of every glyph is kept in a single
+//! A8 mask and composited with the fill style of each draw.
+//!
+use crate::{
+    canvas::{Canvas, CanvasDescription, CurveRendering, ViewBox},
+    color::{FillRule, FillStyle},
+    geometry::{BoundingBox, Point},
+    mask::Mask,
+    math::map_viewbox,
+    renderer::{fill_coverage, fill_region_adjusted, render_device_path, RenderState},
+    text::{
+        color::{draw_color_glyph, is_painted},
+        layout::map_points,
+        layout_text,
+        rendering::{glyph_path, points},
+        Font, GlyphId, TextLayout, TextRendering,
+    },
+};
+use std::collections::HashMap;
+
+///
+/// Empty pixels left between glyphs.
+///
+const PADDING: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: u64,
+    pub glyph: GlyphId,
+    ///
+    /// The bits of the size in pixels per em.
+    ///
+    pub size: u64,
+    ///
+    /// The horizontal offset of the glyph in `1 / GlyphAtlas::subpixel_positions` of a pixel.
+    ///
+    pub subpixel: u8,
+    ///
+    /// The options of `TextRendering` that change the outline, the gamma is applied when drawing.
+    ///
+    pub stem_darkening: bool,
+    pub snap_vertically: bool,
+    ///
+    /// The bits of the tolerance and the curve rendering of `GlyphAtlas::mask` when the glyph was
+    /// rasterized, `draw_text_cached` sets them to the ones of the canvas.
+    ///
+    pub tolerance: u64,
+    pub curve_rendering: CurveRendering,
+}
+
+///
+/// Where the coverage of a glyph is in the atlas, `left` and `top` placing it relative to the
+/// pixel of the glyph's origin. Glyphs without an outline have an empty entry.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasEntry {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub left: i64,
+    pub top: i64,
+}
+
+///
+/// A row of the atlas, glyphs are put side by side from left to right.
+///
+struct Shelf {
+    y: usize,
+    height: usize,
+    next_x: usize,
+}
+
+pub struct GlyphAtlas {
+    pub mask: Mask,
+    ///
+    /// The number of horizontal positions a glyph is rasterized at within a pixel. Vertically
+    /// glyphs are placed on whole pixels.
+    ///
+    pub subpixel_positions: u8,
+    entries: HashMap<GlyphKey, AtlasEntry>,
+    shelves: Vec<Shelf>,
+}
+
+impl GlyphAtlas {
+    pub fn new(width: usize, height: usize) -> GlyphAtlas {
+        GlyphAtlas {
+            mask: Mask::new(CanvasDescription {
+                width,
+                height,
+                viewbox: ViewBox {
+                    x: 0.0,
+                    y: 0.0,
+                    width: width as f64,
+                    height: height as f64,
+                },
+                ..Default::default()
+            }),
+            subpixel_positions: 4,
+            entries: HashMap::new(),
+            shelves: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.mask.clear();
+        self.entries.clear();
+        self.shelves.clear();
+    }
+
+    ///
+    /// Shelf packing: a glyph goes on the first row it fits in that isn't much higher than itself,
+    /// otherwise a new row is started below the others.
+    ///
+    fn allocate(&mut self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (atlas_width, atlas_height) = (self.mask.desc.width, self.mask.desc.height);
+        let (width, height) = (width + PADDING, height + PADDING);
+
+        if let Some(shelf) = self.shelves.iter_mut().find(|shelf| {
+            shelf.height >= height
+                && shelf.height <= height + height / 2
+                && shelf.next_x + width <= atlas_width
+        }) {
+            shelf.next_x += width;
+            return Some((shelf.next_x - width, shelf.y));
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+
+        if width > atlas_width || y + height > atlas_height {
+            return None;
+        }
+
+        self.shelves.push(Shelf {
+            y,
+            height,
+            next_x: width,
+        });
+        Some((0, y))
+    }
+
+    ///
+    /// The entry of a glyph drawn at `size` pixels per em, rasterized the first time with the
+    /// options of `rendering` that change its shape. When the
+    /// atlas is full it's cleared to make room, `None` means the glyph doesn't fit at all.
+    ///
+    pub fn get(
+        &mut self,
+        font: &mut Font,
+        glyph: GlyphId,
+        size: f64,
+        subpixel: u8,
+        rendering: &TextRendering,
+    ) -> Option<AtlasEntry> {
+        let key = GlyphKey {
+            font: font.id(),
+            glyph,
+            size: size.to_bits(),
+            subpixel,
+            stem_darkening: rendering.stem_darkening,
+            snap_vertically: rendering.snap_vertically,
+            tolerance: self.mask.desc.tolerance.to_bits(),
+            curve_rendering: self.mask.desc.curve_rendering,
+        };
+
+        if let Some(entry) = self.entries.get(&key) {
+            return Some(*entry);
+        }
+
+        let offset = subpixel as f64 / self.subpixel_positions.max(1) as f64;
+        let path = glyph_path(font, glyph, size, rendering);
+        let mut bounds = BoundingBox::default();
+        for op in &path {
+            for p in points(op) {
+                bounds.min_x = bounds.min_x.min((p.x + offset).floor() as i64);
+                bounds.min_y = bounds.min_y.min(p.y.floor() as i64);
+                bounds.max_x = bounds.max_x.max((p.x + offset).ceil() as i64);
+                bounds.max_y = bounds.max_y.max(p.y.ceil() as i64);
+            }
+        }
+
+        let entry = if bounds.is_empty() {
+            AtlasEntry {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+                left: 0,
+                top: 0,
+            }
+        } else {
+            let (left, top) = (bounds.min_x, bounds.min_y);
+            let width = (bounds.max_x - left) as usize;
+            let height = (bounds.max_y - top) as usize;
+            let (x, y) = match self.allocate(width, height) {
+                Some(position) => position,
+                None => {
+                    self.clear();
+                    self.allocate(width, height)?
+                }
+            };
+            let origin = Point {
+                x: (x as i64 - left) as f64 + offset,
+                y: (y as i64 - top) as f64,
+            };
+
+            self.mask.draw_shape(&path, FillRule::NonZero, |p| Point {
+                x: origin.x + p.x,
+                y: origin.y + p.y,
+            });
+
+            AtlasEntry {
+                x,
+                y,
+                width,
+                height,
+                left,
+                top,
+            }
+        };
+
+        self.entries.insert(key, entry);
+        Some(entry)
+    }
+}
+
+impl Canvas {
+    ///
+    /// Same as `draw_text` with the coverage of the glyphs taken from `atlas`. Glyphs are placed
+    /// on whole pixels vertically and on `atlas.subpixel_positions` horizontally, and since the
+    /// coverage is cached there is no transform. The viewbox should scale both axes the same.
+    /// Stem darkening and snapping are cached with the glyphs, the gamma is applied when drawing.
+    /// Glyphs are rasterized with the tolerance and curve rendering of the canvas.
+    /// Color glyphs aren't cached, the atlas only holds coverage, they're painted every time.
+    ///
+    pub fn draw_text_cached(
+        &mut self,
+        text: &str,
+        font: &mut Font,
+        size: f64,
+        layout: impl Into<TextLayout>,
+        fill_style: FillStyle,
+        atlas: &mut GlyphAtlas,
+    ) {
+        let layout = layout.into();
+        let rendering = layout.rendering;
+        let desc = self.desc;
+        let pixel_size = size * desc.width as f64 / desc.viewbox.width;
+        let scale = font.scale(pixel_size);
+        let positions = atlas.subpixel_positions.max(1) as i64;
+        let mut color_glyphs = vec![];
+        atlas.mask.desc.tolerance = desc.tolerance;
+        atlas.mask.desc.curve_rendering = desc.curve_rendering;
+        // The pixel of each glyph's origin and its offset within the pixel.
+        let glyphs = layout_text(text, font, size, layout)
+            .into_iter()
+            .filter(|glyph| {
+                let color = is_painted(font, glyph.glyph, rendering.palette);
+                if color {
+                    color_glyphs.push(*glyph);
+                }
+                !color
+            })
+            .map(|glyph| {
+                let origin = map_viewbox(&desc, &glyph.position);
+                let x = (origin.x * positions as f64).round() as i64;
+
+                (
+                    glyph.glyph,
+                    x.div_euclid(positions),
+                    origin.y.round() as i64,
+                    x.rem_euclid(positions) as u8,
+                )
+            })
+            .collect::<Vec<(GlyphId, i64, i64, u8)>>();
+        let offset = |subpixel: u8| subpixel as f64 / positions as f64;
+
+        // Gradients are laid out over the whole text, like `draw_text` does.
+        let mut bounds = BoundingBox::default();
+        for (glyph, x, y, subpixel) in &glyphs {
+            if let Some(glyph_bounds) = font.glyph_metrics(*glyph).bounds {
+                let (min_x, max_x) = (
+                    glyph_bounds.min_x * scale + offset(*subpixel),
+                    glyph_bounds.max_x * scale + offset(*subpixel),
+                );
+
+                bounds.min_x = bounds.min_x.min(x + min_x.floor() as i64);
+                bounds.max_x = bounds.max_x.max(x + max_x.floor() as i64);
+                bounds.min_y = bounds
+                    .min_y
+                    .min(y + (glyph_bounds.min_y * scale).floor() as i64);
+                bounds.max_y = bounds
+                    .max_y
+                    .max(y + (glyph_bounds.max_y * scale).floor() as i64);
+            }
+        }
+
+        if let Some(recording) = &mut self.recording {
+            let mut path = vec![];
+            for (glyph, x, y, subpixel) in &glyphs {
+                let (x, y) = (*x as f64 + offset(*subpixel), *y as f64);
+                path.extend(
+                    glyph_path(font, *glyph, pixel_size, &rendering)
+                        .iter()
+                        .map(|op| map_points(op, |px, py| (x + px, y + py))),
+                );
+            }
+
+            let to_viewbox = recording.device_to_viewbox();
+            recording.record_laid_out(
+                &path,
+                fill_style,
+                Some(&bounds),
+                FillRule::NonZero.into(),
+                self.blend_mode,
+                to_viewbox,
+            );
+        }
+
+        for (glyph, x, y, subpixel) in glyphs {
+            match atlas.get(font, glyph, pixel_size, subpixel, &rendering) {
+                Some(entry) if entry.width == 0 => {}
+                Some(entry) => {
+                    let region = BoundingBox {
+                        min_x: x + entry.left,
+                        min_y: y + entry.top,
+                        max_x: x + entry.left + entry.width as i64 - 1,
+                        max_y: y + entry.top + entry.height as i64 - 1,
+                    };
+                    let mask = &atlas.mask;
+
+                    fill_coverage(self, fill_style, &bounds, &region, |px, py| {
+                        let row = entry.y + (py as i64 - region.min_y) as usize;
+                        let column = entry.x + (px as i64 - region.min_x) as usize;
+                        rendering.apply_gamma(mask.coverage[row * mask.desc.width + column])
+                    });
+                }
+                // Too large for the atlas, it's rasterized directly instead.
+                None => {
+                    let mut state = RenderState {
+                        desc: &self.desc,
+                        accumulation_buffer: &mut self.accumulation_buffer,
+                    };
+                    let path = glyph_path(font, glyph, pixel_size, &rendering);
+                    let region = render_device_path(&mut state, &path, |p| Point {
+                        x: x as f64 + offset(subpixel) + p.x,
+                        y: y as f64 + p.y,
+                    });
+                    // The gradient spans the text, only the pixels of the glyph are blended.
+                    fill_region_adjusted(
+                        self,
+                        fill_style,
+                        FillRule::NonZero,
+                        &bounds,
+                        &region,
+                        |alpha| rendering.apply_gamma(alpha),
+                    );
+                }
+            }
+        }
+
+        for glyph in &color_glyphs {
+            draw_color_glyph(
+                self,
+                font,
+                glyph,
+                size,
+                rendering.palette,
+                fill_style,
+                &|p| *p,
+            );
+        }
+    }
+}
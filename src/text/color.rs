@@ -0,0 +1,684 @@
+//!
+//! Color glyphs, like emoji, made of layers from the `COLR` table with colors from a `CPAL`
+//! palette. Version 0 glyphs are solid layers, version 1 glyphs add gradients, transforms, clips
+//! and composite modes.
+//!
+//! ttf-parser walks the paint graph of a glyph, the calls are recorded first and then replayed
+//! with the font free to be borrowed mutably for its outlines. The glyph is painted on layers the
+//! size of its bounds, kept premultiplied so they can be composited with each other, and the
+//! result is blended on the canvas with its blending function.
+//!
+use crate::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{BoundingBox, PathOps, Point},
+    mask::Mask,
+    math::{map_viewbox, Angle},
+    renderer::{blend_func, blend_pixel, fill_coverage, BlendFunc, NUM_CHANNELS},
+    text::{layout::map_points, Font, GlyphId, PositionedGlyph},
+};
+use ttf::colr::{ClipBox, CompositeMode, Paint};
+use ttf_parser as ttf;
+
+///
+/// A paint with its stops resolved, in font units with y pointing up.
+///
+enum ColorPaint {
+    Solid(Color),
+    Linear {
+        p0: Point,
+        p1: Point,
+        p2: Point,
+        stops: Vec<(Color, f64)>,
+    },
+    Radial {
+        c0: Point,
+        r0: f64,
+        c1: Point,
+        r1: f64,
+        stops: Vec<(Color, f64)>,
+    },
+    Sweep {
+        center: Point,
+        start_angle: f64,
+        end_angle: f64,
+        stops: Vec<(Color, f64)>,
+    },
+}
+
+///
+/// The calls of ttf-parser, with the transform in effect for the outlines and paints.
+///
+enum Command {
+    Outline(GlyphId, ttf::Transform),
+    Paint(ColorPaint, ttf::Transform),
+    PushClip,
+    PushClipBox(ClipBox, ttf::Transform),
+    PopClip,
+    PushLayer(BlendFunc),
+    PopLayer,
+}
+
+struct Recorder<'a> {
+    commands: Vec<Command>,
+    transforms: Vec<ttf::Transform>,
+    transform: ttf::Transform,
+    palette: u16,
+    coords: &'a [ttf::NormalizedCoordinate],
+}
+
+fn to_color(color: ttf::RgbaColor) -> Color {
+    Color {
+        r: color.red as f64 / 255.0,
+        g: color.green as f64 / 255.0,
+        b: color.blue as f64 / 255.0,
+        a: color.alpha as f64 / 255.0,
+    }
+}
+
+fn to_stops(stops: impl Iterator<Item = ttf::colr::ColorStop>) -> Vec<(Color, f64)> {
+    let mut stops = stops
+        .map(|stop| (to_color(stop.color), stop.stop_offset as f64))
+        .collect::<Vec<(Color, f64)>>();
+    stops.sort_by(|a, b| a.1.total_cmp(&b.1));
+    stops
+}
+
+impl<'a> ttf::colr::Painter<'a> for Recorder<'a> {
+    fn outline_glyph(&mut self, glyph_id: ttf::GlyphId) {
+        self.commands
+            .push(Command::Outline(GlyphId(glyph_id.0), self.transform));
+    }
+
+    fn paint(&mut self, paint: Paint<'a>) {
+        let point = |x: f32, y: f32| Point {
+            x: x as f64,
+            y: y as f64,
+        };
+        let paint = match paint {
+            Paint::Solid(color) => ColorPaint::Solid(to_color(color)),
+            Paint::LinearGradient(gradient) => ColorPaint::Linear {
+                p0: point(gradient.x0, gradient.y0),
+                p1: point(gradient.x1, gradient.y1),
+                p2: point(gradient.x2, gradient.y2),
+                stops: to_stops(gradient.stops(self.palette, self.coords)),
+            },
+            Paint::RadialGradient(gradient) => ColorPaint::Radial {
+                c0: point(gradient.x0, gradient.y0),
+                r0: gradient.r0 as f64,
+                c1: point(gradient.x1, gradient.y1),
+                r1: gradient.r1 as f64,
+                stops: to_stops(gradient.stops(self.palette, self.coords)),
+            },
+            Paint::SweepGradient(gradient) => ColorPaint::Sweep {
+                center: point(gradient.center_x, gradient.center_y),
+                start_angle: gradient.start_angle as f64,
+                end_angle: gradient.end_angle as f64,
+                stops: to_stops(gradient.stops(self.palette, self.coords)),
+            },
+        };
+
+        self.commands.push(Command::Paint(paint, self.transform));
+    }
+
+    fn push_clip(&mut self) {
+        self.commands.push(Command::PushClip);
+    }
+
+    fn push_clip_box(&mut self, clipbox: ClipBox) {
+        self.commands
+            .push(Command::PushClipBox(clipbox, self.transform));
+    }
+
+    fn pop_clip(&mut self) {
+        self.commands.push(Command::PopClip);
+    }
+
+    fn push_layer(&mut self, mode: CompositeMode) {
+        self.commands.push(Command::PushLayer(composite(mode)));
+    }
+
+    fn pop_layer(&mut self) {
+        self.commands.push(Command::PopLayer);
+    }
+
+    fn push_transform(&mut self, transform: ttf::Transform) {
+        self.transforms.push(self.transform);
+        self.transform = ttf::Transform::combine(self.transform, transform);
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some(transform) = self.transforms.pop() {
+            self.transform = transform;
+        }
+    }
+}
+
+///
+/// `blend_func::source` and `blend_func::destination` return their color as it is, the other
+/// functions return premultiplied colors like the layers hold.
+///
+fn source(src: &Color, _dest: &Color) -> Color {
+    Color {
+        r: src.r * src.a,
+        g: src.g * src.a,
+        b: src.b * src.a,
+        a: src.a,
+    }
+}
+
+fn destination(src: &Color, dest: &Color) -> Color {
+    source(dest, src)
+}
+
+///
+/// The blending function of a composite mode. The separable and non-separable blend modes, like
+/// multiply or hue, have no blending function and are drawn as `SourceOver`.
+///
+fn composite(mode: CompositeMode) -> BlendFunc {
+    match mode {
+        CompositeMode::Clear => blend_func::clear,
+        CompositeMode::Source => source,
+        CompositeMode::Destination => destination,
+        CompositeMode::DestinationOver => blend_func::destination_over,
+        CompositeMode::SourceIn => blend_func::source_in,
+        CompositeMode::DestinationIn => blend_func::destination_in,
+        CompositeMode::SourceOut => blend_func::source_out,
+        CompositeMode::DestinationOut => blend_func::destination_out,
+        CompositeMode::SourceAtop => blend_func::source_atop,
+        CompositeMode::DestinationAtop => blend_func::destination_atop,
+        CompositeMode::Xor => blend_func::xor,
+        CompositeMode::Plus => blend_func::additive,
+        _ => blend_func::source_over,
+    }
+}
+
+///
+/// The color of a premultiplied pixel, as the blending functions expect it.
+///
+fn unpremultiply(pixel: &[f64]) -> Color {
+    let a = pixel[3];
+
+    if a <= 0.0 {
+        return Color {
+            a: 0.0,
+            ..Color::default()
+        };
+    }
+
+    Color {
+        r: pixel[0] / a,
+        g: pixel[1] / a,
+        b: pixel[2] / a,
+        a,
+    }
+}
+
+fn apply(transform: &ttf::Transform, p: Point) -> Point {
+    let t = transform;
+
+    Point {
+        x: t.a as f64 * p.x + t.c as f64 * p.y + t.e as f64,
+        y: t.b as f64 * p.x + t.d as f64 * p.y + t.f as f64,
+    }
+}
+
+///
+/// The fill style of a paint and the bounds it's laid out over, `to_layer` maps font units to
+/// the pixels of the layer. Gradients are approximated with the ones `FillStyle` has: two point
+/// radial gradients become concentric around the larger circle, sweeps follow `ConicGradient`
+/// which measures angles from the x axis on both sides, and all of them pad.
+///
+fn paint_fill_style<'a>(
+    paint: &ColorPaint,
+    to_layer: impl Fn(Point) -> Point,
+    offsets: &'a mut Vec<(Color, f64)>,
+    angles: &'a mut Vec<(Color, Angle)>,
+) -> (FillStyle<'a>, BoundingBox) {
+    let solid = |stops: &[(Color, f64)]| {
+        let color = stops.last().map_or(Color::default(), |stop| stop.0);
+        (FillStyle::Plain(color), BoundingBox::default())
+    };
+
+    match paint {
+        ColorPaint::Solid(color) => (FillStyle::Plain(*color), BoundingBox::default()),
+        ColorPaint::Linear { p0, p1, p2, stops } => {
+            let (first, last) = match (stops.first(), stops.last()) {
+                (Some(first), Some(last)) if last.1 - first.1 > 1e-6 => (first.1, last.1),
+                _ => return solid(stops),
+            };
+            // The gradient goes from p0 to p1 projected on the normal of p0 p2.
+            let normal = Point {
+                x: p0.y - p2.y,
+                y: p2.x - p0.x,
+            };
+            let length = normal.x * normal.x + normal.y * normal.y;
+            let p3 = if length > 0.0 {
+                let t = ((p1.x - p0.x) * normal.x + (p1.y - p0.y) * normal.y) / length;
+                Point {
+                    x: p0.x + t * normal.x,
+                    y: p0.y + t * normal.y,
+                }
+            } else {
+                *p1
+            };
+            // Offsets outside of [0, 1] move the ends of the gradient instead.
+            let at = |offset: f64| {
+                to_layer(Point {
+                    x: p0.x + (p3.x - p0.x) * offset,
+                    y: p0.y + (p3.y - p0.y) * offset,
+                })
+            };
+            let (start, end) = (at(first), at(last));
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let distance = f64::hypot(dx, dy);
+
+            if distance < 1e-6 {
+                return solid(stops);
+            }
+
+            offsets.extend(
+                stops
+                    .iter()
+                    .map(|(color, offset)| (*color, (offset - first) / (last - first))),
+            );
+            let center = Point {
+                x: (start.x + end.x) / 2.0,
+                y: (start.y + end.y) / 2.0,
+            };
+
+            (
+                FillStyle::LinearGradient {
+                    stops: offsets,
+                    angle: Angle::from_radians(f64::atan2(-dy, -dx)),
+                },
+                BoundingBox::square(center, distance / 2.0),
+            )
+        }
+        ColorPaint::Radial {
+            c0,
+            r0,
+            c1,
+            r1,
+            stops,
+        } => {
+            let (r0, r1) = (r0.max(0.0), r1.max(0.0));
+            let (center, radius) = if r1 >= r0 { (*c1, r1) } else { (*c0, r0) };
+
+            if radius <= 0.0 || stops.is_empty() {
+                return solid(stops);
+            }
+
+            // The radius in pixels, the average of both axes when the transform scales them
+            // differently.
+            let origin = to_layer(center);
+            let x = to_layer(Point {
+                x: center.x + radius,
+                y: center.y,
+            });
+            let y = to_layer(Point {
+                x: center.x,
+                y: center.y + radius,
+            });
+            let pixels = ((x.x - origin.x) * (y.y - origin.y)
+                - (x.y - origin.y) * (y.x - origin.x))
+                .abs()
+                .sqrt();
+
+            offsets.extend(
+                stops
+                    .iter()
+                    .map(|(color, offset)| (*color, (r0 + offset * (r1 - r0)) / radius)),
+            );
+            offsets.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            (
+                FillStyle::RadialGradient {
+                    stops: offsets,
+                    translation: Point { x: 0.0, y: 0.0 },
+                },
+                BoundingBox::square(origin, pixels),
+            )
+        }
+        ColorPaint::Sweep {
+            center,
+            start_angle,
+            end_angle,
+            stops,
+        } => {
+            if stops.is_empty() {
+                return solid(stops);
+            }
+
+            // Angles are in half turns, counter-clockwise.
+            angles.extend(stops.iter().map(|(color, offset)| {
+                let angle = start_angle + offset * (end_angle - start_angle);
+                (*color, Angle::from_degrees(angle * 180.0))
+            }));
+
+            (
+                FillStyle::ConicGradient {
+                    stops: angles,
+                    translation: Point { x: 0.0, y: 0.0 },
+                },
+                BoundingBox::square(to_layer(*center), 1.0),
+            )
+        }
+    }
+}
+
+///
+/// Records how a glyph is painted, `None` when it has no `COLR` definition.
+///
+fn record(font: &Font, glyph: GlyphId, palette: u16, foreground: Color) -> Option<Vec<Command>> {
+    let face = font.face();
+    let palette = palette.min(face.color_palettes()?.get() - 1);
+    let mut recorder = Recorder {
+        commands: vec![],
+        transforms: vec![],
+        transform: ttf::Transform::default(),
+        palette,
+        coords: face.variation_coordinates(),
+    };
+    let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    face.paint_color_glyph(
+        ttf::GlyphId(glyph.0),
+        palette,
+        ttf::RgbaColor::new(
+            channel(foreground.r),
+            channel(foreground.g),
+            channel(foreground.b),
+            channel(foreground.a),
+        ),
+        &mut recorder,
+    )?;
+
+    Some(recorder.commands)
+}
+
+///
+/// The color of the text used by layers painted with the foreground color, the first stop of
+/// gradients.
+///
+fn foreground(fill_style: FillStyle) -> Color {
+    match fill_style {
+        FillStyle::Plain(color) => color,
+        FillStyle::LinearGradient { stops, .. } | FillStyle::RadialGradient { stops, .. } => {
+            stops.first().map_or(Color::default(), |stop| stop.0)
+        }
+        FillStyle::ConicGradient { stops, .. } => {
+            stops.first().map_or(Color::default(), |stop| stop.0)
+        }
+    }
+}
+
+///
+/// Whether `glyph` is painted from its color definition with `palette`. Glyphs of the `COLR` table
+/// whose paints can't be read are drawn from their outline like the other glyphs.
+///
+pub(crate) fn is_painted(font: &Font, glyph: GlyphId, palette: u16) -> bool {
+    font.is_color_glyph(glyph) && record(font, glyph, palette, Color::default()).is_some()
+}
+
+///
+/// Paints a color glyph, the fill style gives the text color some layers use. Glyphs rejected by
+/// `is_painted` aren't drawn.
+///
+pub(crate) fn draw_color_glyph(
+    canvas: &mut Canvas,
+    font: &mut Font,
+    glyph: &PositionedGlyph,
+    size: f64,
+    palette: u16,
+    fill_style: FillStyle,
+    transform: &impl Fn(&Point) -> Point,
+) {
+    let origin = glyph.position;
+    let commands = match record(font, glyph.glyph, palette, foreground(fill_style)) {
+        Some(commands) => commands,
+        None => return,
+    };
+
+    let desc = canvas.desc;
+    let scale = font.scale(size);
+    // Font units, with y up, to pixels of the canvas.
+    let to_device = |p: Point| {
+        map_viewbox(
+            &desc,
+            &transform(&Point {
+                x: origin.x + p.x * scale,
+                y: origin.y - p.y * scale,
+            }),
+        )
+    };
+    let outline = |font: &mut Font, glyph: GlyphId, t: &ttf::Transform| {
+        font.outline(glyph)
+            .iter()
+            .map(|op| {
+                map_points(op, |x, y| {
+                    let p = to_device(apply(t, Point { x, y: -y }));
+                    (p.x, p.y)
+                })
+            })
+            .collect::<Vec<PathOps>>()
+    };
+    let clip_box = |rect: &ClipBox, t: &ttf::Transform| {
+        let corner = |x: f32, y: f32| {
+            let p = to_device(apply(
+                t,
+                Point {
+                    x: x as f64,
+                    y: y as f64,
+                },
+            ));
+            (p.x, p.y)
+        };
+        let corners = [
+            corner(rect.x_min, rect.y_min),
+            corner(rect.x_max, rect.y_min),
+            corner(rect.x_max, rect.y_max),
+            corner(rect.x_min, rect.y_max),
+        ];
+        let mut path = vec![PathOps::MoveTo {
+            x: corners[0].0,
+            y: corners[0].1,
+        }];
+        path.extend(
+            corners[1..]
+                .iter()
+                .map(|(x, y)| PathOps::LineTo { x: *x, y: *y }),
+        );
+        path.push(PathOps::Close);
+        path
+    };
+
+    // The layers cover what the outlines and clip boxes cover on the canvas.
+    let mut bounds = BoundingBox::default();
+    for command in &commands {
+        let path = match command {
+            Command::Outline(glyph, t) => outline(font, *glyph, t),
+            Command::PushClipBox(rect, t) => clip_box(rect, t),
+            _ => continue,
+        };
+        for op in &path {
+            for p in super::rendering::points(op) {
+                bounds.min_x = bounds.min_x.min(p.x.floor() as i64);
+                bounds.min_y = bounds.min_y.min(p.y.floor() as i64);
+                bounds.max_x = bounds.max_x.max(p.x.ceil() as i64);
+                bounds.max_y = bounds.max_y.max(p.y.ceil() as i64);
+            }
+        }
+    }
+    bounds.min_x = bounds.min_x.max(0);
+    bounds.min_y = bounds.min_y.max(0);
+    bounds.max_x = bounds.max_x.min(desc.width as i64);
+    bounds.max_y = bounds.max_y.min(desc.height as i64);
+
+    if bounds.min_x >= bounds.max_x || bounds.min_y >= bounds.max_y {
+        return;
+    }
+
+    let (width, height) = (
+        (bounds.max_x - bounds.min_x) as usize,
+        (bounds.max_y - bounds.min_y) as usize,
+    );
+    let layer_desc = CanvasDescription {
+        width,
+        height,
+        viewbox: ViewBox {
+            x: bounds.min_x as f64,
+            y: bounds.min_y as f64,
+            width: width as f64,
+            height: height as f64,
+        },
+        background_color: Color {
+            a: 0.0,
+            ..Color::default()
+        },
+        ..desc
+    };
+    let new_layer = || {
+        let mut layer = Canvas::new(layer_desc);
+        layer.set_blending_function(source_over_premultiplied);
+        layer
+    };
+    let to_layer = |p: Point| {
+        let p = to_device(p);
+        Point {
+            x: p.x - bounds.min_x as f64,
+            y: p.y - bounds.min_y as f64,
+        }
+    };
+    let coverage = |path: &[PathOps]| {
+        let mut mask = Mask::new(layer_desc);
+        mask.draw_shape(path, FillRule::NonZero, |p| *p);
+        mask.coverage
+    };
+    let region = BoundingBox {
+        min_x: 0,
+        min_y: 0,
+        max_x: width as i64 - 1,
+        max_y: height as i64 - 1,
+    };
+
+    let mut layers = vec![(new_layer(), blend_func::source_over as BlendFunc)];
+    let mut clips: Vec<Vec<f32>> = vec![];
+    let mut current_outline: Option<Vec<f32>> = None;
+    // The paths of the clips and of the outline, paints are recorded filling the innermost one.
+    let mut clip_paths: Vec<Option<Vec<PathOps>>> = vec![];
+    let mut outline_path: Option<Vec<PathOps>> = None;
+
+    for command in &commands {
+        match command {
+            Command::Outline(glyph, t) => {
+                let path = outline(font, *glyph, t);
+                current_outline = Some(coverage(&path));
+                outline_path = Some(path);
+            }
+            // The outline becomes the clip, the paints inside it are only clipped by it.
+            Command::PushClip | Command::PushClipBox(..) => {
+                let mut clip = match command {
+                    Command::PushClipBox(rect, t) => {
+                        let path = clip_box(rect, t);
+                        let clip = coverage(&path);
+                        clip_paths.push(Some(path));
+                        clip
+                    }
+                    _ => {
+                        clip_paths.push(outline_path.take());
+                        current_outline
+                            .take()
+                            .unwrap_or_else(|| vec![1.0; width * height])
+                    }
+                };
+                if let Some(previous) = clips.last() {
+                    clip.iter_mut().zip(previous).for_each(|(a, b)| *a *= b);
+                }
+                clips.push(clip);
+            }
+            Command::PopClip => {
+                clips.pop();
+                clip_paths.pop();
+                current_outline = None;
+                outline_path = None;
+            }
+            Command::Paint(paint, t) => {
+                let shape_path = outline_path
+                    .as_ref()
+                    .or_else(|| clip_paths.iter().rev().flatten().next());
+
+                if let (Some(recording), Some(path)) = (&mut canvas.recording, shape_path) {
+                    let (mut offsets, mut angles) = (vec![], vec![]);
+                    let (fill_style, paint_bounds) = paint_fill_style(
+                        paint,
+                        |p| to_device(apply(t, p)),
+                        &mut offsets,
+                        &mut angles,
+                    );
+                    let to_viewbox = recording.device_to_viewbox();
+                    recording.record_laid_out(
+                        path,
+                        fill_style,
+                        Some(&paint_bounds),
+                        FillRule::NonZero.into(),
+                        canvas.blend_mode,
+                        to_viewbox,
+                    );
+                }
+
+                let (mut offsets, mut angles) = (vec![], vec![]);
+                let (fill_style, gradient_bounds) =
+                    paint_fill_style(paint, |p| to_layer(apply(t, p)), &mut offsets, &mut angles);
+                let (clip, shape) = (clips.last(), current_outline.as_ref());
+                let layer = &mut layers.last_mut().expect("there is always a layer").0;
+
+                fill_coverage(layer, fill_style, &gradient_bounds, &region, |x, y| {
+                    let i = y * width + x;
+                    clip.map_or(1.0, |clip| clip[i]) * shape.map_or(1.0, |shape| shape[i])
+                });
+            }
+            Command::PushLayer(blend) => layers.push((new_layer(), *blend)),
+            Command::PopLayer => {
+                if layers.len() > 1 {
+                    let (layer, blend) = layers.pop().expect("there are at least two layers");
+                    let parent = &mut layers.last_mut().expect("there is a layer left").0;
+
+                    for (dest, src) in parent
+                        .buffer
+                        .chunks_mut(NUM_CHANNELS)
+                        .zip(layer.buffer.chunks(NUM_CHANNELS))
+                    {
+                        let result = blend(&unpremultiply(src), &unpremultiply(dest));
+                        dest.copy_from_slice(&[result.r, result.g, result.b, result.a]);
+                    }
+                }
+            }
+        }
+    }
+
+    let layer = &layers[0].0;
+    for y in 0..height {
+        for x in 0..width {
+            let src = unpremultiply(&layer.buffer[(y * width + x) * NUM_CHANNELS..]);
+
+            if src.a > 0.0 {
+                let offset = ((bounds.min_y as usize + y) * desc.width + bounds.min_x as usize + x)
+                    * NUM_CHANNELS;
+                blend_pixel(&mut canvas.buffer[offset..], &src, canvas.blend);
+            }
+        }
+    }
+}
+
+///
+/// `blend_func::source_over` for the premultiplied pixels of the layers.
+///
+fn source_over_premultiplied(src: &Color, dest: &Color) -> Color {
+    Color {
+        r: src.r * src.a + dest.r * (1.0 - src.a),
+        g: src.g * src.a + dest.g * (1.0 - src.a),
+        b: src.b * src.a + dest.b * (1.0 - src.a),
+        a: src.a + dest.a * (1.0 - src.a),
+    }
+}
@@ -0,0 +1,312 @@
+//!
+//! Laying out lines of text: glyph advances and kerning, alignment, baselines and word wrapping.
+//!
+use crate::{
+    canvas::Canvas,
+    color::{FillRule, FillStyle},
+    geometry::{Path, PathOps, Point},
+    renderer::{fill_path_adjusted, render_path, RenderState},
+    text::{
+        color::{draw_color_glyph, is_painted},
+        rendering::glyph_path,
+        Font, GlyphId, TextRendering,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+///
+/// Which line of the first row of text is placed at the y of the position, like the
+/// `textBaseline` of an HTML canvas.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Baseline {
+    #[default]
+    Alphabetic,
+    ///
+    /// The ascender of the font.
+    ///
+    Top,
+    ///
+    /// Halfway between the ascender and the descender.
+    ///
+    Middle,
+    ///
+    /// The descender of the font.
+    ///
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextLayout {
+    pub position: Point,
+    pub align: TextAlign,
+    pub baseline: Baseline,
+    ///
+    /// The width of the box the text is wrapped and aligned in, starting at the position. Without
+    /// it lines are only broken at '\n' and aligned around the position.
+    ///
+    pub width: Option<f64>,
+    ///
+    /// The distance between two baselines in pixels, the line height of the font by default.
+    ///
+    pub line_height: Option<f64>,
+    pub rendering: TextRendering,
+}
+
+impl From<Point> for TextLayout {
+    fn from(position: Point) -> Self {
+        TextLayout {
+            position,
+            align: TextAlign::default(),
+            baseline: Baseline::default(),
+            width: None,
+            line_height: None,
+            rendering: TextRendering::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PositionedGlyph {
+    pub glyph: GlyphId,
+    ///
+    /// The origin of the glyph on its baseline, in pixels.
+    ///
+    pub position: Point,
+}
+
+///
+/// The glyphs of a line and their x offsets in font units, with the width of the line.
+///
+pub(crate) fn shape_line(font: &Font, text: &str) -> (Vec<(GlyphId, f64)>, f64) {
+    let mut glyphs = Vec::with_capacity(text.len());
+    let mut pen = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        // Characters missing from the font are drawn with the .notdef glyph.
+        let glyph = font.glyph_index(c).unwrap_or(GlyphId(0));
+
+        if let Some(previous) = previous {
+            pen += font.kerning(previous, glyph);
+        }
+        glyphs.push((glyph, pen));
+        pen += font.glyph_metrics(glyph).advance;
+        previous = Some(glyph);
+    }
+
+    (glyphs, pen)
+}
+
+///
+/// Greedily fits as many words as possible on each line, a word longer than the width gets a line
+/// of its own.
+///
+fn wrap<'a>(font: &Font, paragraph: &'a str, width: f64) -> Vec<&'a str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut end = 0;
+
+    for (index, _) in paragraph
+        .match_indices(' ')
+        .chain(std::iter::once((paragraph.len(), "")))
+    {
+        if end > start && shape_line(font, &paragraph[start..index]).1 > width {
+            lines.push(&paragraph[start..end]);
+            start = end + 1;
+        }
+        end = index;
+    }
+    lines.push(&paragraph[start..]);
+
+    lines
+}
+
+///
+/// Positions the glyphs of `text` drawn at `size` pixels per em.
+///
+pub fn layout_text(
+    text: &str,
+    font: &Font,
+    size: f64,
+    layout: impl Into<TextLayout>,
+) -> Vec<PositionedGlyph> {
+    let layout = layout.into();
+    let scale = font.scale(size);
+    let metrics = font.metrics();
+    let line_height = layout.line_height.unwrap_or(metrics.line_height * scale);
+    let mut baseline = layout.position.y
+        - scale
+            * match layout.baseline {
+                Baseline::Alphabetic => 0.0,
+                Baseline::Top => metrics.ascender,
+                Baseline::Middle => (metrics.ascender + metrics.descender) / 2.0,
+                Baseline::Bottom => metrics.descender,
+            };
+    let mut result = vec![];
+
+    for paragraph in text.lines() {
+        let lines = match layout.width {
+            Some(width) => wrap(font, paragraph, width / scale),
+            None => vec![paragraph],
+        };
+
+        for line in lines {
+            let (glyphs, width) = shape_line(font, line);
+            let free_space = layout.width.unwrap_or(0.0) - width * scale;
+            let x = layout.position.x
+                + match layout.align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => free_space / 2.0,
+                    TextAlign::Right => free_space,
+                };
+
+            result.extend(glyphs.into_iter().map(|(glyph, offset)| PositionedGlyph {
+                glyph,
+                position: Point {
+                    x: x + offset * scale,
+                    y: baseline,
+                },
+            }));
+            baseline += line_height;
+        }
+    }
+
+    result
+}
+
+pub(crate) fn map_points(op: &PathOps, f: impl Fn(f64, f64) -> (f64, f64)) -> PathOps {
+    match *op {
+        PathOps::MoveTo { x, y } => {
+            let (x, y) = f(x, y);
+            PathOps::MoveTo { x, y }
+        }
+        PathOps::LineTo { x, y } => {
+            let (x, y) = f(x, y);
+            PathOps::LineTo { x, y }
+        }
+        PathOps::QuadTo { x1, y1, x2, y2 } => {
+            let ((x1, y1), (x2, y2)) = (f(x1, y1), f(x2, y2));
+            PathOps::QuadTo { x1, y1, x2, y2 }
+        }
+        PathOps::CubicTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3,
+        } => {
+            let ((x1, y1), (x2, y2), (x3, y3)) = (f(x1, y1), f(x2, y2), f(x3, y3));
+            PathOps::CubicTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3,
+            }
+        }
+        PathOps::Close => PathOps::Close,
+        _ => unreachable!("glyph outlines only have absolute commands"),
+    }
+}
+
+impl Canvas {
+    ///
+    /// `draw_shape` with grayscale antialiasing and the coverage of each pixel going through
+    /// `adjust`, text uses it for its gamma.
+    ///
+    pub(crate) fn draw_shape_adjusted(
+        &mut self,
+        path: Path,
+        fill_style: FillStyle,
+        fill_rule: FillRule,
+        transform: impl Fn(&Point) -> Point,
+        adjust: impl Fn(f32) -> f32,
+    ) {
+        if let Some(recording) = &mut self.recording {
+            recording.record(
+                path,
+                fill_style,
+                fill_rule.into(),
+                self.blend_mode,
+                &transform,
+            );
+        }
+
+        let mut state = RenderState {
+            desc: &self.desc,
+            accumulation_buffer: &mut self.accumulation_buffer,
+        };
+        let bounds = render_path(&mut state, path, transform);
+        fill_path_adjusted(self, fill_style, fill_rule, &bounds, adjust)
+    }
+
+    ///
+    /// Draws `text` at `size` pixels per em. The glyphs are filled as a single shape, so gradients
+    /// span the whole text and `transform` applies to it after the layout. Snapping assumes a unit
+    /// of the viewbox is a pixel and a transform that doesn't rotate or scale.
+    ///
+    /// Color glyphs are painted with their own colors after the other glyphs, the fill style only
+    /// gives the color of the layers that use the text color.
+    ///
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        font: &mut Font,
+        size: f64,
+        layout: impl Into<TextLayout>,
+        fill_style: FillStyle,
+        transform: impl Fn(&Point) -> Point,
+    ) {
+        let layout = layout.into();
+        let rendering = layout.rendering;
+        let mut path = vec![];
+        let mut color_glyphs = vec![];
+
+        for positioned in layout_text(text, font, size, layout) {
+            let PositionedGlyph { glyph, position } = positioned;
+
+            if is_painted(font, glyph, rendering.palette) {
+                color_glyphs.push(positioned);
+                continue;
+            }
+
+            let y = if rendering.snap_vertically {
+                position.y.round()
+            } else {
+                position.y
+            };
+
+            path.extend(
+                glyph_path(font, glyph, size, &rendering)
+                    .iter()
+                    .map(|op| map_points(op, |px, py| (position.x + px, y + py))),
+            );
+        }
+
+        self.draw_shape_adjusted(&path, fill_style, FillRule::NonZero, &transform, |alpha| {
+            rendering.apply_gamma(alpha)
+        });
+
+        for glyph in &color_glyphs {
+            draw_color_glyph(
+                self,
+                font,
+                glyph,
+                size,
+                rendering.palette,
+                fill_style,
+                &transform,
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//!
+//! Laying out text along a path, like map labels following a road: each glyph is rotated to the
+//! tangent of the path at the middle of its advance.
+//!
+use crate::{
+    canvas::Canvas,
+    color::{FillRule, FillStyle},
+    geometry::{Path, PathMeasure, Point},
+    text::{
+        color::{draw_color_glyph, is_painted},
+        layout::{map_points, shape_line},
+        rendering::glyph_path,
+        Baseline, Font, PositionedGlyph, TextAlign, TextRendering,
+    },
+};
+
+///
+/// Where text goes along a path.
+///
+#[derive(Clone, Copy)]
+pub struct TextPath<'a> {
+    pub path: Path<'a>,
+    ///
+    /// The distance along the path the text is aligned at, in units of the viewbox.
+    ///
+    pub start_offset: f64,
+    ///
+    /// How the text sits around the start offset: starting at it, centered on it or ending at it.
+    ///
+    pub align: TextAlign,
+    ///
+    /// Which line of the text follows the path.
+    ///
+    pub baseline: Baseline,
+    ///
+    /// Snapping doesn't apply, glyphs along a curve don't line up with the pixel grid.
+    ///
+    pub rendering: TextRendering,
+}
+
+impl<'a> From<Path<'a>> for TextPath<'a> {
+    fn from(path: Path<'a>) -> Self {
+        TextPath {
+            path,
+            start_offset: 0.0,
+            align: TextAlign::default(),
+            baseline: Baseline::default(),
+            rendering: TextRendering::default(),
+        }
+    }
+}
+
+///
+/// The point of a glyph along the path and the direction of its baseline.
+///
+#[derive(Debug, Clone, Copy)]
+struct PlacedGlyph {
+    origin: Point,
+    tangent: Point,
+}
+
+impl PlacedGlyph {
+    ///
+    /// Maps a point relative to the origin of the glyph, with y pointing down, to the path.
+    ///
+    fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.origin.x + self.tangent.x * x - self.tangent.y * y,
+            self.origin.y + self.tangent.y * x + self.tangent.x * y,
+        )
+    }
+}
+
+impl Canvas {
+    ///
+    /// Draws `text` on a single line along a path, at `size` pixels per em. The path is measured
+    /// with the tolerance of the canvas and glyphs whose middle falls past either end of it are
+    /// left out. All glyphs are filled as one shape like `draw_text` does, then color glyphs are
+    /// painted over them.
+    ///
+    pub fn draw_text_on_path<'a>(
+        &mut self,
+        text: &str,
+        font: &mut Font,
+        size: f64,
+        text_path: impl Into<TextPath<'a>>,
+        fill_style: FillStyle,
+        transform: impl Fn(&Point) -> Point,
+    ) {
+        let text_path = text_path.into();
+        let rendering = TextRendering {
+            snap_vertically: false,
+            ..text_path.rendering
+        };
+        let measure = PathMeasure::new(text_path.path, self.desc.tolerance);
+        let scale = font.scale(size);
+        let metrics = font.metrics();
+        let baseline = -scale
+            * match text_path.baseline {
+                Baseline::Alphabetic => 0.0,
+                Baseline::Top => metrics.ascender,
+                Baseline::Middle => (metrics.ascender + metrics.descender) / 2.0,
+                Baseline::Bottom => metrics.descender,
+            };
+        let (glyphs, width) = shape_line(font, &text.replace('\n', " "));
+        let start = text_path.start_offset
+            - width
+                * scale
+                * match text_path.align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => 0.5,
+                    TextAlign::Right => 1.0,
+                };
+        let mut path = vec![];
+        let mut color_glyphs = vec![];
+
+        for (glyph, offset) in glyphs {
+            let half_advance = font.glyph_metrics(glyph).advance * scale / 2.0;
+            let (middle, tangent) = match measure.point_at(start + offset * scale + half_advance) {
+                Some(point) => point,
+                None => continue,
+            };
+            let placed = PlacedGlyph {
+                origin: Point {
+                    x: middle.x - tangent.x * half_advance,
+                    y: middle.y - tangent.y * half_advance,
+                },
+                tangent,
+            };
+
+            if is_painted(font, glyph, rendering.palette) {
+                color_glyphs.push((glyph, placed));
+                continue;
+            }
+
+            path.extend(
+                glyph_path(font, glyph, size, &rendering)
+                    .iter()
+                    .map(|op| map_points(op, |x, y| placed.map(x, y + baseline))),
+            );
+        }
+
+        self.draw_shape_adjusted(&path, fill_style, FillRule::NonZero, &transform, |alpha| {
+            rendering.apply_gamma(alpha)
+        });
+
+        for (glyph, placed) in color_glyphs {
+            let positioned = PositionedGlyph {
+                glyph,
+                position: Point { x: 0.0, y: 0.0 },
+            };
+            draw_color_glyph(
+                self,
+                font,
+                &positioned,
+                size,
+                rendering.palette,
+                fill_style,
+                &|p: &Point| {
+                    let (x, y) = placed.map(p.x, p.y + baseline);
+                    transform(&Point { x, y })
+                },
+            );
+        }
+    }
+}
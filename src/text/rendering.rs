@@ -0,0 +1,285 @@
+//!
+//! Options for rendering small text without hinting instructions: stem darkening, a gamma applied
+//! to the coverage and snapping horizontal stems to the pixel grid. None of them affect shapes
+//! drawn with `Canvas::draw_shape`, nor color glyphs which only use the palette chosen here.
+//!
+use crate::{
+    geometry::{PathOps, Point},
+    text::{Font, GlyphId},
+};
+
+///
+/// Outset of the outline per pixel per em, horizontally and vertically, the same as macOS.
+///
+const STEM_DARKENING_FACTORS: [f64; 2] = [0.0121, 0.0121 * 1.25];
+const MAX_STEM_DARKENING_AMOUNT: f64 = 0.3;
+///
+/// Glyphs larger than that are left as they are.
+///
+const MAX_STEM_DARKENING_SIZE: f64 = 72.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextRendering {
+    ///
+    /// Emboldens glyphs by an amount growing with their size, up to 0.3 pixels on each side, so
+    /// the thin stems of small text don't look washed out.
+    ///
+    pub stem_darkening: bool,
+    ///
+    /// Coverage is raised to `1 / gamma`, values above 1 make text heavier, 1 leaves it unchanged.
+    ///
+    pub gamma: f64,
+    ///
+    /// Moves horizontal edges, like the baseline, the x-height and the bars of E, to pixel
+    /// boundaries so horizontal stems are sharp. Only y is changed, which keeps the advances, and
+    /// the baseline is rounded to a whole pixel.
+    ///
+    pub snap_vertically: bool,
+    ///
+    /// The `CPAL` palette of color glyphs, fonts can have others than the default one, for dark
+    /// backgrounds for instance. Palettes the font doesn't have fall back to the last one.
+    ///
+    pub palette: u16,
+}
+
+impl Default for TextRendering {
+    fn default() -> Self {
+        TextRendering {
+            stem_darkening: false,
+            gamma: 1.0,
+            snap_vertically: false,
+            palette: 0,
+        }
+    }
+}
+
+impl TextRendering {
+    pub fn apply_gamma(&self, coverage: f32) -> f32 {
+        if self.gamma == 1.0 {
+            coverage
+        } else {
+            coverage.powf(1.0 / self.gamma as f32)
+        }
+    }
+}
+
+///
+/// The outset in pixels applied by stem darkening to a glyph of `size` pixels per em.
+///
+pub fn stem_darkening_amount(size: f64) -> Point {
+    if size > MAX_STEM_DARKENING_SIZE {
+        return Point { x: 0.0, y: 0.0 };
+    }
+
+    let [x, y] = STEM_DARKENING_FACTORS.map(|f| (f * size).min(MAX_STEM_DARKENING_AMOUNT));
+    Point { x, y }
+}
+
+fn points_mut(op: &mut PathOps) -> Vec<(&mut f64, &mut f64)> {
+    match op {
+        PathOps::MoveTo { x, y } | PathOps::LineTo { x, y } => vec![(x, y)],
+        PathOps::QuadTo { x1, y1, x2, y2 } => vec![(x1, y1), (x2, y2)],
+        PathOps::CubicTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3,
+        } => vec![(x1, y1), (x2, y2), (x3, y3)],
+        PathOps::Close => vec![],
+        _ => unreachable!("glyph outlines only have absolute commands"),
+    }
+}
+
+pub(crate) fn points(op: &PathOps) -> Vec<Point> {
+    match *op {
+        PathOps::MoveTo { x, y } | PathOps::LineTo { x, y } => vec![Point { x, y }],
+        PathOps::QuadTo { x1, y1, x2, y2 } => vec![Point { x: x1, y: y1 }, Point { x: x2, y: y2 }],
+        PathOps::CubicTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x3,
+            y3,
+        } => vec![
+            Point { x: x1, y: y1 },
+            Point { x: x2, y: y2 },
+            Point { x: x3, y: y3 },
+        ],
+        _ => vec![],
+    }
+}
+
+fn normalize(p: Point) -> Point {
+    let length = f64::hypot(p.x, p.y);
+    Point {
+        x: p.x / length,
+        y: p.y / length,
+    }
+}
+
+///
+/// Moves every point of the outline, control points included, outwards along the bisector of its
+/// edges so the edges move by `amount`, like `FT_Outline_EmboldenXY`.
+///
+fn embolden(path: &mut [PathOps], amount: Point) {
+    let mut contours: Vec<Vec<Point>> = vec![];
+    for op in path.iter_mut() {
+        if matches!(op, PathOps::MoveTo { .. }) {
+            contours.push(vec![]);
+        }
+        if let Some(contour) = contours.last_mut() {
+            contour.extend(points(op));
+        }
+    }
+
+    // Outer contours go one way and holes the other, the sign of the total area tells which side
+    // is outside.
+    let area: f64 = contours
+        .iter()
+        .flat_map(|contour| {
+            contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(a, b)| a.x * b.y - b.x * a.y)
+        })
+        .sum();
+    let orientation = area.signum();
+
+    let shifts = contours.iter().flat_map(|contour| {
+        let n = contour.len();
+        (0..n).map(move |i| {
+            let p = contour[i];
+            // The closest points on both sides that aren't at the same place.
+            let distinct = |j: &usize| contour[*j].distance_to(&p) > 1e-9;
+            let previous = (1..n).map(|k| (i + n - k) % n).find(distinct);
+            let next = (1..n).map(|k| (i + k) % n).find(distinct);
+
+            match (previous, next) {
+                (Some(previous), Some(next)) => {
+                    let a = normalize(Point {
+                        x: p.x - contour[previous].x,
+                        y: p.y - contour[previous].y,
+                    });
+                    let b = normalize(Point {
+                        x: contour[next].x - p.x,
+                        y: contour[next].y - p.y,
+                    });
+                    let d = 1.0 + a.x * b.x + a.y * b.y;
+
+                    // Spikes are left alone, their miter would be far too long.
+                    if d < 1.0 / 16.0 {
+                        return Point { x: 0.0, y: 0.0 };
+                    }
+
+                    Point {
+                        x: orientation * (a.y + b.y) / d * amount.x,
+                        y: -orientation * (a.x + b.x) / d * amount.y,
+                    }
+                }
+                _ => Point { x: 0.0, y: 0.0 },
+            }
+        })
+    });
+    let shifts = shifts.collect::<Vec<Point>>();
+
+    let mut shifts = shifts.iter();
+    for op in path.iter_mut() {
+        for (x, y) in points_mut(op) {
+            let shift = shifts.next().unwrap();
+            *x += shift.x;
+            *y += shift.y;
+        }
+    }
+}
+
+///
+/// Rounds the y of horizontal lines to whole pixels, keeping them in order and at least a pixel
+/// apart so thin stems don't vanish. Other points are moved proportionally between the closest
+/// lines above and below them.
+///
+fn snap_vertically(path: &mut [PathOps]) {
+    let mut edges = vec![];
+    let mut last = 0.0;
+
+    for op in path.iter() {
+        match *op {
+            PathOps::MoveTo { y, .. } => last = y,
+            PathOps::LineTo { y, .. } => {
+                // Stem darkening can move both ends by slightly different amounts.
+                if (y - last).abs() < 1e-9 {
+                    edges.push(y);
+                }
+                last = y;
+            }
+            PathOps::QuadTo { y2, .. } => last = y2,
+            PathOps::CubicTo { y3, .. } => last = y3,
+            _ => {}
+        }
+    }
+    edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    edges.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+
+    if edges.is_empty() {
+        return;
+    }
+
+    let mut snapped: Vec<f64> = Vec::with_capacity(edges.len());
+    for edge in &edges {
+        let y = match snapped.last() {
+            Some(previous) => edge.round().max(previous + 1.0),
+            None => edge.round(),
+        };
+        snapped.push(y);
+    }
+
+    let map = |y: f64| {
+        let i = edges.partition_point(|edge| *edge < y);
+
+        if i == 0 {
+            y + snapped[0] - edges[0]
+        } else if i == edges.len() {
+            y + snapped[i - 1] - edges[i - 1]
+        } else {
+            let t = (y - edges[i - 1]) / (edges[i] - edges[i - 1]);
+            snapped[i - 1] + t * (snapped[i] - snapped[i - 1])
+        }
+    };
+
+    for op in path.iter_mut() {
+        for (_, y) in points_mut(op) {
+            *y = map(*y);
+        }
+    }
+}
+
+///
+/// The outline of a glyph in pixels for `size` pixels per em, relative to its origin, with the
+/// options that change its shape applied.
+///
+pub(crate) fn glyph_path(
+    font: &mut Font,
+    glyph: GlyphId,
+    size: f64,
+    rendering: &TextRendering,
+) -> Vec<PathOps> {
+    let scale = font.scale(size);
+    let mut path = font.outline(glyph).to_vec();
+
+    for op in path.iter_mut() {
+        for (x, y) in points_mut(op) {
+            *x *= scale;
+            *y *= scale;
+        }
+    }
+    if rendering.stem_darkening {
+        embolden(&mut path, stem_darkening_amount(size));
+    }
+    if rendering.snap_vertically {
+        snap_vertically(&mut path);
+    }
+
+    path
+}
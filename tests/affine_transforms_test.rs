@@ -75,9 +75,8 @@ pub fn callback(canvas: &mut Canvas) {
     let cyan = FillStyle::Plain(Color::cyan());
 
     {
-        let translate = |i: usize| {
-            return move |p: &Point| translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
-        };
+        let translate =
+            |i: usize| move |p: &Point| translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
 
         canvas.draw_shape(&PATH, black, FILL_RULE, translate(0));
         canvas.draw_shape(&PATH, dark_slate_blue, FILL_RULE, translate(1));
@@ -88,7 +87,7 @@ pub fn callback(canvas: &mut Canvas) {
 
     {
         let rotate = |i: usize, angle: f64| {
-            return move |p: &Point| {
+            move |p: &Point| {
                 let p = translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
                 let around = Point {
                     x: TRANSLATIONS[i].x + 10.0,
@@ -96,7 +95,7 @@ pub fn callback(canvas: &mut Canvas) {
                 };
 
                 rotate_around(&p, &around, Angle::from_degrees(angle))
-            };
+            }
         };
 
         canvas.draw_shape(&PATH, dark_slate_blue, FILL_RULE, rotate(5, 25.0));
@@ -108,7 +107,7 @@ pub fn callback(canvas: &mut Canvas) {
 
     {
         let scale = |i: usize, sx: f64, sy: f64| {
-            return move |p: &Point| {
+            move |p: &Point| {
                 let p = translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
                 let center = Point {
                     x: TRANSLATIONS[i].x + 10.0,
@@ -116,7 +115,7 @@ pub fn callback(canvas: &mut Canvas) {
                 };
 
                 scale_around(&p, &center, sx, sy)
-            };
+            }
         };
 
         canvas.draw_shape(&PATH, dark_slate_blue, FILL_RULE, scale(10, 0.5, 0.5));
@@ -128,7 +127,7 @@ pub fn callback(canvas: &mut Canvas) {
 
     {
         let skew = |i: usize, x: f64, y: f64| {
-            return move |p: &Point| {
+            move |p: &Point| {
                 let p = translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
                 let center = Point {
                     x: TRANSLATIONS[i].x + 10.0,
@@ -136,7 +135,7 @@ pub fn callback(canvas: &mut Canvas) {
                 };
 
                 skew_around(&p, &center, Angle::from_degrees(x), Angle::from_degrees(y))
-            };
+            }
         };
 
         canvas.draw_shape(&PATH, dark_slate_blue, FILL_RULE, skew(15, 15.0, 15.0));
@@ -148,7 +147,7 @@ pub fn callback(canvas: &mut Canvas) {
 
     {
         let transform = |i: usize, sx: f64, sy: f64, skew_x: f64, skew_y: f64, rotate: f64| {
-            return move |p: &Point| {
+            move |p: &Point| {
                 let p = translate(p, TRANSLATIONS[i].x, TRANSLATIONS[i].y);
                 let center = Point {
                     x: TRANSLATIONS[i].x + 10.0,
@@ -164,7 +163,7 @@ pub fn callback(canvas: &mut Canvas) {
                 );
 
                 rotate_around(&p, &center, Angle::from_degrees(rotate))
-            };
+            }
         };
 
         canvas.draw_shape(
@@ -0,0 +1,226 @@
+// This tests the aliased fill mode, where pixels are covered when their center is inside a shape.
+
+use verg::{
+    color::{Antialiasing, Color, FillMode, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+};
+
+mod common;
+
+const ALIASED: FillMode = FillMode {
+    fill_rule: FillRule::NonZero,
+    antialiasing: Antialiasing::None,
+};
+
+fn polygon(points: &[Point]) -> Vec<PathOps> {
+    let mut path = vec![PathOps::MoveTo {
+        x: points[0].x,
+        y: points[0].y,
+    }];
+    path.extend(
+        points[1..]
+            .iter()
+            .map(|p| PathOps::LineTo { x: p.x, y: p.y }),
+    );
+    path.push(PathOps::Close);
+    path
+}
+
+// White on black, so the red channel is the coverage.
+fn coverage(path: &[PathOps], fill_mode: FillMode) -> Vec<f64> {
+    let mut canvas = common::new_canvas_with_background(24, 24, Color::black());
+    canvas.draw_shape(path, FillStyle::Plain(Color::white()), fill_mode, |p| *p);
+    canvas.buffer.chunks(4).map(|pixel| pixel[0]).collect()
+}
+
+#[test]
+fn shared_edges_leave_no_gaps_or_overlaps() {
+    const CELLS: usize = 6;
+    const CELL_SIZE: f64 = 4.0;
+
+    // A grid covering the canvas, inner vertices are moved around and some land on pixel centers.
+    let vertex = |i: usize, j: usize| {
+        let inner = i > 0 && i < CELLS && j > 0 && j < CELLS;
+        let offset = |k: usize| match (i * 7 + j * 3 + k) % 4 {
+            0 => 0.5,
+            1 => -0.75,
+            2 => 1.3,
+            _ => 0.0,
+        };
+        let (dx, dy) = if inner {
+            (offset(0), offset(1))
+        } else {
+            (0.0, 0.0)
+        };
+
+        Point {
+            x: i as f64 * CELL_SIZE + dx,
+            y: j as f64 * CELL_SIZE + dy,
+        }
+    };
+    let mut total = vec![0.0; 24 * 24];
+
+    for i in 0..CELLS {
+        for j in 0..CELLS {
+            let corners = [
+                vertex(i, j),
+                vertex(i + 1, j),
+                vertex(i + 1, j + 1),
+                vertex(i, j + 1),
+            ];
+            // Alternate the diagonal and the orientation of the triangles.
+            let triangles = if (i + j) % 2 == 0 {
+                [
+                    [corners[0], corners[1], corners[2]],
+                    [corners[0], corners[3], corners[2]],
+                ]
+            } else {
+                [
+                    [corners[1], corners[2], corners[3]],
+                    [corners[3], corners[0], corners[1]],
+                ]
+            };
+
+            for triangle in triangles {
+                for (sum, value) in total.iter_mut().zip(coverage(&polygon(&triangle), ALIASED)) {
+                    *sum += value;
+                }
+            }
+        }
+    }
+
+    for (i, sum) in total.iter().enumerate() {
+        assert_eq!(*sum, 1.0, "pixel {}x{}", i % 24, i / 24);
+    }
+}
+
+#[test]
+fn top_left_rule() {
+    // Covers the centers of columns 2 to 5 and rows 3 to 6, the centers on its right and bottom
+    // edges are left out.
+    let square = polygon(&[
+        Point { x: 2.5, y: 3.5 },
+        Point { x: 6.5, y: 3.5 },
+        Point { x: 6.5, y: 7.5 },
+        Point { x: 2.5, y: 7.5 },
+    ]);
+
+    for fill_mode in [
+        ALIASED,
+        FillMode {
+            fill_rule: FillRule::EvenOdd,
+            ..ALIASED
+        },
+    ] {
+        let coverage = coverage(&square, fill_mode);
+
+        for (i, value) in coverage.iter().enumerate() {
+            let (x, y) = (i % 24, i / 24);
+            let inside = (2..=5).contains(&x) && (3..=6).contains(&y);
+            assert_eq!(*value, if inside { 1.0 } else { 0.0 }, "{}x{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn fill_rules() {
+    let mut path = polygon(&[
+        Point { x: 2.0, y: 2.0 },
+        Point { x: 22.0, y: 2.0 },
+        Point { x: 22.0, y: 22.0 },
+        Point { x: 2.0, y: 22.0 },
+    ]);
+    path.extend(polygon(&[
+        Point { x: 8.0, y: 8.0 },
+        Point { x: 16.0, y: 8.0 },
+        Point { x: 16.0, y: 16.0 },
+        Point { x: 8.0, y: 16.0 },
+    ]));
+
+    let non_zero = coverage(&path, ALIASED);
+    let even_odd = coverage(
+        &path,
+        FillMode {
+            fill_rule: FillRule::EvenOdd,
+            ..ALIASED
+        },
+    );
+
+    assert!(non_zero
+        .iter()
+        .chain(&even_odd)
+        .all(|v| *v == 0.0 || *v == 1.0));
+    assert_eq!(non_zero[12 * 24 + 12], 1.0);
+    assert_eq!(even_odd[12 * 24 + 12], 0.0);
+    assert_eq!(even_odd[4 * 24 + 4], 1.0);
+}
+
+#[test]
+fn aliased() {
+    const SIZE: f64 = 60.0;
+
+    let mut canvas = common::new_canvas(2 * SIZE as usize, SIZE as usize);
+    let star = |offset: f64| {
+        polygon(
+            &(0..10)
+                .map(|i| {
+                    let angle = std::f64::consts::PI * i as f64 / 5.0;
+                    let radius = if i % 2 == 0 { 27.0 } else { 11.0 };
+                    Point {
+                        x: offset + SIZE / 2.0 + radius * angle.sin(),
+                        y: SIZE / 2.0 - radius * angle.cos(),
+                    }
+                })
+                .collect::<Vec<Point>>(),
+        )
+    };
+    let stops = [(Color::crimson(), 0.0), (Color::dark_slate_blue(), 1.0)];
+    let circle = [
+        PathOps::MoveTo { x: 0.0, y: 20.0 },
+        PathOps::QuadTo {
+            x1: 0.0,
+            y1: 0.0,
+            x2: 20.0,
+            y2: 0.0,
+        },
+        PathOps::QuadTo {
+            x1: 40.0,
+            y1: 0.0,
+            x2: 40.0,
+            y2: 20.0,
+        },
+        PathOps::QuadTo {
+            x1: 40.0,
+            y1: 40.0,
+            x2: 20.0,
+            y2: 40.0,
+        },
+        PathOps::QuadTo {
+            x1: 0.0,
+            y1: 40.0,
+            x2: 0.0,
+            y2: 20.0,
+        },
+    ];
+
+    // Antialiased on the left, aliased on the right.
+    for (offset, fill_mode) in [(0.0, FillMode::from(FillRule::NonZero)), (SIZE, ALIASED)] {
+        canvas.draw_shape(&circle, FillStyle::Plain(Color::yellow()), fill_mode, |p| {
+            Point {
+                x: p.x + offset + 2.5,
+                y: p.y + 17.0,
+            }
+        });
+        canvas.draw_shape(
+            &star(offset),
+            FillStyle::LinearGradient {
+                stops: &stops,
+                angle: verg::math::Angle::from_degrees(45.0),
+            },
+            fill_mode,
+            |p| *p,
+        );
+    }
+
+    common::check_against_reference("aliased", &canvas);
+}
@@ -0,0 +1,98 @@
+// This test zooms in on curves rendered with `CurveRendering::Analytic`, they should stay smooth
+// even though the canvas tolerance is large.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, CurveRendering, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+};
+
+mod common;
+
+const WIDTH: usize = 600;
+const HEIGHT: usize = 600;
+
+fn canvas_description() -> CanvasDescription {
+    CanvasDescription {
+        width: WIDTH,
+        height: HEIGHT,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: 60.0,
+            height: 60.0,
+        },
+        background_color: Color::white(),
+        tolerance: 10.0,
+        curve_rendering: CurveRendering::Analytic,
+        ..Default::default()
+    }
+}
+
+// Circle approximated with 4 cubic curves.
+const K: f64 = 0.552_284_749_831 * 20.0;
+const CIRCLE: [PathOps; 6] = [
+    PathOps::MoveTo { x: 30.0, y: 10.0 },
+    PathOps::CubicTo {
+        x1: 30.0 + K,
+        y1: 10.0,
+        x2: 50.0,
+        y2: 30.0 - K,
+        x3: 50.0,
+        y3: 30.0,
+    },
+    PathOps::CubicTo {
+        x1: 50.0,
+        y1: 30.0 + K,
+        x2: 30.0 + K,
+        y2: 50.0,
+        x3: 30.0,
+        y3: 50.0,
+    },
+    PathOps::CubicTo {
+        x1: 30.0 - K,
+        y1: 50.0,
+        x2: 10.0,
+        y2: 30.0 + K,
+        x3: 10.0,
+        y3: 30.0,
+    },
+    PathOps::CubicTo {
+        x1: 10.0,
+        y1: 30.0 - K,
+        x2: 30.0 - K,
+        y2: 10.0,
+        x3: 30.0,
+        y3: 10.0,
+    },
+    PathOps::Close,
+];
+
+const LEAF: [PathOps; 4] = [
+    PathOps::MoveTo { x: 20.0, y: 40.0 },
+    PathOps::QuadTo {
+        x1: 20.0,
+        y1: 20.0,
+        x2: 40.0,
+        y2: 20.0,
+    },
+    PathOps::QuadTo {
+        x1: 40.0,
+        y1: 40.0,
+        x2: 20.0,
+        y2: 40.0,
+    },
+    PathOps::Close,
+];
+
+const STEEL_BLUE: FillStyle = FillStyle::Plain(Color::steel_blue());
+const CORAL: FillStyle = FillStyle::Plain(Color::coral());
+
+fn callback(canvas: &mut Canvas) {
+    canvas.draw_shape(&CIRCLE, STEEL_BLUE, FillRule::NonZero, |p| *p);
+    canvas.draw_shape(&LEAF, CORAL, FillRule::NonZero, |p| *p);
+}
+
+implement_test! {
+    analytic_curve_test, canvas_description, callback |
+}
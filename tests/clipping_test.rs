@@ -0,0 +1,285 @@
+// This test draws shapes that cross every side of the canvas or are completely outside of it.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle, SubpixelOrder},
+    geometry::{BoundingBox, PathOps, Point},
+    math::translate,
+    renderer::{fill_coverage, fill_path, fill_path_subpixel},
+};
+
+mod common;
+
+const WIDTH: usize = 400;
+const HEIGHT: usize = 300;
+
+fn canvas_description() -> CanvasDescription {
+    CanvasDescription {
+        width: WIDTH,
+        height: HEIGHT,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: WIDTH as f64,
+            height: HEIGHT as f64,
+        },
+        background_color: Color::white(),
+        ..Default::default()
+    }
+}
+
+fn transparent_canvas_description() -> CanvasDescription {
+    CanvasDescription {
+        background_color: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.0,
+        },
+        ..canvas_description()
+    }
+}
+
+const DIAMOND: [PathOps; 5] = [
+    PathOps::MoveTo { x: 0.0, y: -80.0 },
+    PathOps::LineTo { x: 80.0, y: 0.0 },
+    PathOps::LineTo { x: 0.0, y: 80.0 },
+    PathOps::LineTo { x: -80.0, y: 0.0 },
+    PathOps::Close,
+];
+
+const BLOB: [PathOps; 4] = [
+    PathOps::MoveTo { x: -60.0, y: 0.0 },
+    PathOps::CubicTo {
+        x1: -60.0,
+        y1: -90.0,
+        x2: 60.0,
+        y2: -90.0,
+        x3: 60.0,
+        y3: 0.0,
+    },
+    PathOps::CubicTo {
+        x1: 60.0,
+        y1: 90.0,
+        x2: -60.0,
+        y2: 90.0,
+        x3: -60.0,
+        y3: 0.0,
+    },
+    PathOps::Close,
+];
+
+const CORAL: FillStyle = FillStyle::Plain(Color::coral());
+const STEEL_BLUE: FillStyle = FillStyle::Plain(Color::steel_blue());
+const CRIMSON: FillStyle = FillStyle::Plain(Color::crimson());
+const FOREST_GREEN: FillStyle = FillStyle::Plain(Color::forest_green());
+const WHITE: FillStyle = FillStyle::Plain(Color::white());
+
+fn callback(canvas: &mut Canvas) {
+    let (width, height) = (WIDTH as f64, HEIGHT as f64);
+    let at = |x: f64, y: f64| move |p: &Point| translate(p, x, y);
+
+    // Corners
+    canvas.draw_shape(&DIAMOND, CORAL, FillRule::NonZero, at(10.0, 15.0));
+    canvas.draw_shape(
+        &DIAMOND,
+        STEEL_BLUE,
+        FillRule::NonZero,
+        at(width - 10.5, 5.0),
+    );
+    canvas.draw_shape(
+        &DIAMOND,
+        CRIMSON,
+        FillRule::NonZero,
+        at(20.0, height - 3.25),
+    );
+    canvas.draw_shape(&BLOB, FOREST_GREEN, FillRule::NonZero, at(width, height));
+
+    // Sides
+    canvas.draw_shape(
+        &BLOB,
+        STEEL_BLUE,
+        FillRule::NonZero,
+        at(-20.5, height / 2.0),
+    );
+    canvas.draw_shape(
+        &BLOB,
+        CORAL,
+        FillRule::NonZero,
+        at(width + 30.0, height / 2.0),
+    );
+    canvas.draw_shape(
+        &DIAMOND,
+        FOREST_GREEN,
+        FillRule::NonZero,
+        at(width / 2.0, -40.0),
+    );
+    canvas.draw_shape(
+        &DIAMOND,
+        CRIMSON,
+        FillRule::NonZero,
+        at(width / 2.0, height + 60.0),
+    );
+
+    // Bigger than the canvas
+    canvas.draw_shape(
+        &[
+            PathOps::MoveTo {
+                x: -100.0,
+                y: -100.0,
+            },
+            PathOps::LineTo {
+                x: width + 100.0,
+                y: -100.0,
+            },
+            PathOps::LineTo {
+                x: width + 100.0,
+                y: height + 100.0,
+            },
+            PathOps::LineTo {
+                x: -100.0,
+                y: height + 100.0,
+            },
+            PathOps::Close,
+            PathOps::MoveTo { x: 100.0, y: 100.0 },
+            PathOps::LineTo {
+                x: 100.0,
+                y: height - 100.0,
+            },
+            PathOps::LineTo {
+                x: width - 100.0,
+                y: height - 100.0,
+            },
+            PathOps::LineTo {
+                x: width - 100.0,
+                y: 100.0,
+            },
+            PathOps::Close,
+        ],
+        FillStyle::Plain(Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.25,
+        }),
+        FillRule::NonZero,
+        |p| *p,
+    );
+}
+
+fn assert_accumulation_buffer_is_clean(canvas: &Canvas) {
+    for (i, cell) in canvas.accumulation_buffer.iter().enumerate() {
+        assert!(
+            cell.area == 0.0,
+            "Cell ({}, {}) still holds {}",
+            i % WIDTH,
+            i / WIDTH,
+            cell.area
+        );
+    }
+}
+
+#[test]
+fn shapes_outside_of_the_canvas_are_ignored() {
+    let mut canvas = Canvas::new(canvas_description());
+    let (width, height) = (WIDTH as f64, HEIGHT as f64);
+    let offsets = [
+        (-100.0, -100.0),
+        (width / 2.0, -100.0),
+        (width + 100.0, -100.0),
+        (width + 100.0, height / 2.0),
+        (width + 100.0, height + 100.0),
+        (width / 2.0, height + 100.0),
+        (-100.0, height + 100.0),
+        (-100.0, height / 2.0),
+        (-1000.0, height / 2.0),
+        (width / 2.0, 1e6),
+    ];
+
+    for (x, y) in offsets {
+        canvas.draw_shape(&DIAMOND, CRIMSON, FillRule::NonZero, |p| translate(p, x, y));
+        canvas.draw_shape(&BLOB, CORAL, FillRule::EvenOdd, |p| translate(p, x, y));
+    }
+
+    for pixel in canvas.buffer.chunks(4) {
+        assert_eq!(pixel, [1.0, 1.0, 1.0, 1.0]);
+    }
+    assert_accumulation_buffer_is_clean(&canvas);
+}
+
+#[test]
+fn edges_left_of_the_canvas_keep_their_winding() {
+    let mut canvas = Canvas::new(transparent_canvas_description());
+    let path = [
+        PathOps::MoveTo { x: -50.5, y: 10.0 },
+        PathOps::LineTo { x: 50.0, y: 10.0 },
+        PathOps::LineTo { x: 50.0, y: 20.0 },
+        PathOps::LineTo { x: -80.0, y: 20.0 },
+        PathOps::Close,
+    ];
+
+    canvas.draw_shape(&path, WHITE, FillRule::NonZero, |p| *p);
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let alpha = canvas.buffer[(y * WIDTH + x) * 4 + 3];
+            let expected = if (10..20).contains(&y) && x < 50 {
+                1.0
+            } else {
+                0.0
+            };
+
+            assert!(
+                (alpha - expected).abs() < 1e-6,
+                "Pixel ({}, {}) has alpha {}",
+                x,
+                y,
+                alpha
+            );
+        }
+    }
+    assert_accumulation_buffer_is_clean(&canvas);
+}
+
+#[test]
+fn shapes_crossing_the_borders_leave_no_coverage_behind() {
+    let mut canvas = Canvas::new(transparent_canvas_description());
+
+    callback(&mut canvas);
+    assert_accumulation_buffer_is_clean(&canvas);
+}
+
+#[test]
+fn empty_canvases_draw_nothing() {
+    // Shapes always map inside an empty canvas, the bounds can still extend past it.
+    let bounds = BoundingBox {
+        min_x: -5,
+        min_y: -5,
+        max_x: 5,
+        max_y: 5,
+    };
+
+    for (width, height) in [(0, 0), (WIDTH, 0), (0, HEIGHT)] {
+        let mut canvas = Canvas::new(CanvasDescription {
+            width,
+            height,
+            ..canvas_description()
+        });
+
+        fill_path(&mut canvas, CRIMSON, FillRule::NonZero, &bounds);
+        fill_path_subpixel(
+            &mut canvas,
+            CRIMSON,
+            FillRule::NonZero,
+            SubpixelOrder::Rgb,
+            &bounds,
+        );
+        fill_coverage(&mut canvas, CRIMSON, &bounds, &bounds, |_, _| 1.0);
+
+        assert!(canvas.buffer.is_empty());
+    }
+}
+
+implement_test! {
+    clipping_test, canvas_description, callback |
+}
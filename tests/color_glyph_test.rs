@@ -0,0 +1,189 @@
+// This tests color glyphs from the COLR and CPAL tables: solid layers, gradients, transforms, clips
+// and composite modes.
+
+use verg::{
+    canvas::Canvas,
+    color::{Color, FillStyle},
+    geometry::Point,
+    text::{Font, GlyphAtlas, TextLayout, TextRendering},
+};
+
+mod common;
+
+// The test glyphs of the COLR v1 specification, with a single COLR v0 glyph at U+F0E00.
+const COLR: &str = "media/colr_1.ttf";
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+const COLR_V0: char = '\u{F0E00}';
+
+fn with_palette(x: f64, y: f64, palette: u16) -> TextLayout {
+    TextLayout {
+        rendering: TextRendering {
+            palette,
+            ..Default::default()
+        },
+        ..TextLayout::from(Point { x, y })
+    }
+}
+
+// The distinct colors of the pixels, on 8 bits.
+fn colors(canvas: &Canvas) -> Vec<[u8; 4]> {
+    let mut colors = canvas
+        .to_u8()
+        .chunks(4)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        .collect::<Vec<[u8; 4]>>();
+    colors.sort_unstable();
+    colors.dedup();
+    colors
+}
+
+#[test]
+fn color_tables() {
+    let colr = Font::from_file(COLR).unwrap();
+    let roboto = Font::from_file(ROBOTO).unwrap();
+
+    assert_eq!(colr.color_palettes(), 3);
+    assert_eq!(roboto.color_palettes(), 0);
+    assert!(colr.is_color_glyph(colr.glyph_index(COLR_V0).unwrap()));
+    assert!(colr.is_color_glyph(colr.glyph_index('\u{F0100}').unwrap()));
+    assert!(!roboto.is_color_glyph(roboto.glyph_index('a').unwrap()));
+}
+
+#[test]
+fn layers_use_the_palette() {
+    let mut font = Font::from_file(COLR).unwrap();
+    let draw = |font: &mut Font, palette| {
+        let mut canvas = common::new_canvas(120, 120);
+        canvas.draw_text(
+            &COLR_V0.to_string(),
+            font,
+            100.0,
+            with_palette(10.0, 100.0, palette),
+            FillStyle::Plain(Color::black()),
+            |p| *p,
+        );
+        canvas
+    };
+
+    // Solid layers in several colors, not the fill style.
+    let default = draw(&mut font, 0);
+    assert!(colors(&default).len() > 8);
+    assert!(!colors(&default).contains(&[0, 0, 0, 255]));
+
+    let other = draw(&mut font, 1);
+    assert_ne!(colors(&default), colors(&other));
+    // Palettes that don't exist fall back to the last one.
+    assert_eq!(draw(&mut font, 2).buffer, draw(&mut font, 7).buffer);
+}
+
+#[test]
+fn unreadable_color_glyphs_use_their_outline() {
+    // The layers of the COLR v0 glyph point past the colors of the palettes.
+    let mut data = std::fs::read(COLR).unwrap();
+    let num_tables = u16::from_be_bytes([data[4], data[5]]) as usize;
+    let colr = (0..num_tables)
+        .map(|i| 12 + 16 * i)
+        .find(|record| &data[*record..record + 4] == b"COLR")
+        .map(|record| u32::from_be_bytes(data[record + 8..record + 12].try_into().unwrap()))
+        .unwrap() as usize;
+    let layers = u32::from_be_bytes(data[colr + 8..colr + 12].try_into().unwrap()) as usize;
+    let num_layers = u16::from_be_bytes([data[colr + 12], data[colr + 13]]) as usize;
+    for i in 0..num_layers {
+        let palette_index = colr + layers + 4 * i + 2;
+        data[palette_index..palette_index + 2].copy_from_slice(&[0xff, 0xfe]);
+    }
+    let mut font = Font::from_bytes(data, 0).unwrap();
+    assert!(font.is_color_glyph(font.glyph_index(COLR_V0).unwrap()));
+
+    let text = &COLR_V0.to_string();
+    let black = FillStyle::Plain(Color::black());
+    let mut direct = common::new_canvas(120, 120);
+    direct.draw_text(
+        text,
+        &mut font,
+        100.0,
+        Point { x: 10.0, y: 10.0 },
+        black,
+        |p| *p,
+    );
+    let mut cached = common::new_canvas(120, 120);
+    cached.draw_text_cached(
+        text,
+        &mut font,
+        100.0,
+        Point { x: 10.0, y: 10.0 },
+        black,
+        &mut GlyphAtlas::new(128, 128),
+    );
+
+    // The outline of the glyph, a square below the baseline, is filled with the fill style.
+    for canvas in [&direct, &cached] {
+        assert!(colors(canvas)
+            .iter()
+            .any(|color| color[..3] == [0, 0, 0] && color[3] > 250));
+    }
+}
+
+#[test]
+fn cached_text_paints_color_glyphs() {
+    let mut font = Font::from_file(COLR).unwrap();
+    let mut atlas = GlyphAtlas::new(64, 64);
+    let text = "\u{F0E00}\u{F0100}\u{F0200}";
+    let black = FillStyle::Plain(Color::black());
+    let (mut expected, mut actual) = (common::new_canvas(200, 80), common::new_canvas(200, 80));
+
+    expected.draw_text(
+        text,
+        &mut font,
+        60.0,
+        Point { x: 4.0, y: 64.0 },
+        black,
+        |p| *p,
+    );
+    actual.draw_text_cached(
+        text,
+        &mut font,
+        60.0,
+        Point { x: 4.0, y: 64.0 },
+        black,
+        &mut atlas,
+    );
+
+    assert_eq!(expected.buffer, actual.buffer);
+    assert!(atlas.is_empty());
+}
+
+#[test]
+fn color_glyphs() {
+    const SIZE: usize = 64;
+    const COLUMNS: usize = 10;
+
+    let mut font = Font::from_file(COLR).unwrap();
+    // A few glyphs of each group of the test font: gradients, sweeps, scales, extend modes,
+    // rotations, skews, transforms, translations, composite modes, foreground colors, clip boxes
+    // and the COLR v0 glyph.
+    let glyphs = [
+        0xF0100, 0xF0101, 0xF0102, 0xF0103, 0xF0200, 0xF0204, 0xF0208, 0xF0300, 0xF0302, 0xF0304,
+        0xF0500, 0xF0501, 0xF0502, 0xF0600, 0xF0601, 0xF0700, 0xF0701, 0xF0800, 0xF0801, 0xF0900,
+        0xF0A00, 0xF0A01, 0xF0A02, 0xF0A03, 0xF0A04, 0xF0A05, 0xF0A06, 0xF0A07, 0xF0A08, 0xF0A09,
+        0xF0A0A, 0xF0A0B, 0xF0B00, 0xF0B01, 0xF0B02, 0xF0B03, 0xF0C00, 0xF0C01, 0xF0D00, 0xF0E00,
+    ]
+    .map(|c| char::from_u32(c).unwrap());
+    let mut canvas = common::new_canvas(SIZE * COLUMNS, SIZE * glyphs.len().div_ceil(COLUMNS));
+
+    for (i, glyph) in glyphs.iter().enumerate() {
+        canvas.draw_text(
+            &glyph.to_string(),
+            &mut font,
+            SIZE as f64 * 0.8,
+            Point {
+                x: ((i % COLUMNS) * SIZE) as f64 + 0.1 * SIZE as f64,
+                y: ((i / COLUMNS + 1) * SIZE) as f64 - 0.15 * SIZE as f64,
+            },
+            FillStyle::Plain(Color::dark_slate_blue()),
+            |p| *p,
+        );
+    }
+
+    common::check_against_reference("color_glyphs", &canvas);
+}
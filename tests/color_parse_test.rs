@@ -0,0 +1,170 @@
+// These tests parse colors written in CSS syntax and format them back.
+
+use verg::color::{Color, ParseColorError};
+
+fn assert_color(text: &str, expected: (f64, f64, f64, f64), tolerance: f64) {
+    let color = Color::parse(text).unwrap_or_else(|err| panic!("`{}`: {}", text, err));
+    let actual = (color.r, color.g, color.b, color.a);
+    let close = [
+        (actual.0, expected.0),
+        (actual.1, expected.1),
+        (actual.2, expected.2),
+        (actual.3, expected.3),
+    ]
+    .iter()
+    .all(|(a, e)| (a - e).abs() <= tolerance);
+
+    assert!(close, "`{}` is {:?}, expected {:?}", text, actual, expected);
+}
+
+fn rgb8(r: u8, g: u8, b: u8) -> (f64, f64, f64, f64) {
+    (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0, 1.0)
+}
+
+#[test]
+fn hex_colors() {
+    assert_color("#f80", rgb8(0xff, 0x88, 0x00), 0.0);
+    assert_color(
+        "#F808",
+        (1.0, 0x88 as f64 / 255.0, 0.0, 0x88 as f64 / 255.0),
+        0.0,
+    );
+    assert_color("#ff7f50", rgb8(0xff, 0x7f, 0x50), 0.0);
+    assert_color(
+        "  #12345678 ",
+        (
+            0x12 as f64 / 255.0,
+            0x34 as f64 / 255.0,
+            0x56 as f64 / 255.0,
+            0x78 as f64 / 255.0,
+        ),
+        0.0,
+    );
+}
+
+#[test]
+fn named_colors() {
+    assert_color("rebeccapurple", rgb8(0x66, 0x33, 0x99), 0.0);
+    assert_color("CornflowerBlue", rgb8(0x64, 0x95, 0xed), 0.0);
+    assert_color("aliceblue", rgb8(0xf0, 0xf8, 0xff), 0.0);
+    assert_color("yellowgreen", rgb8(0x9a, 0xcd, 0x32), 0.0);
+    assert_color("transparent", (0.0, 0.0, 0.0, 0.0), 0.0);
+}
+
+#[test]
+fn rgb_functions() {
+    assert_color("rgb(255, 0, 51)", rgb8(255, 0, 51), 0.0);
+    assert_color("rgba(255, 0, 51, 0.5)", (1.0, 0.0, 0.2, 0.5), 1e-12);
+    assert_color("rgb(100% 0% 20% / 50%)", (1.0, 0.0, 0.2, 0.5), 1e-12);
+    assert_color("rgb(300 -10 none)", (1.0, 0.0, 0.0, 1.0), 0.0);
+    assert_color("RGBA(0 0 0/0.25)", (0.0, 0.0, 0.0, 0.25), 0.0);
+}
+
+#[test]
+fn hsl_and_hwb_functions() {
+    assert_color("hsl(120, 100%, 25%)", rgb8(0, 128, 0), 0.002);
+    assert_color("hsla(0.5turn 100% 50% / 0.3)", (0.0, 1.0, 1.0, 0.3), 1e-12);
+    assert_color("hsl(-120deg 100% 50%)", (0.0, 0.0, 1.0, 1.0), 1e-12);
+    assert_color("hsl(0 0% 50%)", (0.5, 0.5, 0.5, 1.0), 1e-12);
+    assert_color("hwb(0 0% 0%)", (1.0, 0.0, 0.0, 1.0), 1e-12);
+    assert_color("hwb(90 60% 60%)", (0.5, 0.5, 0.5, 1.0), 1e-12);
+    assert_color("hwb(240 20% 40%)", (0.2, 0.2, 0.6, 1.0), 1e-12);
+}
+
+#[test]
+fn lab_and_oklab_functions() {
+    assert_color("lab(100% 0 0)", (1.0, 1.0, 1.0, 1.0), 1e-4);
+    assert_color("lab(54.29 80.82 69.91)", (1.0, 0.0, 0.0, 1.0), 0.005);
+    assert_color("lch(54.29 106.84 40.85)", (1.0, 0.0, 0.0, 1.0), 0.005);
+    assert_color("oklab(1 0 0)", (1.0, 1.0, 1.0, 1.0), 1e-4);
+    assert_color("oklch(62.8% 0.2577 29.23)", (1.0, 0.0, 0.0, 1.0), 0.005);
+    assert_color(
+        "oklch(45.2% 0.313 264.05 / 0.5)",
+        (0.0, 0.0, 1.0, 0.5),
+        0.005,
+    );
+    assert_color("color(srgb 0.25 0.5 0.75)", (0.25, 0.5, 0.75, 1.0), 0.0);
+    assert_color("color(srgb-linear 0.2140 1 0)", (0.5, 1.0, 0.0, 1.0), 0.001);
+}
+
+#[test]
+fn invalid_colors() {
+    assert_eq!(Color::parse(" "), Err(ParseColorError::Empty));
+    assert_eq!(
+        Color::parse("#12345"),
+        Err(ParseColorError::InvalidHex("#12345".to_string()))
+    );
+    assert_eq!(
+        Color::parse("#ggg"),
+        Err(ParseColorError::InvalidHex("#ggg".to_string()))
+    );
+    assert_eq!(
+        Color::parse("bluish"),
+        Err(ParseColorError::UnknownName("bluish".to_string()))
+    );
+    assert_eq!(
+        Color::parse("cmyk(0 0 0 0)"),
+        Err(ParseColorError::UnknownFunction("cmyk".to_string()))
+    );
+    assert_eq!(
+        Color::parse("color(display-p3 1 0 0)"),
+        Err(ParseColorError::UnknownColorSpace("display-p3".to_string()))
+    );
+    assert_eq!(
+        Color::parse("rgb(1 2 3"),
+        Err(ParseColorError::MissingParenthesis)
+    );
+    assert_eq!(
+        Color::parse("rgb(1, 2)"),
+        Err(ParseColorError::InvalidArgumentCount {
+            function: "rgb".to_string(),
+            found: 2
+        })
+    );
+    assert_eq!(
+        Color::parse("hsl(10% 20% 30%)"),
+        Err(ParseColorError::InvalidComponent("10%".to_string()))
+    );
+    assert_eq!(
+        Color::parse("rgb(1 2 three)"),
+        Err(ParseColorError::InvalidComponent("three".to_string()))
+    );
+    assert!("rgb(1 2 3 / 0.5 0.5)".parse::<Color>().is_err());
+}
+
+#[test]
+fn display_round_trips() {
+    assert_eq!(Color::parse("#FF7F50").unwrap().to_string(), "#ff7f50");
+    assert_eq!(Color::parse("#0000").unwrap().to_string(), "#00000000");
+    assert_eq!(
+        Color::parse("rgb(0 0 0 / 0.5)").unwrap().to_string(),
+        "color(srgb 0 0 0 / 0.5)"
+    );
+    assert_eq!(Color::coral().to_string(), "color(srgb 1 0.498 0.313)");
+
+    // Colors outside of the sRGB gamut keep their channels.
+    let out_of_gamut = Color {
+        r: 1.25,
+        g: -0.5,
+        b: 0.5,
+        a: 1.0,
+    };
+    assert_eq!(out_of_gamut.to_string(), "color(srgb 1.25 -0.5 0.5)");
+    assert_eq!(Color::parse(&out_of_gamut.to_string()), Ok(out_of_gamut));
+
+    let colors = [
+        "tomato",
+        "#1234",
+        "hsl(200 50% 40% / 0.7)",
+        "oklch(70% 0.1 150)",
+        "lab(40 20 -30)",
+        "hwb(300 10% 20%)",
+    ];
+
+    for text in colors {
+        let color = Color::parse(text).unwrap();
+        let round_trip = Color::parse(&color.to_string()).unwrap();
+
+        assert_eq!(color, round_trip, "`{}` was written as `{}`", text, color);
+    }
+}
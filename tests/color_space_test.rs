@@ -0,0 +1,245 @@
+// These tests convert colors between color spaces and check the operations built on top of them.
+
+use verg::color::{Color, ColorSpace, Hsl, Hsv, Lab, Lch, LinearRgb, Oklab, Oklch, Xyz};
+
+fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+    let close = actual
+        .iter()
+        .zip(expected)
+        .all(|(a, e)| (a - e).abs() <= tolerance);
+
+    assert!(close, "got {:?}, expected {:?}", actual, expected);
+}
+
+fn channels(color: &Color) -> [f64; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+fn hex(text: &str) -> Color {
+    Color::parse(text).unwrap()
+}
+
+const SAMPLES: [&str; 8] = [
+    "#000000",
+    "#ffffff",
+    "#ff7f50",
+    "#663399",
+    "#20b2aa",
+    "#80808080",
+    "#0000ff",
+    "#f0e68c",
+];
+
+#[test]
+fn conversions_round_trip() {
+    for text in SAMPLES {
+        let color = hex(text);
+        let expected = channels(&color);
+
+        assert_close(
+            &channels(&Color::from(LinearRgb::from(color))),
+            &expected,
+            1e-9,
+        );
+        assert_close(&channels(&Color::from(Xyz::from(color))), &expected, 1e-9);
+        assert_close(&channels(&Color::from(Hsl::from(color))), &expected, 1e-9);
+        assert_close(&channels(&Color::from(Hsv::from(color))), &expected, 1e-9);
+        assert_close(&channels(&Color::from(Lab::from(color))), &expected, 1e-9);
+        // The hue of grays is dropped along with their rounding errors
+        assert_close(&channels(&Color::from(Lch::from(color))), &expected, 1e-6);
+        // The published OKLab matrices only have 10 digits
+        assert_close(&channels(&Color::from(Oklab::from(color))), &expected, 1e-5);
+        assert_close(&channels(&Color::from(Oklch::from(color))), &expected, 1e-5);
+    }
+}
+
+#[test]
+fn known_values() {
+    let red = Color::red();
+    let coral = hex("#ff7f50");
+
+    let xyz = Xyz::from(Color::white());
+    assert_close(&[xyz.x, xyz.y, xyz.z], &[0.950_46, 1.0, 1.089_06], 1e-4);
+
+    let linear = LinearRgb::from(hex("#808080"));
+    assert_close(&[linear.r], &[0.215_861], 1e-6);
+
+    let hsl = Hsl::from(coral);
+    assert_close(&[hsl.h, hsl.s, hsl.l], &[16.114, 1.0, 0.656_86], 1e-3);
+
+    let hsv = Hsv::from(coral);
+    assert_close(&[hsv.h, hsv.s, hsv.v], &[16.114, 0.686_27, 1.0], 1e-3);
+
+    let lab = Lab::from(red);
+    assert_close(&[lab.l, lab.a, lab.b], &[54.29, 80.80, 69.89], 0.05);
+
+    let lch = Lch::from(red);
+    assert_close(&[lch.l, lch.c, lch.h], &[54.29, 106.84, 40.85], 0.05);
+
+    let oklab = Oklab::from(Color::white());
+    assert_close(&[oklab.l, oklab.a, oklab.b], &[1.0, 0.0, 0.0], 1e-4);
+
+    let oklch = Oklch::from(red);
+    assert_close(
+        &[oklch.l, oklch.c, oklch.h],
+        &[0.627_96, 0.257_68, 29.234],
+        1e-3,
+    );
+
+    // Grays have no hue
+    let gray = Oklch::from(hex("#777777"));
+    assert_eq!(gray.h, 0.0);
+    assert!(gray.c < 1e-4);
+}
+
+#[test]
+fn contrast_ratio() {
+    assert_close(
+        &[Color::white().contrast_ratio(&Color::black())],
+        &[21.0],
+        1e-9,
+    );
+    assert_close(
+        &[Color::black().contrast_ratio(&Color::white())],
+        &[21.0],
+        1e-9,
+    );
+    assert_close(&[Color::red().contrast_ratio(&Color::red())], &[1.0], 1e-9);
+    assert_close(
+        &[hex("#777777").contrast_ratio(&Color::white())],
+        &[4.478],
+        1e-3,
+    );
+    assert_close(&[Color::white().relative_luminance()], &[1.0], 1e-9);
+}
+
+#[test]
+fn lighten_darken_and_saturate() {
+    let red = Color::red();
+
+    assert_close(&channels(&red.lighten(0.2)), &[1.0, 0.4, 0.4, 1.0], 1e-9);
+    assert_close(&channels(&red.darken(0.2)), &[0.6, 0.0, 0.0, 1.0], 1e-9);
+    assert_close(
+        &channels(&red.lighten(2.0)),
+        &channels(&Color::white()),
+        1e-9,
+    );
+    assert_close(&channels(&red.desaturate(1.0)), &[0.5, 0.5, 0.5, 1.0], 1e-9);
+
+    let muted = Color::from(Hsl {
+        h: 120.0,
+        s: 0.5,
+        l: 0.5,
+        alpha: 0.5,
+    });
+    let saturated = Hsl::from(muted.saturate(0.25));
+    assert_close(
+        &[saturated.h, saturated.s, saturated.l, saturated.alpha],
+        &[120.0, 0.75, 0.5, 0.5],
+        1e-9,
+    );
+}
+
+#[test]
+fn mix() {
+    let (white, black) = (Color::white(), Color::black());
+
+    assert_close(
+        &channels(&white.mix(&black, 0.5, ColorSpace::Srgb)),
+        &[0.5, 0.5, 0.5, 1.0],
+        1e-9,
+    );
+    // Mixing in linear light gives a lighter gray
+    assert_close(
+        &channels(&white.mix(&black, 0.5, ColorSpace::LinearSrgb)),
+        &[0.735_36, 0.735_36, 0.735_36, 1.0],
+        1e-5,
+    );
+    assert_close(
+        &channels(&white.mix(&black, 0.0, ColorSpace::Oklab)),
+        &channels(&white),
+        1e-6,
+    );
+    assert_close(
+        &channels(&white.mix(&black, 1.0, ColorSpace::Lab)),
+        &channels(&black),
+        1e-9,
+    );
+
+    // Hues take the shorter arc
+    let a = Color::from(Hsl {
+        h: 350.0,
+        s: 1.0,
+        l: 0.5,
+        alpha: 1.0,
+    });
+    let b = Color::from(Hsl {
+        h: 30.0,
+        s: 1.0,
+        l: 0.5,
+        alpha: 1.0,
+    });
+    assert_close(
+        &[Hsl::from(a.mix(&b, 0.5, ColorSpace::Hsl)).h],
+        &[10.0],
+        1e-9,
+    );
+    assert_close(
+        &[Hsl::from(b.mix(&a, 0.75, ColorSpace::Hsl)).h],
+        &[0.0],
+        1e-9,
+    );
+
+    // Grays don't pull the hue towards red
+    let blue_to_white = Oklch::from(Color::blue().mix(&white, 0.5, ColorSpace::Oklch));
+    assert_close(&[blue_to_white.h], &[Oklch::from(Color::blue()).h], 1e-6);
+
+    // Alpha is premultiplied, a transparent color doesn't tint the result
+    let transparent = Color {
+        a: 0.0,
+        ..Color::blue()
+    };
+    assert_close(
+        &channels(&Color::red().mix(&transparent, 0.5, ColorSpace::Srgb)),
+        &[1.0, 0.0, 0.0, 0.5],
+        1e-9,
+    );
+}
+
+#[test]
+fn gamut_mapping() {
+    let in_gamut = hex("#ff7f50");
+
+    assert!(in_gamut.in_gamut());
+    assert_eq!(in_gamut.gamut_map(), in_gamut);
+
+    let vivid = Color::from(Oklch {
+        l: 0.7,
+        c: 0.4,
+        h: 150.0,
+        alpha: 1.0,
+    });
+    assert!(!vivid.in_gamut());
+
+    let mapped = vivid.gamut_map();
+    let mapped_oklch = Oklch::from(mapped);
+
+    assert!(mapped.in_gamut());
+    assert_close(&[mapped_oklch.l], &[0.7], 0.02);
+    assert_close(&[mapped_oklch.h], &[150.0], 4.0);
+    assert!(mapped_oklch.c < 0.4);
+
+    let too_bright = Color::from(Oklch {
+        l: 1.2,
+        c: 0.1,
+        h: 30.0,
+        alpha: 0.5,
+    });
+    assert_eq!(
+        too_bright.gamut_map(),
+        Color {
+            a: 0.5,
+            ..Color::white()
+        }
+    );
+}
@@ -0,0 +1,203 @@
+// Helpers that explain how two images differ: a per-pixel diff image, PSNR, SSIM and the bounding
+// box of the changed pixels.
+
+use image::{imageops, Rgba, RgbaImage};
+
+// Size of the windows SSIM is computed on and the distance between two windows.
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+
+// Stabilizing constants from the SSIM paper for 8-bit channels.
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedArea {
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageComparison {
+    pub changed_pixels: usize,
+    pub max_difference: u8,
+    // Peak signal-to-noise ratio in decibels, infinite for identical images.
+    pub psnr: f64,
+    // Mean structural similarity of the luminance, 1 for identical images.
+    pub ssim: f64,
+    // Inclusive bounds of the changed pixels, `None` if the images are identical.
+    pub changed_area: Option<ChangedArea>,
+}
+
+impl ImageComparison {
+    pub fn is_identical(&self) -> bool {
+        self.changed_pixels == 0
+    }
+}
+
+impl std::fmt::Display for ImageComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} pixels changed (max channel difference: {}), PSNR: {:.2}dB, SSIM: {:.5}",
+            self.changed_pixels, self.max_difference, self.psnr, self.ssim
+        )?;
+
+        if let Some(area) = self.changed_area {
+            write!(
+                f,
+                ", changes within ({}, {}) - ({}, {})",
+                area.min_x, area.min_y, area.max_x, area.max_y
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn pixel_difference(expected: &Rgba<u8>, actual: &Rgba<u8>) -> u8 {
+    (0..4)
+        .map(|channel| (expected[channel] as i16 - actual[channel] as i16).unsigned_abs() as u8)
+        .max()
+        .unwrap_or(0)
+}
+
+fn luminance(pixel: &Rgba<u8>) -> f64 {
+    let alpha = pixel[3] as f64 / 255.0;
+    alpha * (0.2126 * pixel[0] as f64 + 0.7152 * pixel[1] as f64 + 0.0722 * pixel[2] as f64)
+}
+
+fn ssim(expected: &RgbaImage, actual: &RgbaImage) -> f64 {
+    let (width, height) = expected.dimensions();
+    let window = SSIM_WINDOW.min(width).min(height);
+
+    if window == 0 {
+        return 1.0;
+    }
+
+    let mut total = 0.0;
+    let mut num_windows = 0;
+    let mut y = 0;
+
+    while y + window <= height {
+        let mut x = 0;
+
+        while x + window <= width {
+            let n = (window * window) as f64;
+            let (mut sum_e, mut sum_a, mut sum_ee, mut sum_aa, mut sum_ea) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+
+            for wy in y..y + window {
+                for wx in x..x + window {
+                    let e = luminance(expected.get_pixel(wx, wy));
+                    let a = luminance(actual.get_pixel(wx, wy));
+
+                    sum_e += e;
+                    sum_a += a;
+                    sum_ee += e * e;
+                    sum_aa += a * a;
+                    sum_ea += e * a;
+                }
+            }
+
+            let (mean_e, mean_a) = (sum_e / n, sum_a / n);
+            let variance_e = sum_ee / n - mean_e * mean_e;
+            let variance_a = sum_aa / n - mean_a * mean_a;
+            let covariance = sum_ea / n - mean_e * mean_a;
+
+            total += ((2.0 * mean_e * mean_a + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_e * mean_e + mean_a * mean_a + SSIM_C1)
+                    * (variance_e + variance_a + SSIM_C2));
+            num_windows += 1;
+            x += SSIM_STRIDE;
+        }
+
+        y += SSIM_STRIDE;
+    }
+
+    total / num_windows as f64
+}
+
+pub fn compare(expected: &RgbaImage, actual: &RgbaImage) -> ImageComparison {
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "Can't compare images of different sizes"
+    );
+
+    let mut changed_pixels = 0;
+    let mut max_difference = 0;
+    let mut squared_error = 0.0;
+    let mut changed_area: Option<ChangedArea> = None;
+
+    for ((x, y, e), a) in expected.enumerate_pixels().zip(actual.pixels()) {
+        for channel in 0..4 {
+            let error = e[channel] as f64 - a[channel] as f64;
+            squared_error += error * error;
+        }
+
+        let difference = pixel_difference(e, a);
+
+        if difference == 0 {
+            continue;
+        }
+
+        changed_pixels += 1;
+        max_difference = max_difference.max(difference);
+        changed_area = Some(match changed_area {
+            None => ChangedArea {
+                min_x: x,
+                min_y: y,
+                max_x: x,
+                max_y: y,
+            },
+            Some(area) => ChangedArea {
+                min_x: area.min_x.min(x),
+                min_y: area.min_y.min(y),
+                max_x: area.max_x.max(x),
+                max_y: area.max_y.max(y),
+            },
+        });
+    }
+
+    let num_samples = (expected.width() * expected.height() * 4).max(1) as f64;
+    let mean_squared_error = squared_error / num_samples;
+    let psnr = 10.0 * f64::log10(255.0 * 255.0 / mean_squared_error);
+
+    ImageComparison {
+        changed_pixels,
+        max_difference,
+        psnr,
+        ssim: ssim(expected, actual),
+        changed_area,
+    }
+}
+
+// Dims `expected` and paints every changed pixel red, brighter for bigger differences.
+pub fn diff_image(expected: &RgbaImage, actual: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let e = expected.get_pixel(x, y);
+        let difference = pixel_difference(e, actual.get_pixel(x, y));
+
+        if difference == 0 {
+            let gray = 64 + (luminance(e) / 4.0) as u8;
+            Rgba([gray, gray, gray, 255])
+        } else {
+            Rgba([128 + difference / 2, 0, 0, 255])
+        }
+    })
+}
+
+// Places the expected image, the actual image and their diff next to each other.
+pub fn side_by_side(expected: &RgbaImage, actual: &RgbaImage) -> RgbaImage {
+    let (width, height) = expected.dimensions();
+    let mut result = RgbaImage::new(width * 3, height);
+
+    imageops::replace(&mut result, expected, 0, 0);
+    imageops::replace(&mut result, actual, width, 0);
+    imageops::replace(&mut result, &diff_image(expected, actual), 2 * width, 0);
+
+    result
+}
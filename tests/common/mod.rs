@@ -0,0 +1,149 @@
+use image::RgbaImage;
+use std::path::PathBuf;
+use verg::canvas::{Canvas, CanvasDescription, ViewBox};
+use verg::color::Color;
+use verg::geometry::PathOps;
+use verg::renderer::blend_func;
+
+pub mod image_diff;
+
+// Set this environment variable to overwrite the reference images with the current output.
+const UPDATE_REFERENCES: &str = "UPDATE_REFERENCES";
+
+// Another false positive, this function is used in a lot of tests.
+#[allow(dead_code)]
+pub fn default_blending(src: &Color, dest: &Color) -> Color {
+    blend_func::source_over(src, dest)
+}
+
+#[allow(dead_code)]
+pub fn default_callback(_canvas: &mut Canvas) {}
+
+// A white canvas with a viewbox in pixels.
+#[allow(dead_code)]
+pub fn new_canvas(width: usize, height: usize) -> Canvas {
+    new_canvas_with_background(width, height, Color::white())
+}
+
+#[allow(dead_code)]
+pub fn new_canvas_with_background(width: usize, height: usize, background_color: Color) -> Canvas {
+    Canvas::new(CanvasDescription {
+        width,
+        height,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: width as f64,
+            height: height as f64,
+        },
+        background_color,
+        ..Default::default()
+    })
+}
+
+// A rectangle with its top-left corner at `x`, `y`.
+#[allow(dead_code)]
+pub fn rect(x: f64, y: f64, width: f64, height: f64) -> [PathOps; 5] {
+    [
+        PathOps::MoveTo { x, y },
+        PathOps::LineTo { x: x + width, y },
+        PathOps::LineTo {
+            x: x + width,
+            y: y + height,
+        },
+        PathOps::LineTo { x, y: y + height },
+        PathOps::Close,
+    ]
+}
+
+// Compares the canvas against `tests/references/<name>.png`. On mismatch the actual image and a
+// side-by-side diff (expected, actual, changed pixels) are written to the target directory.
+#[allow(dead_code)]
+pub fn check_against_reference(name: &str, canvas: &Canvas) {
+    let actual = RgbaImage::from_raw(
+        canvas.desc.width as u32,
+        canvas.desc.height as u32,
+        canvas.to_u8(),
+    )
+    .unwrap();
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("references")
+        .join(format!("{}.png", name));
+
+    if std::env::var_os(UPDATE_REFERENCES).is_some() {
+        actual.save(&reference_path).unwrap();
+        return;
+    }
+
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("image_diffs");
+    let actual_path = output_dir.join(format!("{}.png", name));
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let expected = match image::open(&reference_path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(err) => {
+            actual.save(&actual_path).unwrap();
+            panic!(
+                "Couldn't load `{}` ({}), the output was written to `{}`. Run the tests with `{}=1` to accept it.",
+                reference_path.display(),
+                err,
+                actual_path.display(),
+                UPDATE_REFERENCES
+            );
+        }
+    };
+
+    if expected.dimensions() != actual.dimensions() {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "`{}` should be {:?} but is {:?}, the output was written to `{}`",
+            name,
+            expected.dimensions(),
+            actual.dimensions(),
+            actual_path.display()
+        );
+    }
+
+    let comparison = image_diff::compare(&expected, &actual);
+
+    if comparison.is_identical() {
+        return;
+    }
+
+    let diff_path = output_dir.join(format!("{}.diff.png", name));
+    actual.save(&actual_path).unwrap();
+    image_diff::side_by_side(&expected, &actual)
+        .save(&diff_path)
+        .unwrap();
+
+    panic!(
+        "`{}` differs from its reference: {}. See `{}`, run the tests with `{}=1` to accept `{}`.",
+        name,
+        comparison,
+        diff_path.display(),
+        UPDATE_REFERENCES,
+        actual_path.display()
+    );
+}
+
+#[macro_export]
+macro_rules! implement_test {
+    ( $($name:ident, $canvas:ident, $custom:ident)? | $($path:expr, $fill_style:expr, $fill_rule:expr, $blend:ident),* ) => {
+        #[test]
+        fn $($name)?() {
+            let _transform = |p: &Point| -> Point { *p };
+            let mut canvas = Canvas::new($($canvas)?());
+            $(
+                canvas.set_blending_function($blend);
+                canvas.draw_shape(&($path), $fill_style, $fill_rule, _transform);
+            )*
+
+            $(
+                $custom(&mut canvas);
+            )?
+
+            common::check_against_reference(stringify!($($name)?), &canvas);
+        }
+    }
+}
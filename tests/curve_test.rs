@@ -25,6 +25,7 @@ fn canvas_description() -> CanvasDescription {
         },
         background_color: Color::white(),
         tolerance: 0.25,
+        ..Default::default()
     }
 }
 
@@ -299,16 +300,14 @@ fn callback(canvas: &mut Canvas) {
             x: MOON_WIDTH / 2.0,
             y: MOON_HEIGHT / 2.0,
         };
-        let p = rotate_around(&p, &center, Angle::from_degrees(65.0));
+        let p = rotate_around(p, &center, Angle::from_degrees(65.0));
         let p = scale_around(&p, &center, 1.5, 1.5);
 
         translate(&p, 280.0, 180.0)
     };
     canvas.draw_shape(&MOON, YELLOW, FILL_RULE, transform);
 
-    let transform = |x: f64, y: f64| {
-        return move |p: &Point| translate(&p, x, y);
-    };
+    let transform = |x: f64, y: f64| move |p: &Point| translate(p, x, y);
     canvas.draw_shape(&EYE, BLACK, FILL_RULE, transform(400.0, 50.0));
     canvas.draw_shape(&EYE, BLACK, FILL_RULE, transform(400.0 + EYE_WIDTH, 50.0));
     canvas.draw_shape(&SMALL_EYE, BLACK, FILL_RULE, transform(433.0, 220.0));
@@ -0,0 +1,207 @@
+// This tests the dithering applied when the canvas is quantized to 8 bits.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, Compositing, ViewBox},
+    color::{linear_to_srgb, Color, FillRule, FillStyle},
+    dither::Dithering,
+    geometry::PathOps,
+    math::Angle,
+};
+
+mod common;
+
+const MODES: [Dithering; 4] = [
+    Dithering::None,
+    Dithering::Bayer,
+    Dithering::BlueNoise { seed: 42 },
+    Dithering::FloydSteinberg,
+];
+
+fn viewbox(width: usize, height: usize) -> ViewBox {
+    ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: width as f64,
+        height: height as f64,
+    }
+}
+
+fn flat_canvas(size: usize, value: f64, dithering: Dithering) -> Canvas {
+    Canvas::new(CanvasDescription {
+        width: size,
+        height: size,
+        viewbox: viewbox(size, size),
+        background_color: Color {
+            r: value,
+            g: value,
+            b: value,
+            a: 1.0,
+        },
+        dithering,
+        ..Default::default()
+    })
+}
+
+// The red channel of every pixel.
+fn reds(canvas: &Canvas) -> Vec<u8> {
+    canvas.to_u8().chunks(4).map(|pixel| pixel[0]).collect()
+}
+
+fn mean(values: &[u8]) -> f64 {
+    values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64
+}
+
+#[test]
+fn flat_areas_keep_their_value() {
+    for dithering in &MODES[1..] {
+        let reds = reds(&flat_canvas(64, 100.3 / 255.0, *dithering));
+
+        assert!(
+            reds.iter().all(|v| *v == 100 || *v == 101),
+            "{:?}",
+            dithering
+        );
+        assert!((mean(&reds) - 100.3).abs() < 0.01, "{:?}", dithering);
+    }
+
+    // Without dithering the fraction is lost.
+    assert!(reds(&flat_canvas(64, 100.3 / 255.0, Dithering::None))
+        .iter()
+        .all(|v| *v == 100));
+
+    // Exact values aren't dithered.
+    for dithering in &MODES[1..] {
+        assert!(reds(&flat_canvas(16, 100.0 / 255.0, *dithering))
+            .iter()
+            .all(|v| *v == 100));
+    }
+}
+
+#[test]
+fn ordered_dithering_covers_each_level_exactly() {
+    // Both matrices tile a 64x64 canvas and every threshold appears as often.
+    for dithering in [Dithering::Bayer, Dithering::BlueNoise { seed: 7 }] {
+        let reds = reds(&flat_canvas(64, 100.25 / 255.0, dithering));
+
+        assert_eq!(reds.iter().filter(|v| **v == 101).count(), 1024);
+    }
+}
+
+#[test]
+fn blue_noise_is_spread_evenly() {
+    let reds = reds(&flat_canvas(
+        64,
+        100.25 / 255.0,
+        Dithering::BlueNoise { seed: 1 },
+    ));
+
+    // Random noise would often leave an 8x8 block far from the expected 16 raised pixels.
+    for block_y in 0..8 {
+        for block_x in 0..8 {
+            let raised = (0..64)
+                .filter(|i| reds[(block_y * 8 + i / 8) * 64 + block_x * 8 + i % 8] == 101)
+                .count();
+
+            assert!(
+                (10..=22).contains(&raised),
+                "{} in block {}x{}",
+                raised,
+                block_x,
+                block_y
+            );
+        }
+    }
+}
+
+#[test]
+fn blue_noise_is_deterministic() {
+    let value = 50.5 / 255.0;
+    let first = flat_canvas(64, value, Dithering::BlueNoise { seed: 3 }).to_u8();
+
+    assert_eq!(
+        first,
+        flat_canvas(64, value, Dithering::BlueNoise { seed: 3 }).to_u8()
+    );
+    assert_ne!(
+        first,
+        flat_canvas(64, value, Dithering::BlueNoise { seed: 4 }).to_u8()
+    );
+}
+
+#[test]
+fn dithering_encodes_linear_light() {
+    let linear = 0.2;
+    let canvas = Canvas::new(CanvasDescription {
+        compositing: Compositing::LinearLight,
+        background_color: Color {
+            r: linear_to_srgb(linear),
+            g: linear_to_srgb(linear),
+            b: linear_to_srgb(linear),
+            a: 1.0,
+        },
+        ..flat_canvas(32, 0.0, Dithering::FloydSteinberg).desc
+    });
+
+    let expected = linear_to_srgb(linear) * 255.0;
+    assert!((mean(&reds(&canvas)) - expected).abs() < 0.02);
+}
+
+#[test]
+fn dithering() {
+    const WIDTH: usize = 600;
+    const BAND_HEIGHT: usize = 60;
+
+    let stops = [
+        (Color::parse("#202030").unwrap(), 0.0),
+        (Color::parse("#2a2a3c").unwrap(), 1.0),
+    ];
+    let band = [
+        PathOps::MoveTo { x: 0.0, y: 0.0 },
+        PathOps::LineTo {
+            x: WIDTH as f64,
+            y: 0.0,
+        },
+        PathOps::LineTo {
+            x: WIDTH as f64,
+            y: BAND_HEIGHT as f64,
+        },
+        PathOps::LineTo {
+            x: 0.0,
+            y: BAND_HEIGHT as f64,
+        },
+        PathOps::Close,
+    ];
+    let mut pixels = Vec::<u8>::new();
+
+    // The same subtle gradient quantized with every mode, stacked vertically.
+    for dithering in MODES {
+        let mut canvas = Canvas::new(CanvasDescription {
+            width: WIDTH,
+            height: BAND_HEIGHT,
+            viewbox: viewbox(WIDTH, BAND_HEIGHT),
+            dithering,
+            ..Default::default()
+        });
+        canvas.draw_shape(
+            &band,
+            FillStyle::LinearGradient {
+                stops: &stops,
+                angle: Angle::from_degrees(0.0),
+            },
+            FillRule::NonZero,
+            |p| *p,
+        );
+        pixels.extend(canvas.to_u8());
+    }
+
+    let mut canvas = Canvas::new(CanvasDescription {
+        width: WIDTH,
+        height: BAND_HEIGHT * MODES.len(),
+        viewbox: viewbox(WIDTH, BAND_HEIGHT * MODES.len()),
+        ..Default::default()
+    });
+    // The middle of each 8-bit level is truncated to that level by `to_u8`.
+    canvas.buffer = pixels.iter().map(|v| (*v as f64 + 0.5) / 255.0).collect();
+
+    common::check_against_reference("dithering", &canvas);
+}
@@ -0,0 +1,225 @@
+// This tests the glyph atlas, caching the coverage of glyphs drawn with `draw_text_cached`.
+
+use verg::{
+    canvas::{Canvas, CurveRendering},
+    color::{Color, FillStyle},
+    geometry::Point,
+    math::Angle,
+    recording::BlendMode,
+    text::{Font, GlyphAtlas, TextLayout, TextRendering},
+};
+
+mod common;
+
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+
+fn max_difference(a: &Canvas, b: &Canvas) -> f64 {
+    a.buffer
+        .iter()
+        .zip(&b.buffer)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max)
+}
+
+#[test]
+fn cached_text_matches_draw_text() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(256, 256);
+    // Fine enough for the glyphs after the first to be placed almost where `draw_text` puts them.
+    atlas.subpixel_positions = 64;
+    let stops = [(Color::crimson(), 0.0), (Color::dark_slate_blue(), 1.0)];
+
+    for fill_style in [
+        FillStyle::Plain(Color::black()),
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(0.0),
+        },
+    ] {
+        // The origin is on a whole pixel vertically and on a subpixel position horizontally, so
+        // the glyphs are rasterized at the same place either way.
+        let position = Point { x: 10.25, y: 30.0 };
+        let mut expected = common::new_canvas(200, 40);
+        let mut actual = common::new_canvas(200, 40);
+
+        expected.draw_text("Hill jump", &mut font, 24.0, position, fill_style, |p| *p);
+        actual.draw_text_cached(
+            "Hill jump",
+            &mut font,
+            24.0,
+            position,
+            fill_style,
+            &mut atlas,
+        );
+
+        assert!(max_difference(&expected, &actual) < 0.01);
+    }
+}
+
+#[test]
+fn cached_text_follows_the_canvas() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(256, 256);
+    atlas.subpixel_positions = 64;
+    let black = FillStyle::Plain(Color::black());
+    let position = Point { x: 10.25, y: 30.0 };
+
+    // The same atlas holds the glyphs of canvases flattening curves differently.
+    for (tolerance, curve_rendering) in [
+        (0.1, CurveRendering::Flattened),
+        (2.0, CurveRendering::Flattened),
+        (2.0, CurveRendering::Analytic),
+    ] {
+        let (mut expected, mut actual) = (common::new_canvas(200, 40), common::new_canvas(200, 40));
+        for canvas in [&mut expected, &mut actual] {
+            canvas.desc.tolerance = tolerance;
+            canvas.desc.curve_rendering = curve_rendering;
+        }
+
+        expected.draw_text("Sobo", &mut font, 24.0, position, black, |p| *p);
+        actual.draw_text_cached("Sobo", &mut font, 24.0, position, black, &mut atlas);
+
+        assert!(
+            max_difference(&expected, &actual) < 0.01,
+            "{} {:?}",
+            tolerance,
+            curve_rendering
+        );
+    }
+}
+
+#[test]
+fn glyphs_are_rasterized_once() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(256, 256);
+    let mut canvas = common::new_canvas(200, 100);
+    let black = FillStyle::Plain(Color::black());
+    let mut draw = |text: &str, font: &mut Font, atlas: &mut GlyphAtlas, size: f64, x: f64| {
+        canvas.draw_text_cached(text, font, size, Point { x, y: 50.0 }, black, atlas);
+    };
+
+    draw("a", &mut font, &mut atlas, 16.0, 10.0);
+    assert_eq!(atlas.len(), 1);
+    draw("a", &mut font, &mut atlas, 16.0, 10.0);
+    assert_eq!(atlas.len(), 1);
+    // The space has an entry but no coverage.
+    draw(" ", &mut font, &mut atlas, 16.0, 10.0);
+    assert_eq!(atlas.len(), 2);
+    // Another size, subpixel position or font are cached separately.
+    draw("a", &mut font, &mut atlas, 20.0, 10.0);
+    assert_eq!(atlas.len(), 3);
+    draw("a", &mut font, &mut atlas, 16.0, 10.5);
+    assert_eq!(atlas.len(), 4);
+    draw("a", &mut font, &mut atlas, 16.0, 10.55);
+    assert_eq!(atlas.len(), 4);
+    draw(
+        "a",
+        &mut Font::from_file(ROBOTO).unwrap(),
+        &mut atlas,
+        16.0,
+        10.0,
+    );
+    assert_eq!(atlas.len(), 5);
+}
+
+#[test]
+fn full_atlas() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let black = FillStyle::Plain(Color::black());
+    let position = Point { x: 4.0, y: 60.0 };
+    let mut expected = common::new_canvas(300, 80);
+    expected.draw_text("Atlas overflow", &mut font, 40.0, position, black, |p| *p);
+
+    // A few glyphs fill the atlas, it's cleared to make room for the next ones. A glyph larger
+    // than the atlas is drawn without being cached.
+    for size in [64, 24] {
+        let mut atlas = GlyphAtlas::new(size, size);
+        atlas.subpixel_positions = 64;
+        let mut actual = common::new_canvas(300, 80);
+        actual.draw_text_cached(
+            "Atlas overflow",
+            &mut font,
+            40.0,
+            position,
+            black,
+            &mut atlas,
+        );
+
+        assert!(atlas.len() < "Atlas overflow".len());
+        assert!(max_difference(&expected, &actual) < 0.01);
+    }
+}
+
+#[test]
+fn oversized_glyphs_only_touch_their_pixels() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(8, 8);
+    let mut canvas = common::new_canvas(200, 60);
+    canvas.set_blend_mode(BlendMode::Source);
+    // Stem darkening moves the outlines out of the bounds of the glyphs in the font.
+    let layout = TextLayout {
+        rendering: TextRendering {
+            stem_darkening: true,
+            ..Default::default()
+        },
+        ..TextLayout::from(Point { x: 10.0, y: 50.0 })
+    };
+
+    canvas.draw_text_cached(
+        "l        l",
+        &mut font,
+        40.0,
+        layout,
+        FillStyle::Plain(Color::black()),
+        &mut atlas,
+    );
+
+    // The second glyph leaves the first one alone, and the accumulation buffer is left clean.
+    let first_glyph = (0..60)
+        .flat_map(|y| (0..100).map(move |x| (y * 200 + x) * 4))
+        .filter(|offset| canvas.buffer[offset + 3] > 0.9 && canvas.buffer[*offset] < 0.1)
+        .count();
+    assert!(first_glyph > 20, "{}", first_glyph);
+    assert!(canvas
+        .accumulation_buffer
+        .iter()
+        .all(|cell| cell.area == 0.0));
+}
+
+#[test]
+fn glyph_atlas() {
+    const WIDTH: usize = 128;
+
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(WIDTH, WIDTH);
+    let mut canvas = common::new_canvas(2 * WIDTH, WIDTH);
+    let lines = ["File  Edit  View", "Open...", "Save as..."];
+
+    // A menu drawn twice, on the left, and the atlas on the right.
+    for frame in 0..2 {
+        for (i, line) in lines.iter().enumerate() {
+            canvas.draw_text_cached(
+                line,
+                &mut font,
+                if i == 0 { 14.0 } else { 12.0 },
+                TextLayout::from(Point {
+                    x: 6.0 + frame as f64 * 0.5,
+                    y: 20.0 + i as f64 * 18.0 + frame as f64 * 60.0,
+                }),
+                FillStyle::Plain(Color::black()),
+                &mut atlas,
+            );
+        }
+    }
+
+    for y in 0..WIDTH {
+        for x in 0..WIDTH {
+            let offset = (y * 2 * WIDTH + WIDTH + x) * 4;
+            // The middle of each 8-bit level is truncated to that level by `to_u8`.
+            let value = (atlas.mask.to_u8()[y * WIDTH + x] as f64 + 0.5) / 255.0;
+            canvas.buffer[offset..offset + 4].copy_from_slice(&[value, value, value, 1.0]);
+        }
+    }
+
+    common::check_against_reference("glyph_atlas", &canvas);
+}
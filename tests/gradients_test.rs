@@ -22,6 +22,7 @@ fn canvas_description() -> CanvasDescription {
         },
         background_color: Color::dark_slate_blue(),
         tolerance: 1.0,
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,96 @@
+// These tests check the helpers that explain why a rendered image doesn't match its reference.
+
+use crate::common::image_diff::{compare, diff_image, side_by_side, ChangedArea};
+use image::{Rgba, RgbaImage};
+
+mod common;
+
+fn checkerboard() -> RgbaImage {
+    RgbaImage::from_fn(32, 32, |x, y| {
+        if (x / 4 + y / 4) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    })
+}
+
+#[test]
+fn identical_images() {
+    let image = checkerboard();
+    let comparison = compare(&image, &image);
+
+    assert!(comparison.is_identical());
+    assert_eq!(comparison.max_difference, 0);
+    assert_eq!(comparison.changed_area, None);
+    assert!(comparison.psnr.is_infinite());
+    assert!((comparison.ssim - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn changed_pixels_are_located() {
+    let expected = checkerboard();
+    let mut actual = expected.clone();
+
+    actual.put_pixel(3, 20, Rgba([255, 0, 0, 255]));
+    actual.put_pixel(17, 9, Rgba([0, 0, 10, 255]));
+
+    let comparison = compare(&expected, &actual);
+
+    assert_eq!(comparison.changed_pixels, 2);
+    assert_eq!(comparison.max_difference, 255);
+    assert_eq!(
+        comparison.changed_area,
+        Some(ChangedArea {
+            min_x: 3,
+            min_y: 9,
+            max_x: 17,
+            max_y: 20,
+        })
+    );
+    assert!(comparison.psnr.is_finite());
+    assert!(comparison.ssim < 1.0);
+}
+
+#[test]
+fn small_differences_score_better_than_big_ones() {
+    let expected = checkerboard();
+    let slightly_off = RgbaImage::from_fn(32, 32, |x, y| {
+        let pixel = expected.get_pixel(x, y);
+        Rgba([pixel[0].saturating_sub(2), pixel[1], pixel[2], pixel[3]])
+    });
+    let inverted = RgbaImage::from_fn(32, 32, |x, y| {
+        let pixel = expected.get_pixel(x, y);
+        Rgba([255 - pixel[0], 255 - pixel[1], 255 - pixel[2], pixel[3]])
+    });
+
+    let small = compare(&expected, &slightly_off);
+    let big = compare(&expected, &inverted);
+
+    assert!(small.psnr > 40.0);
+    assert!(small.ssim > 0.99);
+    assert!(big.psnr < small.psnr);
+    assert!(big.ssim < 0.0);
+}
+
+#[test]
+fn diff_images_highlight_changes() {
+    let expected = checkerboard();
+    let mut actual = expected.clone();
+
+    actual.put_pixel(5, 6, Rgba([0, 0, 0, 0]));
+
+    let diff = diff_image(&expected, &actual);
+    let changed = diff.get_pixel(5, 6);
+    let unchanged = diff.get_pixel(6, 6);
+
+    assert!(changed[0] > 128 && changed[1] == 0 && changed[2] == 0);
+    assert_eq!(unchanged[0], unchanged[1]);
+
+    let artifact = side_by_side(&expected, &actual);
+
+    assert_eq!(artifact.dimensions(), (96, 32));
+    assert_eq!(artifact.get_pixel(5, 6), expected.get_pixel(5, 6));
+    assert_eq!(artifact.get_pixel(32 + 5, 6), actual.get_pixel(5, 6));
+    assert_eq!(artifact.get_pixel(64 + 5, 6), changed);
+}
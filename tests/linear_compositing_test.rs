@@ -0,0 +1,134 @@
+// This tests blending and antialiasing in linear light.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, Compositing, ViewBox},
+    color::{linear_to_srgb, Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    math::{translate, Angle},
+};
+
+mod common;
+
+const WIDTH: usize = 600;
+const HEIGHT: usize = 400;
+
+fn canvas_description(width: usize, height: usize, compositing: Compositing) -> CanvasDescription {
+    CanvasDescription {
+        width,
+        height,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: width as f64,
+            height: height as f64,
+        },
+        background_color: Color::black(),
+        compositing,
+        ..Default::default()
+    }
+}
+
+fn half_covered_pixel(compositing: Compositing) -> u8 {
+    let mut canvas = Canvas::new(canvas_description(2, 1, compositing));
+
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 0.5, 1.0),
+        FillStyle::Plain(Color::white()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    canvas.to_u8()[0]
+}
+
+#[test]
+fn antialiasing_keeps_brightness() {
+    // Half of a white pixel over black is 50% of the light, not 50% of the encoded value.
+    assert_eq!(half_covered_pixel(Compositing::Encoded), 127);
+    assert_eq!(half_covered_pixel(Compositing::LinearLight), 187);
+}
+
+#[test]
+fn to_u8_encodes_every_value() {
+    // https://stackoverflow.com/a/56842762/8622014
+    const FACTOR: f64 = (u8::MAX as f64) - f64::EPSILON * 128_f64;
+
+    let mut canvas = Canvas::new(canvas_description(1024, 1, Compositing::LinearLight));
+
+    for (i, pixel) in canvas.buffer.chunks_mut(4).enumerate() {
+        let value = i as f64 / 1023.0;
+        pixel.copy_from_slice(&[value, value, value, value]);
+    }
+
+    for (i, pixel) in canvas.to_u8().chunks(4).enumerate() {
+        let value = i as f64 / 1023.0;
+        let encoded = (linear_to_srgb(value) * FACTOR) as u8;
+
+        assert_eq!(pixel, [encoded, encoded, encoded, (value * FACTOR) as u8]);
+    }
+}
+
+#[test]
+fn background_round_trips() {
+    let canvas = Canvas::new(CanvasDescription {
+        background_color: Color::parse("#ff7f5080").unwrap(),
+        ..canvas_description(1, 1, Compositing::LinearLight)
+    });
+
+    // `to_u8` truncates, like with `Compositing::Encoded` a channel can end up one below.
+    for (actual, expected) in canvas.to_u8().iter().zip([0xff, 0x7f, 0x50, 0x80]) {
+        assert!(
+            (*actual as i32 - expected).abs() <= 1,
+            "{} should be {}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn linear_compositing() {
+    let mut canvas = Canvas::new(canvas_description(WIDTH, HEIGHT, Compositing::LinearLight));
+    let lime = Color::parse("lime").unwrap();
+    let stops = [(Color::red(), 0.0), (lime, 1.0)];
+
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 500.0, 100.0),
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(0.0),
+        },
+        FillRule::NonZero,
+        |p: &Point| translate(p, 50.0, 30.0),
+    );
+
+    let mut star = vec![PathOps::MoveTo { x: 100.0, y: 0.0 }];
+    for i in 1..5 {
+        let angle = (i * 144) as f64 * std::f64::consts::PI / 180.0;
+        star.push(PathOps::LineTo {
+            x: 100.0 + 100.0 * angle.sin(),
+            y: 100.0 - 100.0 * angle.cos(),
+        });
+    }
+    star.push(PathOps::Close);
+
+    canvas.draw_shape(
+        &star,
+        FillStyle::Plain(Color::white()),
+        FillRule::NonZero,
+        |p: &Point| translate(p, 50.0, 170.0),
+    );
+
+    // Overlapping translucent squares
+    let colors = [Color::red(), lime, Color::blue()];
+    for (i, color) in colors.iter().enumerate() {
+        canvas.draw_shape(
+            &common::rect(0.0, 0.0, 120.0, 120.0),
+            FillStyle::Plain(Color { a: 0.6, ..*color }),
+            FillRule::NonZero,
+            |p: &Point| translate(p, 300.0 + 60.0 * i as f64, 170.0 + 40.0 * i as f64),
+        );
+    }
+
+    common::check_against_reference("linear_compositing", &canvas);
+}
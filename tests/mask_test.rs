@@ -0,0 +1,184 @@
+// This tests coverage-only rendering into masks, which must match what a canvas draws.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, CurveRendering, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    mask::Mask,
+    math::{scale, translate},
+    text::Font,
+};
+
+mod common;
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 100;
+const FONT_SIZE: f64 = 64.0;
+
+// The outlines of `text` in font units and the pen position of each glyph, on a baseline at y = 75.
+// Font units are converted to pixels by the returned scale.
+fn glyphs(text: &str) -> (f64, Vec<(Vec<PathOps>, Point)>) {
+    let mut font = Font::from_file("media/Roboto-MediumItalic.ttf").unwrap();
+    let em = font.scale(FONT_SIZE);
+    let mut pen = Point { x: 10.0, y: 75.0 };
+    let mut result = vec![];
+
+    for c in text.chars() {
+        let glyph = font.glyph_index(c).unwrap();
+        let outline = font.outline(glyph);
+
+        if !outline.is_empty() {
+            result.push((outline.to_vec(), pen));
+        }
+
+        pen.x += font.glyph_metrics(glyph).advance * em;
+    }
+
+    (em, result)
+}
+
+fn description(curve_rendering: CurveRendering) -> CanvasDescription {
+    CanvasDescription {
+        width: WIDTH,
+        height: HEIGHT,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: WIDTH as f64,
+            height: HEIGHT as f64,
+        },
+        tolerance: 0.5,
+        background_color: Color {
+            a: 0.0,
+            ..Color::white()
+        },
+        curve_rendering,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn mask_matches_canvas_alpha() {
+    let (em, glyphs) = glyphs("Verg&@");
+    let circles = [
+        PathOps::MoveTo { x: 60.0, y: 50.0 },
+        PathOps::CubicTo {
+            x1: 60.0,
+            y1: 10.0,
+            x2: 140.0,
+            y2: 10.0,
+            x3: 140.0,
+            y3: 50.0,
+        },
+        PathOps::CubicTo {
+            x1: 140.0,
+            y1: 90.0,
+            x2: 60.0,
+            y2: 90.0,
+            x3: 60.0,
+            y3: 50.0,
+        },
+        PathOps::MoveTo { x: 80.0, y: 50.0 },
+        PathOps::QuadTo {
+            x1: 100.0,
+            y1: -20.0,
+            x2: 120.0,
+            y2: 50.0,
+        },
+        PathOps::QuadTo {
+            x1: 100.0,
+            y1: 120.0,
+            x2: 80.0,
+            y2: 50.0,
+        },
+    ];
+
+    for curve_rendering in [CurveRendering::Flattened, CurveRendering::Analytic] {
+        for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            let desc = description(curve_rendering);
+            let mut canvas = Canvas::new(desc);
+            let mut mask = Mask::new(desc);
+
+            for (path, pen) in &glyphs {
+                let transform = |p: &Point| translate(&scale(p, em, em), pen.x, pen.y);
+
+                canvas.draw_shape(path, FillStyle::Plain(Color::white()), fill_rule, transform);
+                mask.draw_shape(path, fill_rule, transform);
+            }
+            // Overlaps the glyphs, the coverage combines like alpha.
+            canvas.draw_shape(&circles, FillStyle::Plain(Color::white()), fill_rule, |p| {
+                *p
+            });
+            mask.draw_shape(&circles, fill_rule, |p| *p);
+
+            for (i, coverage) in mask.coverage.iter().enumerate() {
+                let alpha = canvas.buffer[i * 4 + 3];
+                assert!(
+                    (*coverage as f64 - alpha).abs() < 1e-6,
+                    "{:?} {:?} at {}: {} != {}",
+                    curve_rendering,
+                    fill_rule,
+                    i,
+                    coverage,
+                    alpha
+                );
+            }
+            assert!(mask.accumulation_buffer.iter().all(|cell| cell.area == 0.0));
+        }
+    }
+}
+
+#[test]
+fn a8_output() {
+    let mut mask = Mask::new(CanvasDescription {
+        width: 4,
+        height: 1,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: 4.0,
+            height: 1.0,
+        },
+        ..Default::default()
+    });
+    let square = [
+        PathOps::MoveTo { x: 0.0, y: 0.0 },
+        PathOps::LineTo { x: 2.5, y: 0.0 },
+        PathOps::LineTo { x: 2.5, y: 1.0 },
+        PathOps::LineTo { x: 0.0, y: 1.0 },
+        PathOps::Close,
+    ];
+
+    mask.draw_shape(&square, FillRule::NonZero, |p| *p);
+    assert_eq!(mask.to_u8(), [255, 255, 128, 0]);
+
+    mask.clear();
+    assert_eq!(mask.to_u8(), [0; 4]);
+}
+
+#[test]
+fn mask_glyphs() {
+    let mut mask = Mask::new(description(CurveRendering::Analytic));
+
+    let (em, glyphs) = glyphs("Masks!");
+
+    for (path, pen) in glyphs {
+        mask.draw_shape(&path, FillRule::NonZero, |p| {
+            translate(&scale(p, em, em), pen.x, pen.y)
+        });
+    }
+
+    let mut canvas = Canvas::new(description(CurveRendering::Analytic));
+    // The A8 values as opaque gray, the middle of each 8-bit level is truncated to that level by
+    // `to_u8`.
+    canvas.buffer = mask
+        .to_u8()
+        .iter()
+        .flat_map(|v| {
+            let gray = (*v as f64 + 0.5) / 255.0;
+            [gray, gray, gray, 1.0]
+        })
+        .collect();
+
+    common::check_against_reference("mask_glyphs", &canvas);
+}
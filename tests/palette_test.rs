@@ -0,0 +1,282 @@
+// This tests the reduction of a canvas to a palette and the export of indexed PNGs.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription},
+    color::{Color, FillRule, FillStyle, Oklab, RgbSpace},
+    dither::Dithering,
+    geometry::Point,
+    math::Angle,
+    palette::{IndexedImage, QuantizeOptions, Quantizer},
+};
+
+mod common;
+
+const WIDTH: usize = 300;
+const HEIGHT: usize = 200;
+
+fn gradient_canvas() -> Canvas {
+    let mut canvas = common::new_canvas(WIDTH, HEIGHT);
+    let stops = [
+        (Color::crimson(), 0.0),
+        (Color::yellow(), 0.5),
+        (Color::dark_slate_blue(), 1.0),
+    ];
+
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, WIDTH as f64, HEIGHT as f64 / 2.0),
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(0.0),
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.draw_shape(
+        &common::rect(40.0, 120.0, 220.0, 60.0),
+        FillStyle::RadialGradient {
+            stops: &[
+                (Color::cyan(), 0.0),
+                (
+                    Color {
+                        a: 0.3,
+                        ..Color::forest_green()
+                    },
+                    1.0,
+                ),
+            ],
+            translation: Point { x: 0.0, y: 0.0 },
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    canvas
+}
+
+// Average OKLab distance between the original pixels and the quantized ones.
+fn mean_error(canvas: &Canvas, image: &IndexedImage) -> f64 {
+    let to_lab = |pixel: &[u8]| {
+        let lab = Oklab::from(Color {
+            r: pixel[0] as f64 / 255.0,
+            g: pixel[1] as f64 / 255.0,
+            b: pixel[2] as f64 / 255.0,
+            a: pixel[3] as f64 / 255.0,
+        });
+        [lab.l, lab.a, lab.b, lab.alpha]
+    };
+    let original = canvas.to_u8();
+    let quantized = image.to_rgba();
+
+    original
+        .chunks(4)
+        .zip(quantized.chunks(4))
+        .map(|(a, b)| {
+            let (a, b) = (to_lab(a), to_lab(b));
+            (0..4).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt()
+        })
+        .sum::<f64>()
+        / (canvas.desc.width * canvas.desc.height) as f64
+}
+
+#[test]
+fn few_colors_are_kept() {
+    let mut canvas = common::new_canvas(40, 20);
+    let colors = [Color::red(), Color::blue(), Color::coral()];
+
+    for (i, color) in colors.iter().enumerate() {
+        canvas.draw_shape(
+            &common::rect(10.0 * i as f64, 0.0, 10.0, 20.0),
+            FillStyle::Plain(*color),
+            FillRule::NonZero,
+            |p| *p,
+        );
+    }
+
+    let image = canvas.quantize(&QuantizeOptions {
+        num_colors: 4,
+        ..Default::default()
+    });
+
+    assert_eq!(image.palette.len(), 4);
+    assert_eq!(image.to_rgba(), canvas.to_u8());
+    assert_eq!((image.width, image.height), (40, 20));
+    assert_eq!(image.color_space, RgbSpace::Srgb);
+}
+
+#[test]
+fn gradients_are_reduced() {
+    let canvas = gradient_canvas();
+
+    for num_colors in [1, 2, 16, 256] {
+        let image = canvas.quantize(&QuantizeOptions {
+            num_colors,
+            ..Default::default()
+        });
+
+        assert!(image.palette.len() <= num_colors);
+        assert!(image
+            .indices
+            .iter()
+            .all(|index| (*index as usize) < image.palette.len()));
+    }
+
+    let median_cut = canvas.quantize(&QuantizeOptions {
+        num_colors: 16,
+        ..Default::default()
+    });
+    let k_means = canvas.quantize(&QuantizeOptions {
+        num_colors: 16,
+        quantizer: Quantizer::KMeans { iterations: 10 },
+        ..Default::default()
+    });
+    let (median_cut_error, k_means_error) = (
+        mean_error(&canvas, &median_cut),
+        mean_error(&canvas, &k_means),
+    );
+
+    assert!(median_cut_error < 0.05, "{}", median_cut_error);
+    assert!(
+        k_means_error <= median_cut_error,
+        "{} > {}",
+        k_means_error,
+        median_cut_error
+    );
+
+    // The palette doesn't depend on the order colors are found in.
+    assert_eq!(
+        k_means,
+        canvas.quantize(&QuantizeOptions {
+            num_colors: 16,
+            quantizer: Quantizer::KMeans { iterations: 10 },
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn dithering_mixes_palette_colors() {
+    let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+    let mut canvas = common::new_canvas(64, 64);
+    canvas.draw_shape(
+        &common::rect(0.0, 32.0, 64.0, 32.0),
+        FillStyle::Plain(Color {
+            r: 0.5,
+            g: 0.5,
+            b: 0.5,
+            a: 1.0,
+        }),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    let white_ratio = |image: &IndexedImage| {
+        let gray_area = &image.indices[32 * 64..];
+        gray_area.iter().filter(|i| **i == 1).count() as f64 / gray_area.len() as f64
+    };
+
+    // Without dithering the gray is closest to white, which is perceptually closer.
+    let undithered = canvas.quantize_to(&[black, white], Dithering::None);
+    assert_eq!(white_ratio(&undithered), 1.0);
+
+    // Error diffusion keeps the average value.
+    let diffused = canvas.quantize_to(&[black, white], Dithering::FloydSteinberg);
+    assert!((white_ratio(&diffused) - 0.5).abs() < 0.02);
+
+    for dithering in [Dithering::Bayer, Dithering::BlueNoise { seed: 5 }] {
+        let image = canvas.quantize_to(&[black, white], dithering);
+        let ratio = white_ratio(&image);
+
+        assert!(ratio > 0.25 && ratio < 0.75, "{:?}: {}", dithering, ratio);
+        // Colors of the palette aren't dithered.
+        assert!(image.indices[..32 * 64].iter().all(|i| *i == 1));
+    }
+}
+
+#[test]
+fn dithering_of_the_canvas_is_ignored() {
+    let mut dithered = gradient_canvas();
+    dithered.desc.dithering = Dithering::Bayer;
+    let options = QuantizeOptions {
+        num_colors: 16,
+        ..Default::default()
+    };
+
+    // The pixels are quantized once, with the dithering of the options.
+    assert_eq!(
+        dithered.quantize(&options),
+        gradient_canvas().quantize(&options)
+    );
+}
+
+#[test]
+fn colors_are_compared_in_the_space_of_the_canvas() {
+    let (gray, green) = ([128, 128, 128, 255], [0, 255, 0, 255]);
+    let space = RgbSpace::Rec2020;
+    let background_color = space.to_srgb(&Color {
+        r: 0.0,
+        g: 0.6,
+        b: 0.0,
+        a: 1.0,
+    });
+    let canvas = Canvas::new(CanvasDescription {
+        color_space: space,
+        ..common::new_canvas_with_background(4, 4, background_color).desc
+    });
+
+    // Read as sRGB the pixels would be a dark green, closer to the gray.
+    let image = canvas.quantize_to(&[gray, green], Dithering::None);
+    assert!(image.indices.iter().all(|index| *index == 1));
+}
+
+#[test]
+#[should_panic(expected = "A palette has between 1 and 256 colors")]
+fn empty_palette() {
+    gradient_canvas().quantize(&QuantizeOptions {
+        num_colors: 0,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn indexed_png() {
+    let canvas = gradient_canvas();
+
+    for (num_colors, bit_depth) in [(2, 1), (4, 2), (16, 4), (100, 8)] {
+        let image = canvas.quantize(&QuantizeOptions {
+            num_colors,
+            dithering: Dithering::FloydSteinberg,
+            ..Default::default()
+        });
+        let png = image.to_png();
+
+        // The bit depth and color type in IHDR.
+        assert_eq!(&png[24..26], &[bit_depth, 3]);
+        assert!(png.windows(4).any(|kind| kind == b"PLTE"));
+        assert!(png.windows(4).any(|kind| kind == b"tRNS"));
+
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded.into_raw(), image.to_rgba(), "{} colors", num_colors);
+    }
+
+    let opaque = canvas.quantize_to(&[[0, 0, 0, 255], [255, 127, 80, 255]], Dithering::None);
+    assert!(!opaque.to_png().windows(4).any(|kind| kind == b"tRNS"));
+}
+
+#[test]
+fn palette_quantization() {
+    let source = gradient_canvas();
+    let image = source.quantize(&QuantizeOptions {
+        num_colors: 16,
+        quantizer: Quantizer::KMeans { iterations: 10 },
+        dithering: Dithering::FloydSteinberg,
+    });
+
+    let mut canvas = common::new_canvas(WIDTH, HEIGHT);
+    // The middle of each 8-bit level is truncated to that level by `to_u8`.
+    canvas.buffer = image
+        .to_rgba()
+        .iter()
+        .map(|v| (*v as f64 + 0.5) / 255.0)
+        .collect();
+
+    common::check_against_reference("palette_quantization", &canvas);
+}
@@ -0,0 +1,251 @@
+// This tests exporting recorded shapes as PDF documents: the structure of the file, path
+// operators and fill rules, shadings for gradients, graphics states for opacity and soft masks
+// for blending functions erasing what's under the shapes.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    math::Angle,
+    recording::BlendMode,
+};
+
+mod common;
+
+fn new_canvas(width: usize, height: usize, viewbox_size: f64) -> Canvas {
+    let mut canvas = Canvas::new(CanvasDescription {
+        width,
+        height,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: viewbox_size,
+            height: viewbox_size,
+        },
+        background_color: Color::white(),
+        ..Default::default()
+    });
+    canvas.start_recording();
+    canvas
+}
+
+fn draw_square(canvas: &mut Canvas, fill_style: FillStyle) {
+    canvas.draw_shape(
+        &common::rect(5.0, 5.0, 10.0, 10.0),
+        fill_style,
+        FillRule::NonZero,
+        |p| *p,
+    );
+}
+
+fn find(pdf: &[u8], text: &str, from: usize) -> Option<usize> {
+    pdf[from..]
+        .windows(text.len())
+        .position(|window| window == text.as_bytes())
+        .map(|i| i + from)
+}
+
+// Checks the cross-reference table and the length of the streams, returns the text of the
+// document.
+fn check_structure(pdf: &[u8]) -> String {
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+
+    // One character per byte so offsets in the text are offsets in the file, the only bytes
+    // that aren't ASCII are in the comment after the header.
+    let text = pdf
+        .iter()
+        .map(|&byte| if byte.is_ascii() { byte as char } else { '?' })
+        .collect::<String>();
+    let start = find(pdf, "startxref\n", 0).unwrap() + "startxref\n".len();
+    let xref = text[start..]
+        .lines()
+        .next()
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+    let mut lines = text[xref..].lines();
+    assert_eq!(lines.next(), Some("xref"));
+    let count = lines.next().unwrap()[2..].parse::<usize>().unwrap();
+    assert_eq!(lines.next(), Some("0000000000 65535 f "));
+
+    for object in 1..count {
+        let offset = lines.next().unwrap()[..10].parse::<usize>().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj\n", object).as_bytes()));
+    }
+    assert!(text.contains(&format!("trailer\n<< /Size {} /Root 1 0 R >>", count)));
+
+    let mut from = 0;
+    while let Some(stream) = find(pdf, ">>\nstream\n", from) {
+        let length_start = text[..stream].rfind("/Length ").unwrap() + "/Length ".len();
+        let length = text[length_start..stream].trim().parse::<usize>().unwrap();
+        let data = stream + ">>\nstream\n".len();
+        assert!(pdf[data + length..].starts_with(b"\nendstream\n"));
+        from = data + length;
+    }
+
+    text
+}
+
+// The content stream of the page.
+fn contents(text: &str) -> &str {
+    let start = text.find("5 0 obj\n").unwrap();
+    let data = text[start..].find("stream\n").unwrap() + start + "stream\n".len();
+    let end = text[data..].find("\nendstream").unwrap() + data;
+    &text[data..end]
+}
+
+#[test]
+fn paths_and_fill_rules() {
+    let mut canvas = new_canvas(40, 40, 20.0);
+    draw_square(&mut canvas, FillStyle::Plain(Color::blue()));
+    canvas.draw_shape(
+        &[
+            PathOps::MoveTo { x: 0.0, y: 0.0 },
+            PathOps::QuadToRel {
+                x1: 3.0,
+                y1: 6.0,
+                x2: 6.0,
+                y2: 0.0,
+            },
+            PathOps::CubicTo {
+                x1: 7.0,
+                y1: 1.0,
+                x2: 8.0,
+                y2: 2.0,
+                x3: 9.0,
+                y3: 3.0,
+            },
+        ],
+        FillStyle::Plain(Color::red()),
+        FillRule::EvenOdd,
+        |p| *p,
+    );
+
+    let pdf = canvas.take_recording().unwrap().to_pdf();
+    let text = check_structure(&pdf);
+    assert!(text.contains("/MediaBox [0 0 40 40]"));
+
+    // The page is flipped, the paths are in pixels.
+    let expected = "1 0 0 -1 0 40 cm
+q 1 1 1 rg 0 0 40 40 re f Q
+q 0 0 1 rg
+10 10 m
+30 10 l
+30 30 l
+10 30 l
+h
+f
+Q
+q 1 0 0 rg
+0 0 m
+4 8 8 8 12 0 c
+14 2 16 4 18 6 c
+f*
+Q";
+    // Quadratic curves are raised to cubic ones.
+    assert_eq!(contents(&text), expected);
+}
+
+#[test]
+fn gradients_are_shadings() {
+    let mut canvas = new_canvas(20, 20, 20.0);
+    let stops = [
+        (Color::red(), 0.0),
+        (Color::yellow(), 0.6),
+        (Color::blue(), 1.0),
+    ];
+    draw_square(
+        &mut canvas,
+        FillStyle::LinearGradient {
+            stops: &stops[1..],
+            angle: Angle::from_degrees(0.0),
+        },
+    );
+    draw_square(
+        &mut canvas,
+        FillStyle::RadialGradient {
+            stops: &stops,
+            translation: Point { x: 0.0, y: 0.0 },
+        },
+    );
+    let angles = [(Color::red(), Angle::from_degrees(0.0))];
+    draw_square(
+        &mut canvas,
+        FillStyle::ConicGradient {
+            stops: &angles,
+            translation: Point { x: 0.0, y: 0.0 },
+        },
+    );
+
+    let text = check_structure(&canvas.take_recording().unwrap().to_pdf());
+
+    // The stops are padded to the ends of the shading.
+    assert!(text.contains("/Sh0 << /ShadingType 2 /ColorSpace /DeviceRGB /Coords [15.5 10.5 5.5 10.5] /Function << /FunctionType 3 /Domain [0 1] /Functions [<< /FunctionType 2 /Domain [0 1] /C0 [1 1 0] /C1 [1 1 0] /N 1 >> << /FunctionType 2 /Domain [0 1] /C0 [1 1 0] /C1 [0 0 1] /N 1 >>] /Bounds [0.6] /Encode [0 1 0 1] >> /Extend [true true] >>"));
+    assert!(text.contains(
+        "/Sh1 << /ShadingType 3 /ColorSpace /DeviceRGB /Coords [10.5 10.5 0 10.5 10.5 5]"
+    ));
+    assert!(text.contains("/Bounds [0.6] /Encode [0 1 0 1]"));
+
+    // Shadings are clipped to their shape, conic gradients take their first color.
+    let contents = contents(&text);
+    assert!(contents.contains("h\nW n\n/Sh0 sh\nQ"));
+    assert!(contents.contains("h\nW n\n/Sh1 sh\nQ"));
+    assert!(contents.contains("q 1 0 0 rg\n5 5 m"));
+}
+
+#[test]
+fn opacity_and_soft_masks() {
+    let mut canvas = new_canvas(20, 20, 20.0);
+    let translucent = Color {
+        a: 0.5,
+        ..Color::red()
+    };
+    draw_square(&mut canvas, FillStyle::Plain(translucent));
+    draw_square(&mut canvas, FillStyle::Plain(translucent));
+    canvas.set_blend_mode(BlendMode::DestinationOut);
+    draw_square(&mut canvas, FillStyle::Plain(Color::blue()));
+    canvas.set_blend_mode(BlendMode::Destination);
+    canvas.draw_shape(
+        &common::rect(1.0, 1.0, 2.0, 2.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blend_mode(BlendMode::DestinationOver);
+    draw_square(&mut canvas, FillStyle::Plain(Color::blue()));
+
+    // The modes are kept as they were set, in every build profile.
+    let recording = canvas.take_recording().unwrap();
+    assert_eq!(
+        recording
+            .commands
+            .iter()
+            .map(|command| command.blend)
+            .collect::<Vec<Option<BlendMode>>>(),
+        [
+            Some(BlendMode::SourceOver),
+            Some(BlendMode::SourceOver),
+            Some(BlendMode::DestinationOut),
+            Some(BlendMode::Destination),
+            Some(BlendMode::DestinationOver),
+        ]
+    );
+
+    let text = check_structure(&recording.to_pdf());
+
+    // Both shapes share the graphics state of their opacity.
+    assert!(text.contains("/ExtGState << /GS0 << /ca 0.5 >> /GS1 << /SMask << /Type /Mask /S /Luminosity /G 6 0 R >> >> >>"));
+    assert!(text.contains("/XObject << /Fm0 6 0 R /Fm1 7 0 R >>"));
+
+    // The mask draws the shape in black over white, the shapes drawn before are in a group.
+    assert!(text.contains("6 0 obj\n<< /Type /XObject /Subtype /Form /BBox [0 0 20 20] /Group << /S /Transparency /CS /DeviceRGB >> /Resources 4 0 R /Length"));
+    assert!(text.contains("stream\n1 g 0 0 20 20 re f\nq 0 0 0 rg\n5 5 m"));
+    assert_eq!(text.matches("q /GS0 gs 1 0 0 rg").count(), 2);
+
+    // `destination_over` goes behind the masked group, `destination` draws nothing.
+    let contents = contents(&text);
+    assert!(contents.starts_with("1 0 0 -1 0 20 cm\nq 0 0 1 rg\n5 5 m"));
+    assert!(contents.ends_with("Q\nq /GS1 gs /Fm1 Do Q"));
+    assert!(!text.contains("1 1 m"));
+}
@@ -0,0 +1,190 @@
+// This tests the PNG export and the chunks describing the color space of the canvas.
+
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle, RgbSpace},
+    geometry::PathOps,
+    math::Angle,
+    palette::QuantizeOptions,
+};
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+
+fn draw(color_space: RgbSpace) -> Canvas {
+    let mut canvas = Canvas::new(CanvasDescription {
+        width: WIDTH,
+        height: HEIGHT,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: WIDTH as f64,
+            height: HEIGHT as f64,
+        },
+        background_color: Color {
+            a: 0.5,
+            ..Color::coral()
+        },
+        color_space,
+        ..Default::default()
+    });
+    let stops = [(Color::red(), 0.0), (Color::blue(), 1.0)];
+
+    canvas.draw_shape(
+        &[
+            PathOps::MoveTo { x: 8.0, y: 4.0 },
+            PathOps::LineTo { x: 60.0, y: 20.0 },
+            PathOps::LineTo { x: 20.0, y: 44.0 },
+            PathOps::Close,
+        ],
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(30.0),
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    canvas
+}
+
+// The type and data of every chunk, after checking their CRC.
+fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+    assert_eq!(&png[..8], &[137, 80, 78, 71, 13, 10, 26, 10]);
+
+    let mut chunks = vec![];
+    let mut offset = 8;
+
+    while offset < png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let kind = String::from_utf8(png[offset + 4..offset + 8].to_vec()).unwrap();
+        let data = png[offset + 8..offset + 8 + length].to_vec();
+        let crc = u32::from_be_bytes(
+            png[offset + 8 + length..offset + 12 + length]
+                .try_into()
+                .unwrap(),
+        );
+
+        assert_eq!(
+            crc,
+            crc32(&png[offset + 4..offset + 8 + length]),
+            "{}",
+            kind
+        );
+        chunks.push((kind, data));
+        offset += 12 + length;
+    }
+
+    chunks
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+#[test]
+fn pixels_round_trip() {
+    for space in [RgbSpace::Srgb, RgbSpace::DisplayP3, RgbSpace::LinearRec2020] {
+        let canvas = draw(space);
+        let decoded = image::load_from_memory(&canvas.to_png())
+            .unwrap()
+            .to_rgba8();
+
+        assert_eq!(decoded.dimensions(), (WIDTH as u32, HEIGHT as u32));
+        assert_eq!(decoded.into_raw(), canvas.to_u8(), "{:?}", space);
+    }
+}
+
+#[test]
+fn srgb_chunks() {
+    let chunks = chunks(&draw(RgbSpace::Srgb).to_png());
+    let kinds = chunks
+        .iter()
+        .map(|(kind, _)| kind.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(kinds, ["IHDR", "cICP", "sRGB", "IDAT", "IEND"]);
+    assert_eq!(chunks[1].1, [1, 13, 0, 1]);
+    assert_eq!(chunks[2].1, [0]);
+}
+
+#[test]
+fn wide_gamut_chunks() {
+    let expected_cicp = [
+        (RgbSpace::LinearSrgb, [1, 8, 0, 1]),
+        (RgbSpace::DisplayP3, [12, 13, 0, 1]),
+        (RgbSpace::LinearDisplayP3, [12, 8, 0, 1]),
+        (RgbSpace::Rec2020, [9, 1, 0, 1]),
+        (RgbSpace::LinearRec2020, [9, 8, 0, 1]),
+    ];
+
+    for (space, cicp) in expected_cicp {
+        let chunks = chunks(&draw(space).to_png());
+        let kinds = chunks
+            .iter()
+            .map(|(kind, _)| kind.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, ["IHDR", "cICP", "iCCP", "IDAT", "IEND"]);
+        assert_eq!(chunks[1].1, cicp);
+
+        let iccp = &chunks[2].1;
+        let name_end = iccp.iter().position(|byte| *byte == 0).unwrap();
+
+        assert_eq!(&iccp[..name_end], space.name().as_bytes());
+        assert_eq!(iccp[name_end + 1], 0);
+        assert_eq!(
+            decompress_to_vec_zlib(&iccp[name_end + 2..]).unwrap(),
+            space.icc_profile()
+        );
+    }
+}
+
+#[test]
+fn empty_canvases() {
+    for (width, height) in [(0, 4), (4, 0)] {
+        let canvas = Canvas::new(CanvasDescription {
+            width,
+            height,
+            ..Default::default()
+        });
+
+        for png in [
+            canvas.to_png(),
+            canvas.quantize(&QuantizeOptions::default()).to_png(),
+        ] {
+            let chunks = chunks(&png);
+
+            assert_eq!(chunks[0].0, "IHDR");
+            assert_eq!(
+                chunks[0].1[..8],
+                [0, 0, 0, width as u8, 0, 0, 0, height as u8]
+            );
+            let idat = chunks.iter().find(|(kind, _)| kind == "IDAT").unwrap();
+            assert!(decompress_to_vec_zlib(&idat.1).unwrap().is_empty());
+        }
+    }
+}
+
+#[test]
+fn save_png() {
+    let canvas = draw(RgbSpace::DisplayP3);
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("save_png.png");
+
+    canvas.save_png(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), canvas.to_png());
+}
@@ -3,7 +3,7 @@
 use verg::{
     canvas::{Canvas, CanvasDescription, ViewBox},
     color::{Color, FillRule, FillStyle},
-    geometry::{PathOps, Point},
+    geometry::{PathOps, Point, QuadraticBezier},
 };
 
 mod common;
@@ -23,6 +23,7 @@ fn canvas_description() -> CanvasDescription {
         },
         background_color: Color::white(),
         tolerance: 0.5,
+        ..Default::default()
     }
 }
 
@@ -47,3 +48,17 @@ fn callback(canvas: &mut Canvas) {
 implement_test! {
     quadbezier_test, canvas_description, callback |
 }
+
+#[test]
+fn sharp_curves_are_flattened() {
+    // The turn is sharp enough for the parabola approximation to go past its usual range.
+    let curve = QuadraticBezier::new(
+        Point { x: 80.0, y: 50.0 },
+        Point { x: 100.0, y: -20.0 },
+        Point { x: 120.0, y: 50.0 },
+    );
+    let splits = curve.subdivide(0.5);
+
+    assert!(splits.len() > 2);
+    assert!(splits.windows(2).all(|t| t[0] < t[1]), "{:?}", splits);
+}
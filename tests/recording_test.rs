@@ -0,0 +1,448 @@
+// This tests recording the shapes drawn on a canvas and exporting them as an SVG document: the
+// recorded paths, paints and blending functions, text, and the document rendered back against the
+// canvas it was recorded from.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Antialiasing, Color, FillMode, FillRule, FillStyle},
+    geometry::{BoundingBox, PathOps, Point},
+    math::{translate, Angle},
+    recording::{BlendMode, Paint},
+    reference::ImageDiff,
+    svg::SvgDocument,
+    text::{Font, GlyphAtlas},
+};
+
+mod common;
+
+fn new_canvas(width: usize, height: usize, viewbox: ViewBox) -> Canvas {
+    Canvas::new(CanvasDescription {
+        width,
+        height,
+        viewbox,
+        background_color: Color::white(),
+        tolerance: 0.25,
+        ..Default::default()
+    })
+}
+
+fn assert_point(p: Point, x: f64, y: f64) {
+    assert!(
+        (p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9,
+        "{:?} ({}, {})",
+        p,
+        x,
+        y
+    );
+}
+
+#[test]
+fn draw_calls_are_recorded() {
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+    let mut canvas = new_canvas(100, 100, viewbox);
+
+    // Nothing is recorded until asked.
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 10.0, 10.0),
+        FillStyle::Plain(Color::red()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    assert!(canvas.recording.is_none());
+
+    canvas.start_recording();
+    let path = [
+        PathOps::MoveTo { x: 10.0, y: 10.0 },
+        PathOps::LineToRel { x: 20.0, y: 0.0 },
+        PathOps::QuadToRel {
+            x1: 0.0,
+            y1: 10.0,
+            x2: -10.0,
+            y2: 20.0,
+        },
+        PathOps::Close,
+    ];
+    canvas.draw_shape(
+        &path,
+        FillStyle::Plain(Color::blue()),
+        FillMode {
+            fill_rule: FillRule::EvenOdd,
+            antialiasing: Antialiasing::None,
+        },
+        |p| translate(p, 5.0, 2.5),
+    );
+    canvas.set_blend_mode(BlendMode::DestinationOut);
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 10.0, 10.0),
+        FillStyle::Plain(Color::red()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blending_function(|src, _| *src);
+    assert_eq!(canvas.blend_mode(), None);
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 10.0, 10.0),
+        FillStyle::Plain(Color::red()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    let recording = canvas.take_recording().unwrap();
+    assert!(canvas.recording.is_none());
+    assert_eq!(recording.commands.len(), 3);
+
+    // Relative commands are resolved and the transform is applied.
+    let command = &recording.commands[0];
+    match command.path[..] {
+        [PathOps::MoveTo { x: x0, y: y0 }, PathOps::LineTo { x: x1, y: y1 }, PathOps::QuadTo {
+            x1: cx,
+            y1: cy,
+            x2,
+            y2,
+        }, PathOps::Close] => {
+            assert_point(Point { x: x0, y: y0 }, 15.0, 12.5);
+            assert_point(Point { x: x1, y: y1 }, 35.0, 12.5);
+            assert_point(Point { x: cx, y: cy }, 35.0, 22.5);
+            assert_point(Point { x: x2, y: y2 }, 25.0, 32.5);
+        }
+        _ => panic!("unexpected commands"),
+    }
+    assert!(matches!(command.paint, Paint::Color(c) if c == Color::blue()));
+    assert!(matches!(command.fill_mode.fill_rule, FillRule::EvenOdd));
+    assert_eq!(command.fill_mode.antialiasing, Antialiasing::None);
+    assert_eq!(
+        command.bounds,
+        BoundingBox {
+            min_x: 15,
+            min_y: 12,
+            max_x: 35,
+            max_y: 32
+        }
+    );
+
+    assert_eq!(recording.commands[0].blend, Some(BlendMode::SourceOver));
+    assert_eq!(recording.commands[1].blend, Some(BlendMode::DestinationOut));
+    assert_eq!(recording.commands[2].blend, None);
+}
+
+#[test]
+fn gradients_are_laid_out_like_the_renderer() {
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+    let mut canvas = new_canvas(100, 100, viewbox);
+    let stops = [(Color::red(), 0.0), (Color::blue(), 1.0)];
+    canvas.start_recording();
+
+    canvas.draw_shape(
+        &common::rect(10.0, 20.0, 40.0, 40.0),
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(90.0),
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.draw_shape(
+        &common::rect(10.0, 20.0, 40.0, 40.0),
+        FillStyle::RadialGradient {
+            stops: &stops,
+            translation: Point { x: 4.0, y: -2.0 },
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    let recording = canvas.take_recording().unwrap();
+    match &recording.commands[0].paint {
+        Paint::LinearGradient { stops, start, end } => {
+            assert_eq!(stops.len(), 2);
+            assert_point(*start, 30.5, 60.5);
+            assert_point(*end, 30.5, 20.5);
+        }
+        _ => panic!("expected a linear gradient"),
+    }
+    match &recording.commands[1].paint {
+        Paint::RadialGradient { center, radius, .. } => {
+            assert_point(*center, 26.5, 42.5);
+            assert_eq!(*radius, 20.0);
+        }
+        _ => panic!("expected a radial gradient"),
+    }
+}
+
+#[test]
+fn svg_documents() {
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 50.0,
+        height: 50.0,
+    };
+    let mut canvas = new_canvas(100, 100, viewbox);
+    let stops = [(Color::red(), 0.0), (Color::blue(), 1.0)];
+    let angles = [
+        (Color::yellow(), Angle::from_degrees(0.0)),
+        (Color::crimson(), Angle::from_degrees(180.0)),
+    ];
+    canvas.start_recording();
+
+    canvas.draw_shape(
+        &common::rect(5.0, 5.0, 10.0, 10.0),
+        FillStyle::Plain(Color {
+            a: 0.5,
+            ..Color::red()
+        }),
+        FillMode {
+            fill_rule: FillRule::EvenOdd,
+            antialiasing: Antialiasing::None,
+        },
+        |p| *p,
+    );
+    canvas.draw_shape(
+        &common::rect(5.0, 5.0, 10.0, 10.0),
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(0.0),
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.draw_shape(
+        &common::rect(5.0, 5.0, 10.0, 10.0),
+        FillStyle::ConicGradient {
+            stops: &angles,
+            translation: Point { x: 0.0, y: 0.0 },
+        },
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blend_mode(BlendMode::DestinationOver);
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 5.0, 5.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blend_mode(BlendMode::Additive);
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 5.0, 5.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blend_mode(BlendMode::DestinationOut);
+    canvas.draw_shape(
+        &common::rect(0.0, 0.0, 5.0, 5.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.set_blend_mode(BlendMode::Destination);
+    canvas.draw_shape(
+        &common::rect(30.0, 30.0, 5.0, 5.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    let svg = canvas.take_recording().unwrap().to_svg();
+    let lines = svg.lines().collect::<Vec<&str>>();
+
+    assert_eq!(
+        lines[0],
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100">"#
+    );
+    // The paths keep viewbox units, gradients are in pixels.
+    assert!(svg.contains(r##"<linearGradient id="gradient0" gradientUnits="userSpaceOnUse" x1="30.5" y1="20.5" x2="10.5" y2="20.5" gradientTransform="matrix(0.5 0 0 0.5 0 0)"><stop offset="0" stop-color="#ff0000"/><stop offset="1" stop-color="#0000ff"/></linearGradient>"##));
+    assert!(svg.contains(r##"<path transform="matrix(2 0 0 2 0 0)" d="M5 5 L15 5 L15 15 L5 15 Z" fill="#ff0000" fill-opacity="0.5" fill-rule="evenodd" shape-rendering="crispEdges"/>"##));
+    assert!(svg.contains(r#"fill="url(#gradient0)""#));
+    // Conic gradients are drawn with their first color.
+    assert!(svg.contains(r##"d="M5 5 L15 5 L15 15 L5 15 Z" fill="#ffff00"/>"##));
+    assert!(svg.contains(r#"style="mix-blend-mode:plus-lighter""#));
+    // `destination_out` masks what's drawn before, `destination` draws nothing.
+    assert!(svg.contains(r#"<mask id="mask1" maskUnits="userSpaceOnUse" x="0" y="0" width="100" height="100"><rect width="100" height="100" fill="white"/><path transform="matrix(2 0 0 2 0 0)" d="M0 0 L5 0 L5 5 L0 5 Z" fill="black" fill-opacity="1"/></mask>"#));
+    assert_eq!(
+        lines.iter().filter(|line| line.contains("M30 30")).count(),
+        0
+    );
+    // `destination_over` draws behind everything, the background included.
+    let masked = lines
+        .iter()
+        .position(|line| line.starts_with(r#"<g mask="url(#mask1)">"#))
+        .unwrap();
+    assert!(lines[masked + 1].contains(r##"fill="#0000ff""##));
+    assert!(lines[masked + 2].starts_with(r##"<rect width="100" height="100" fill="#ffffff"/>"##));
+    assert_eq!(lines[lines.len() - 1], "</svg>");
+
+    // Without a viewbox to map, the paths have no transform.
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 20.0,
+        height: 20.0,
+    };
+    let mut canvas = new_canvas(20, 20, viewbox);
+    canvas.start_recording();
+    canvas.draw_shape(
+        &common::rect(1.0, 1.0, 5.0, 5.0),
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    assert!(canvas
+        .take_recording()
+        .unwrap()
+        .to_svg()
+        .contains(r##"<path d="M1 1 L6 1 L6 6 L1 6 Z" fill="#0000ff"/>"##));
+}
+
+#[test]
+fn text_is_recorded() {
+    // Two pixels per unit, the recorded paths are in viewbox units.
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 40.0,
+    };
+    let mut font = Font::from_file("media/Roboto-MediumItalic.ttf").unwrap();
+    let mut atlas = GlyphAtlas::new(256, 256);
+    let position = Point { x: 5.0, y: 30.0 };
+    let fill_style = FillStyle::Plain(Color::black());
+
+    let mut canvas = new_canvas(200, 80, viewbox);
+    canvas.start_recording();
+    canvas.draw_text("Hill", &mut font, 24.0, position, fill_style, |p| *p);
+    canvas.draw_text_cached("Hill", &mut font, 24.0, position, fill_style, &mut atlas);
+    let recording = canvas.take_recording().unwrap();
+    assert_eq!(recording.commands.len(), 2);
+
+    // The glyphs of both calls are filled as a single shape over the same pixels.
+    let (direct, cached) = (&recording.commands[0], &recording.commands[1]);
+    for (a, b) in [
+        (direct.bounds.min_x, cached.bounds.min_x),
+        (direct.bounds.min_y, cached.bounds.min_y),
+        (direct.bounds.max_x, cached.bounds.max_x),
+        (direct.bounds.max_y, cached.bounds.max_y),
+    ] {
+        assert!(
+            (a - b).abs() <= 1,
+            "{:?} {:?}",
+            direct.bounds,
+            cached.bounds
+        );
+    }
+    assert!(direct.bounds.min_x >= 10 && direct.bounds.max_x < 120);
+    for command in &recording.commands {
+        assert!(command.path.len() > 4);
+        assert!(matches!(command.paint, Paint::Color(color) if color == Color::black()));
+        assert_eq!(command.blend, Some(BlendMode::SourceOver));
+    }
+
+    // Each layer of a color glyph is a command of its own.
+    let mut colr = Font::from_file("media/colr_1.ttf").unwrap();
+    let mut canvas = new_canvas(200, 80, viewbox);
+    canvas.start_recording();
+    canvas.draw_text("\u{F0E00}", &mut colr, 30.0, position, fill_style, |p| *p);
+    let recording = canvas.take_recording().unwrap();
+    assert!(recording.commands.len() > 1);
+    assert!(recording
+        .commands
+        .iter()
+        .all(|command| command.bounds.min_x >= 10 && command.bounds.max_x < 200));
+}
+
+#[test]
+fn svg_renders_like_the_canvas() {
+    let viewbox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 120.0,
+        height: 80.0,
+    };
+    let stops = [
+        (Color::yellow(), 0.0),
+        (Color::crimson(), 0.6),
+        (Color::dark_slate_blue(), 1.0),
+    ];
+    let star = [
+        PathOps::MoveTo { x: 0.0, y: -20.0 },
+        PathOps::LineTo { x: 12.0, y: 16.0 },
+        PathOps::LineTo { x: -19.0, y: -6.0 },
+        PathOps::LineTo { x: 19.0, y: -6.0 },
+        PathOps::LineTo { x: -12.0, y: 16.0 },
+        PathOps::Close,
+    ];
+    let blob = [
+        PathOps::MoveTo { x: 70.0, y: 10.0 },
+        PathOps::CubicTo {
+            x1: 120.0,
+            y1: 0.0,
+            x2: 120.0,
+            y2: 70.0,
+            x3: 80.0,
+            y3: 70.0,
+        },
+        PathOps::QuadTo {
+            x1: 50.0,
+            y1: 70.0,
+            x2: 70.0,
+            y2: 10.0,
+        },
+        PathOps::Close,
+    ];
+    let draw = |canvas: &mut Canvas| {
+        canvas.draw_shape(
+            &common::rect(5.0, 45.0, 30.0, 30.0),
+            FillStyle::Plain(Color::steel_blue()),
+            FillRule::NonZero,
+            |p| *p,
+        );
+        canvas.draw_shape(
+            &star,
+            FillStyle::Plain(Color::forest_green()),
+            FillRule::EvenOdd,
+            |p| translate(p, 30.0, 25.0),
+        );
+        canvas.draw_shape(
+            &blob,
+            FillStyle::LinearGradient {
+                stops: &stops,
+                angle: Angle::from_degrees(30.0),
+            },
+            FillRule::NonZero,
+            |p| *p,
+        );
+        canvas.draw_shape(
+            &common::rect(45.0, 45.0, 30.0, 30.0),
+            FillStyle::RadialGradient {
+                stops: &stops,
+                translation: Point { x: 5.0, y: 5.0 },
+            },
+            FillRule::NonZero,
+            |p| *p,
+        );
+    };
+
+    let mut canvas = new_canvas(240, 160, viewbox);
+    canvas.start_recording();
+    draw(&mut canvas);
+    let document = SvgDocument::parse(&canvas.take_recording().unwrap().to_svg()).unwrap();
+    assert_eq!(document.len(), 5);
+
+    let mut rendered = new_canvas(240, 160, viewbox);
+    rendered.draw_svg(&document);
+
+    let diff = ImageDiff::between(&canvas, &rendered);
+    // Colors are written with 8 bits.
+    assert!(diff.is_within(1.0 / 255.0, 0.001), "{}", diff);
+}
@@ -0,0 +1,309 @@
+// These tests draw the same scenes with `Canvas::draw_shape` and with the supersampled
+// rasterizer from `verg::reference`, the images should only differ by antialiasing noise.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, CurveRendering, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{Path, PathOps, Point},
+    math::{translate, Angle},
+    reference::{self, ImageDiff},
+};
+
+const SIZE: usize = 200;
+
+fn canvas_description(curve_rendering: CurveRendering) -> CanvasDescription {
+    CanvasDescription {
+        width: SIZE,
+        height: SIZE,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: SIZE as f64,
+            height: SIZE as f64,
+        },
+        background_color: Color::white(),
+        tolerance: 0.05,
+        curve_rendering,
+        ..Default::default()
+    }
+}
+
+type Shape<'a> = (Path<'a>, FillStyle<'a>, FillRule, Point);
+
+fn render(desc: CanvasDescription, shapes: &[Shape]) -> (Canvas, Canvas) {
+    let mut actual = Canvas::new(desc);
+    let mut expected = Canvas::new(desc);
+
+    for &(path, fill_style, fill_rule, offset) in shapes {
+        let transform = |p: &Point| translate(p, offset.x, offset.y);
+
+        actual.draw_shape(path, fill_style, fill_rule, transform);
+        reference::draw_shape(&mut expected, path, fill_style, fill_rule, transform);
+    }
+
+    (expected, actual)
+}
+
+fn assert_close(desc: CanvasDescription, shapes: &[Shape], max: f64, mean: f64) {
+    let (expected, actual) = render(desc, shapes);
+    let diff = ImageDiff::between(&expected, &actual);
+
+    assert!(diff.is_within(max, mean), "{}", diff);
+}
+
+// The reference misses at most one sample row per edge, so pixels crossed by a single edge
+// are off by at most 1 / 16.
+const MAX_EDGE_ERROR: f64 = 0.07;
+
+// Where edges cross inside a pixel the renderer only sees the average winding number of the
+// pixel, so self-intersecting shapes are only close to the reference on average.
+const MAX_CROSSING_ERROR: f64 = 0.6;
+
+const MAX_MEAN_ERROR: f64 = 0.001;
+
+const STAR: [PathOps; 6] = [
+    PathOps::MoveTo { x: 50.0, y: 0.0 },
+    PathOps::LineTo { x: 79.4, y: 90.5 },
+    PathOps::LineTo { x: 2.4, y: 34.5 },
+    PathOps::LineTo { x: 97.6, y: 34.5 },
+    PathOps::LineTo { x: 20.6, y: 90.5 },
+    PathOps::Close,
+];
+
+// Circle approximated with 4 cubic curves.
+const K: f64 = 0.552_284_749_831 * 40.0;
+const CIRCLE: [PathOps; 6] = [
+    PathOps::MoveTo { x: 40.0, y: 0.0 },
+    PathOps::CubicTo {
+        x1: 40.0 + K,
+        y1: 0.0,
+        x2: 80.0,
+        y2: 40.0 - K,
+        x3: 80.0,
+        y3: 40.0,
+    },
+    PathOps::CubicTo {
+        x1: 80.0,
+        y1: 40.0 + K,
+        x2: 40.0 + K,
+        y2: 80.0,
+        x3: 40.0,
+        y3: 80.0,
+    },
+    PathOps::CubicTo {
+        x1: 40.0 - K,
+        y1: 80.0,
+        x2: 0.0,
+        y2: 40.0 + K,
+        x3: 0.0,
+        y3: 40.0,
+    },
+    PathOps::CubicTo {
+        x1: 0.0,
+        y1: 40.0 - K,
+        x2: 40.0 - K,
+        y2: 0.0,
+        x3: 40.0,
+        y3: 0.0,
+    },
+    PathOps::Close,
+];
+
+const LEAF: [PathOps; 4] = [
+    PathOps::MoveTo { x: 0.0, y: 70.0 },
+    PathOps::QuadTo {
+        x1: 0.0,
+        y1: 0.0,
+        x2: 70.0,
+        y2: 0.0,
+    },
+    PathOps::QuadTo {
+        x1: 70.0,
+        y1: 70.0,
+        x2: 0.0,
+        y2: 70.0,
+    },
+    PathOps::Close,
+];
+
+const STRIPE: [PathOps; 5] = [
+    PathOps::MoveTo { x: 0.0, y: 0.0 },
+    PathOps::LineTo { x: 260.0, y: 30.0 },
+    PathOps::LineTo { x: 250.0, y: 60.0 },
+    PathOps::LineTo { x: -10.0, y: 30.0 },
+    PathOps::Close,
+];
+
+fn curves() -> Vec<Shape<'static>> {
+    vec![
+        (
+            &CIRCLE,
+            FillStyle::Plain(Color::steel_blue()),
+            FillRule::NonZero,
+            Point { x: 10.3, y: 10.7 },
+        ),
+        (
+            &LEAF,
+            FillStyle::Plain(Color::crimson()),
+            FillRule::NonZero,
+            Point { x: 110.5, y: 20.2 },
+        ),
+        (
+            &CIRCLE,
+            FillStyle::Plain(Color::forest_green()),
+            FillRule::EvenOdd,
+            Point { x: 100.9, y: 105.1 },
+        ),
+    ]
+}
+
+#[test]
+fn polygons_match_the_reference() {
+    let shapes = [
+        (
+            &STAR[..],
+            FillStyle::Plain(Color::black()),
+            FillRule::NonZero,
+            Point { x: 5.0, y: 5.0 },
+        ),
+        (
+            &STAR[..],
+            FillStyle::Plain(Color::crimson()),
+            FillRule::EvenOdd,
+            Point { x: 100.3, y: 100.6 },
+        ),
+    ];
+
+    assert_close(
+        canvas_description(CurveRendering::Flattened),
+        &shapes,
+        MAX_CROSSING_ERROR,
+        MAX_MEAN_ERROR,
+    );
+}
+
+#[test]
+fn flattened_curves_match_the_reference() {
+    assert_close(
+        canvas_description(CurveRendering::Flattened),
+        &curves(),
+        MAX_EDGE_ERROR,
+        MAX_MEAN_ERROR,
+    );
+}
+
+#[test]
+fn analytic_curves_match_the_reference() {
+    assert_close(
+        canvas_description(CurveRendering::Analytic),
+        &curves(),
+        MAX_EDGE_ERROR,
+        MAX_MEAN_ERROR,
+    );
+}
+
+#[test]
+fn gradients_match_the_reference() {
+    let linear_stops = [(Color::yellow(), 0.0), (Color::steel_blue(), 1.0)];
+    let radial_stops = [
+        (Color::black(), 0.0),
+        (Color::red(), 0.5),
+        (Color::white(), 1.0),
+    ];
+    let conic_stops = [
+        (Color::blue(), Angle::from_degrees(0.0)),
+        (Color::yellow(), Angle::from_degrees(180.0)),
+    ];
+    let shapes = [
+        (
+            &CIRCLE[..],
+            FillStyle::LinearGradient {
+                stops: &linear_stops,
+                angle: Angle::from_degrees(30.0),
+            },
+            FillRule::NonZero,
+            Point { x: 10.0, y: 10.0 },
+        ),
+        (
+            &STAR[..],
+            FillStyle::RadialGradient {
+                stops: &radial_stops,
+                translation: Point { x: 5.0, y: -5.0 },
+            },
+            FillRule::EvenOdd,
+            Point { x: 100.0, y: 10.0 },
+        ),
+        (
+            &LEAF[..],
+            FillStyle::ConicGradient {
+                stops: &conic_stops,
+                translation: Point { x: 0.0, y: 0.0 },
+            },
+            FillRule::NonZero,
+            Point { x: 60.0, y: 110.0 },
+        ),
+    ];
+
+    assert_close(
+        canvas_description(CurveRendering::Analytic),
+        &shapes,
+        MAX_CROSSING_ERROR,
+        MAX_MEAN_ERROR,
+    );
+}
+
+#[test]
+fn shapes_crossing_the_borders_match_the_reference() {
+    let shapes = [
+        (
+            &STRIPE[..],
+            FillStyle::Plain(Color::coral()),
+            FillRule::NonZero,
+            Point { x: -20.0, y: 40.0 },
+        ),
+        (
+            &CIRCLE[..],
+            FillStyle::Plain(Color::dark_slate_blue()),
+            FillRule::EvenOdd,
+            Point { x: 150.5, y: -30.5 },
+        ),
+        (
+            &STAR[..],
+            FillStyle::Plain(Color::black()),
+            FillRule::NonZero,
+            Point { x: -40.2, y: 140.7 },
+        ),
+    ];
+
+    assert_close(
+        canvas_description(CurveRendering::Analytic),
+        &shapes,
+        MAX_CROSSING_ERROR,
+        MAX_MEAN_ERROR,
+    );
+}
+
+#[test]
+fn identical_canvases_have_no_difference() {
+    let (expected, _) = render(canvas_description(CurveRendering::Analytic), &curves());
+    let diff = ImageDiff::between(&expected, &expected);
+
+    assert_eq!(diff.max_error(), 0.0);
+    assert_eq!(diff.max_channel_mean_error(), 0.0);
+}
+
+#[test]
+fn difference_is_reported_per_channel() {
+    let desc = canvas_description(CurveRendering::Flattened);
+    let white = Canvas::new(desc);
+    let yellow = Canvas::new(CanvasDescription {
+        background_color: Color::yellow(),
+        ..desc
+    });
+    let diff = ImageDiff::between(&white, &yellow);
+
+    assert_eq!(diff.max, [0.0, 0.0, 1.0, 0.0]);
+    assert_eq!(diff.mean, [0.0, 0.0, 1.0, 0.0]);
+    assert!(!diff.is_within(0.5, 1.0));
+    assert!(diff.is_within(1.0, 1.0));
+}
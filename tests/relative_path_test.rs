@@ -0,0 +1,96 @@
+// This tests that relative curves move the current point to their end point, the commands after
+// them should draw the same shape as their absolute equivalents.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::PathOps,
+};
+
+const SIZE: usize = 60;
+
+fn draw(path: &[PathOps]) -> Vec<u8> {
+    let mut canvas = Canvas::new(CanvasDescription {
+        width: SIZE,
+        height: SIZE,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: SIZE as f64,
+            height: SIZE as f64,
+        },
+        background_color: Color::white(),
+        ..Default::default()
+    });
+    canvas.draw_shape(
+        path,
+        FillStyle::Plain(Color::black()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    canvas.to_u8()
+}
+
+#[test]
+fn relative_quadratic_curves() {
+    let absolute = [
+        PathOps::MoveTo { x: 10.0, y: 20.0 },
+        PathOps::QuadTo {
+            x1: 30.0,
+            y1: 0.0,
+            x2: 50.0,
+            y2: 20.0,
+        },
+        PathOps::LineTo { x: 50.0, y: 50.0 },
+        PathOps::LineTo { x: 10.0, y: 50.0 },
+        PathOps::Close,
+    ];
+    let relative = [
+        PathOps::MoveTo { x: 10.0, y: 20.0 },
+        PathOps::QuadToRel {
+            x1: 20.0,
+            y1: -20.0,
+            x2: 40.0,
+            y2: 0.0,
+        },
+        PathOps::LineToRel { x: 0.0, y: 30.0 },
+        PathOps::LineToRel { x: -40.0, y: 0.0 },
+        PathOps::Close,
+    ];
+
+    assert!(draw(&absolute) == draw(&relative));
+}
+
+#[test]
+fn relative_cubic_curves() {
+    let absolute = [
+        PathOps::MoveTo { x: 10.0, y: 20.0 },
+        PathOps::CubicTo {
+            x1: 20.0,
+            y1: 0.0,
+            x2: 40.0,
+            y2: 40.0,
+            x3: 50.0,
+            y3: 20.0,
+        },
+        PathOps::LineTo { x: 50.0, y: 50.0 },
+        PathOps::LineTo { x: 10.0, y: 50.0 },
+        PathOps::Close,
+    ];
+    let relative = [
+        PathOps::MoveTo { x: 10.0, y: 20.0 },
+        PathOps::CubicToRel {
+            x1: 10.0,
+            y1: -20.0,
+            x2: 30.0,
+            y2: 20.0,
+            x3: 40.0,
+            y3: 0.0,
+        },
+        PathOps::LineToRel { x: 0.0, y: 30.0 },
+        PathOps::LineToRel { x: -40.0, y: 0.0 },
+        PathOps::Close,
+    ];
+
+    assert!(draw(&absolute) == draw(&relative));
+}
@@ -0,0 +1,190 @@
+// These tests convert colors to wide-gamut working spaces and check their ICC profiles.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, Compositing, ViewBox},
+    color::{Color, FillRule, FillStyle, RgbSpace},
+    geometry::PathOps,
+};
+
+const SPACES: [RgbSpace; 6] = [
+    RgbSpace::Srgb,
+    RgbSpace::LinearSrgb,
+    RgbSpace::DisplayP3,
+    RgbSpace::LinearDisplayP3,
+    RgbSpace::Rec2020,
+    RgbSpace::LinearRec2020,
+];
+
+fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+    let close = actual
+        .iter()
+        .zip(expected)
+        .all(|(a, e)| (a - e).abs() <= tolerance);
+
+    assert!(close, "got {:?}, expected {:?}", actual, expected);
+}
+
+fn channels(color: &Color) -> [f64; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+#[test]
+fn known_values() {
+    let red = Color::red();
+
+    // The values CSS gives for `color(srgb 1 0 0)` in these spaces.
+    assert_close(
+        &channels(&RgbSpace::DisplayP3.from_srgb(&red)),
+        &[0.917_45, 0.200_28, 0.138_56, 1.0],
+        1e-4,
+    );
+    assert_close(
+        &channels(&RgbSpace::Rec2020.from_srgb(&red)),
+        &[0.791_98, 0.230_98, 0.073_76, 1.0],
+        1e-4,
+    );
+    assert_close(
+        &channels(&RgbSpace::LinearSrgb.from_srgb(&Color::parse("#808080").unwrap())),
+        &[0.215_861, 0.215_861, 0.215_861, 1.0],
+        1e-6,
+    );
+
+    // Colors outside of sRGB have channels outside of [0, 1].
+    let p3_green = RgbSpace::DisplayP3.to_srgb(&Color {
+        r: 0.0,
+        g: 1.0,
+        b: 0.0,
+        a: 1.0,
+    });
+    assert!(p3_green.r < 0.0 && p3_green.g > 1.0 && !p3_green.in_gamut());
+}
+
+#[test]
+fn conversions_round_trip() {
+    let colors = ["#ff7f50", "#663399", "#20b2aa80", "#000000", "#ffffff"];
+
+    for space in SPACES {
+        for text in colors {
+            let color = Color::parse(text).unwrap();
+            let converted = space.from_srgb(&color);
+
+            assert_close(
+                &channels(&space.to_srgb(&converted)),
+                &channels(&color),
+                1e-9,
+            );
+        }
+
+        // Every space shares the D65 white point.
+        assert_close(
+            &channels(&space.from_srgb(&Color::white())),
+            &channels(&Color::white()),
+            1e-9,
+        );
+
+        for i in 0..=100 {
+            let c = i as f64 / 100.0;
+            assert_close(&[space.from_linear(space.to_linear(c))], &[c], 1e-12);
+        }
+    }
+}
+
+#[test]
+fn icc_profiles() {
+    let read_u32 = |profile: &[u8], offset: usize| {
+        u32::from_be_bytes(profile[offset..offset + 4].try_into().unwrap())
+    };
+    let read_xyz = |profile: &[u8], offset: usize| {
+        [0, 1, 2].map(|i| read_u32(profile, offset + 8 + 4 * i) as i32 as f64 / 65536.0)
+    };
+
+    for space in SPACES {
+        let profile = space.icc_profile();
+
+        assert_eq!(read_u32(&profile, 0) as usize, profile.len());
+        assert_eq!(&profile[12..24], b"mntrRGB XYZ ");
+        assert_eq!(&profile[36..40], b"acsp");
+
+        let num_tags = read_u32(&profile, 128) as usize;
+        let mut colorants = [0.0; 3];
+        let mut signatures = vec![];
+
+        for i in 0..num_tags {
+            let entry = 132 + 12 * i;
+            let signature = &profile[entry..entry + 4];
+            let (offset, size) = (
+                read_u32(&profile, entry + 4) as usize,
+                read_u32(&profile, entry + 8) as usize,
+            );
+
+            assert!(offset % 4 == 0 && offset + size <= profile.len());
+            signatures.push(signature.to_vec());
+
+            if signature.ends_with(b"XYZ") {
+                let xyz = read_xyz(&profile, offset);
+                (0..3).for_each(|i| colorants[i] += xyz[i]);
+            }
+        }
+
+        for expected in [
+            b"desc", b"cprt", b"wtpt", b"chad", b"rXYZ", b"gXYZ", b"bXYZ", b"rTRC", b"gTRC",
+            b"bTRC",
+        ] {
+            assert!(signatures.contains(&expected.to_vec()), "{:?}", space);
+        }
+
+        // The colorants add up to the D50 white point.
+        assert_close(&colorants, &[0.9642, 1.0, 0.8249], 1e-3);
+    }
+}
+
+#[test]
+fn drawing_in_display_p3() {
+    let mut canvas = Canvas::new(CanvasDescription {
+        width: 2,
+        height: 2,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: 2.0,
+            height: 2.0,
+        },
+        background_color: Color::white(),
+        color_space: RgbSpace::DisplayP3,
+        ..Default::default()
+    });
+    let path = [
+        PathOps::MoveTo { x: 1.0, y: 0.0 },
+        PathOps::LineTo { x: 2.0, y: 0.0 },
+        PathOps::LineTo { x: 2.0, y: 2.0 },
+        PathOps::LineTo { x: 1.0, y: 2.0 },
+        PathOps::Close,
+    ];
+
+    canvas.draw_shape(
+        &path,
+        FillStyle::Plain(Color::red()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    let pixels = canvas.to_u8();
+    assert_eq!(&pixels[0..4], &[254, 254, 254, 254]);
+    // sRGB red isn't the most saturated red of Display P3.
+    assert_eq!(&pixels[4..8], &[233, 51, 35, 254]);
+
+    // Compositing in linear light converts to the same pixels.
+    let mut linear = Canvas::new(CanvasDescription {
+        compositing: Compositing::LinearLight,
+        ..canvas.desc
+    });
+    linear.draw_shape(
+        &path,
+        FillStyle::Plain(Color::red()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+    for (a, b) in linear.to_u8().iter().zip(pixels) {
+        assert!((*a as i32 - b as i32).abs() <= 1);
+    }
+}
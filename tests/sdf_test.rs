@@ -0,0 +1,317 @@
+// This tests the signed distance fields generated from paths, plain and multi-channel.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule},
+    geometry::{segments, PathOps, Point, Segment},
+    mask::Mask,
+    math::map_viewbox,
+    sdf::{multi_channel_distance_field, signed_distance_field, DistanceField},
+};
+
+mod common;
+
+fn description(size: usize, viewbox_size: f64) -> CanvasDescription {
+    CanvasDescription {
+        width: size,
+        height: size,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: viewbox_size,
+            height: viewbox_size,
+        },
+        ..Default::default()
+    }
+}
+
+fn square(min: f64, max: f64) -> [PathOps; 5] {
+    [
+        PathOps::MoveTo { x: min, y: min },
+        PathOps::LineTo { x: max, y: min },
+        PathOps::LineTo { x: max, y: max },
+        PathOps::LineTo { x: min, y: max },
+        PathOps::Close,
+    ]
+}
+
+const LEAF: [PathOps; 4] = [
+    PathOps::MoveTo { x: 4.0, y: 28.0 },
+    PathOps::QuadTo {
+        x1: 4.0,
+        y1: 4.0,
+        x2: 28.0,
+        y2: 4.0,
+    },
+    PathOps::CubicTo {
+        x1: 30.0,
+        y1: 20.0,
+        x2: 16.0,
+        y2: 34.0,
+        x3: 4.0,
+        y3: 28.0,
+    },
+    PathOps::Close,
+];
+
+fn star(center: f64, outer: f64, inner: f64) -> Vec<PathOps> {
+    let mut path = (0..10)
+        .map(|i| {
+            let angle = std::f64::consts::PI * i as f64 / 5.0 - std::f64::consts::FRAC_PI_2;
+            let radius = if i % 2 == 0 { outer } else { inner };
+            let (x, y) = (center + radius * angle.cos(), center + radius * angle.sin());
+
+            if i == 0 {
+                PathOps::MoveTo { x, y }
+            } else {
+                PathOps::LineTo { x, y }
+            }
+        })
+        .collect::<Vec<PathOps>>();
+    path.push(PathOps::Close);
+    path
+}
+
+fn assert_close(actual: f32, expected: f64, tolerance: f64) {
+    assert!(
+        (actual as f64 - expected).abs() <= tolerance,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn distances_to_lines() {
+    let sdf = signed_distance_field(
+        &description(20, 20.0),
+        &square(5.0, 15.0),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    assert_eq!((sdf.width, sdf.height, sdf.channels), (20, 20, 1));
+    // Outside a corner the distance is to the corner itself.
+    assert_close(sdf.distance(0, 0), -(2.0 * 4.5_f64 * 4.5).sqrt(), 1e-6);
+    assert_close(sdf.distance(10, 0), -4.5, 1e-6);
+    assert_close(sdf.distance(10, 10), 4.5, 1e-6);
+    assert_close(sdf.distance(6, 12), 1.5, 1e-6);
+}
+
+#[test]
+fn distances_to_curves_are_exact() {
+    let desc = description(32, 32.0);
+    let sdf = signed_distance_field(&desc, &LEAF, FillRule::NonZero, |p| *p);
+    // Brute force: the closest of many points along the outline.
+    let samples = segments(&LEAF, |p| map_viewbox(&desc, p))
+        .iter()
+        .flat_map(|segment| {
+            (0..=4000).filter_map(move |i| {
+                let t = i as f64 / 4000.0;
+                match segment {
+                    Segment::Quad(curve) => Some(curve.eval(t)),
+                    Segment::Cubic(curve) => Some(curve.eval(t)),
+                    Segment::Close(a, b) => Some(Point {
+                        x: a.x + (b.x - a.x) * t,
+                        y: a.y + (b.y - a.y) * t,
+                    }),
+                    _ => None,
+                }
+            })
+        })
+        .collect::<Vec<Point>>();
+
+    for y in (0..32).step_by(3) {
+        for x in (0..32).step_by(3) {
+            let center = Point {
+                x: x as f64 + 0.5,
+                y: y as f64 + 0.5,
+            };
+            let sampled = samples
+                .iter()
+                .map(|p| p.distance_to(&center))
+                .fold(f64::MAX, f64::min);
+            let exact = sdf.distance(x, y).abs() as f64;
+
+            // Samples can only be further than the closest point.
+            assert!(
+                exact <= sampled + 1e-5 && sampled - exact < 5e-3,
+                "at {}x{}: {} vs {}",
+                x,
+                y,
+                exact,
+                sampled
+            );
+        }
+    }
+}
+
+#[test]
+fn fill_rules() {
+    let mut path = square(2.0, 18.0).to_vec();
+    path.extend(square(6.0, 14.0));
+    let desc = description(20, 20.0);
+
+    let non_zero = signed_distance_field(&desc, &path, FillRule::NonZero, |p| *p);
+    let even_odd = signed_distance_field(&desc, &path, FillRule::EvenOdd, |p| *p);
+
+    assert_close(non_zero.distance(9, 9), 3.5, 1e-6);
+    assert_close(even_odd.distance(9, 9), -3.5, 1e-6);
+    assert_close(non_zero.distance(3, 9), 1.5, 1e-6);
+    assert_close(even_odd.distance(3, 9), 1.5, 1e-6);
+
+    let msdf = multi_channel_distance_field(&desc, &path, FillRule::EvenOdd, |p| *p);
+    assert!(msdf.distance(9, 9) < 0.0 && msdf.distance(3, 9) > 0.0);
+}
+
+#[test]
+fn signs_match_the_rasterizer() {
+    let mut path = LEAF.to_vec();
+    path.extend(star(16.0, 14.0, 6.0));
+
+    for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+        let desc = CanvasDescription {
+            tolerance: 0.05,
+            ..description(32, 32.0)
+        };
+        let sdf = signed_distance_field(&desc, &path, fill_rule, |p| *p);
+        let msdf = multi_channel_distance_field(&desc, &path, fill_rule, |p| *p);
+        let mut mask = Mask::new(desc);
+        mask.draw_shape(&path, fill_rule, |p| *p);
+
+        for (i, coverage) in mask.coverage.iter().enumerate() {
+            let (x, y) = (i % 32, i / 32);
+            let distance = sdf.distance(x, y);
+
+            // Pixels further than half a diagonal from the outline are fully in or out.
+            if distance.abs() > 0.75 {
+                assert_eq!(
+                    *coverage > 0.5,
+                    distance > 0.0,
+                    "{:?} at {}x{}",
+                    fill_rule,
+                    x,
+                    y
+                );
+            }
+            assert_eq!(
+                msdf.distance(x, y) > 0.0,
+                distance > 0.0,
+                "{:?} at {}x{}",
+                fill_rule,
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn multi_channel_fields_keep_corners() {
+    let desc = description(20, 20.0);
+    let sdf = signed_distance_field(&desc, &square(5.0, 15.0), FillRule::NonZero, |p| *p);
+    let msdf = multi_channel_distance_field(&desc, &square(5.0, 15.0), FillRule::NonZero, |p| *p);
+
+    assert_eq!(msdf.channels, 3);
+    assert_eq!(msdf.distances.len(), 20 * 20 * 3);
+    // The median extends the sides of the corner instead of rounding it.
+    assert_close(sdf.distance(0, 0), -6.364, 1e-3);
+    assert_close(msdf.distance(0, 0), -4.5, 1e-6);
+    // Along the sides both agree.
+    assert_close(msdf.distance(10, 2), -2.5, 1e-6);
+    assert_close(msdf.distance(10, 10), 4.5, 1e-6);
+}
+
+#[test]
+fn eight_bit_output() {
+    let field = DistanceField {
+        width: 5,
+        height: 1,
+        channels: 1,
+        distances: vec![-8.0, -4.0, 0.0, 2.0, 4.0],
+    };
+
+    assert_eq!(field.to_u8(4.0), [0, 0, 128, 191, 255]);
+}
+
+// Bilinear interpolation of the distance field, what a GPU sampler does.
+fn sample(field: &DistanceField, x: f64, y: f64) -> f64 {
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let texel = |x: f64, y: f64, channel: usize| {
+        let x = x.clamp(0.0, field.width as f64 - 1.0) as usize;
+        let y = y.clamp(0.0, field.height as f64 - 1.0) as usize;
+        field.distances[(y * field.width + x) * field.channels + channel] as f64
+    };
+    let channel = |c: usize| {
+        let top = texel(x0, y0, c) * (1.0 - fx) + texel(x0 + 1.0, y0, c) * fx;
+        let bottom = texel(x0, y0 + 1.0, c) * (1.0 - fx) + texel(x0 + 1.0, y0 + 1.0, c) * fx;
+        top * (1.0 - fy) + bottom * fy
+    };
+
+    match field.channels {
+        3 => {
+            let (r, g, b) = (channel(0), channel(1), channel(2));
+            r.min(g).max(r.max(g).min(b))
+        }
+        _ => channel(0),
+    }
+}
+
+#[test]
+fn distance_fields() {
+    const FIELD_SIZE: usize = 24;
+    const PANEL_SIZE: usize = 192;
+    const RANGE: f64 = 3.0;
+
+    let desc = description(FIELD_SIZE, 100.0);
+    let star = star(50.0, 46.0, 20.0);
+    let sdf = signed_distance_field(&desc, &star, FillRule::NonZero, |p| *p);
+    let msdf = multi_channel_distance_field(&desc, &star, FillRule::NonZero, |p| *p);
+    let (sdf_u8, msdf_u8) = (sdf.to_u8(RANGE), msdf.to_u8(RANGE));
+    let scale = FIELD_SIZE as f64 / PANEL_SIZE as f64;
+
+    // From left to right: the raw fields, then both upscaled and thresholded by a shader.
+    let panels: [&dyn Fn(usize, usize) -> [f64; 3]; 4] = [
+        &|x, y| {
+            let i = (y as f64 * scale) as usize * FIELD_SIZE + (x as f64 * scale) as usize;
+            [sdf_u8[i] as f64; 3]
+        },
+        &|x, y| {
+            let i = (y as f64 * scale) as usize * FIELD_SIZE + (x as f64 * scale) as usize;
+            [0, 1, 2].map(|c| msdf_u8[i * 3 + c] as f64)
+        },
+        &|x, y| {
+            let d = sample(&sdf, (x as f64 + 0.5) * scale, (y as f64 + 0.5) * scale) / scale;
+            [(0.5 + d).clamp(0.0, 1.0) * 255.0; 3]
+        },
+        &|x, y| {
+            let d = sample(&msdf, (x as f64 + 0.5) * scale, (y as f64 + 0.5) * scale) / scale;
+            [(0.5 + d).clamp(0.0, 1.0) * 255.0; 3]
+        },
+    ];
+    let width = PANEL_SIZE * panels.len();
+    let mut canvas = Canvas::new(CanvasDescription {
+        width,
+        height: PANEL_SIZE,
+        background_color: Color::white(),
+        ..description(PANEL_SIZE, PANEL_SIZE as f64)
+    });
+
+    for y in 0..PANEL_SIZE {
+        for x in 0..width {
+            let [r, g, b] = panels[x / PANEL_SIZE](x % PANEL_SIZE, y);
+            let offset = (y * width + x) * 4;
+            // The middle of each 8-bit level is truncated to that level by `to_u8`.
+            canvas.buffer[offset..offset + 4].copy_from_slice(&[
+                (r.round() + 0.5) / 255.0,
+                (g.round() + 0.5) / 255.0,
+                (b.round() + 0.5) / 255.0,
+                1.0,
+            ]);
+        }
+    }
+
+    common::check_against_reference("distance_fields", &canvas);
+}
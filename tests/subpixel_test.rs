@@ -0,0 +1,143 @@
+// This tests subpixel (LCD) antialiasing, selected for individual `draw_shape` calls.
+
+use verg::{
+    canvas::Canvas,
+    color::{Antialiasing, Color, FillMode, FillRule, FillStyle, SubpixelOrder},
+    geometry::{PathOps, Point},
+    math::{scale, translate},
+    recording::BlendMode,
+    text::Font,
+};
+
+mod common;
+
+const RGB: FillMode = FillMode {
+    fill_rule: FillRule::NonZero,
+    antialiasing: Antialiasing::Subpixel(SubpixelOrder::Rgb),
+};
+const BGR: FillMode = FillMode {
+    fill_rule: FillRule::NonZero,
+    antialiasing: Antialiasing::Subpixel(SubpixelOrder::Bgr),
+};
+
+// White on black, so every channel is the coverage of its subpixel.
+fn coverage(fill_mode: impl Into<FillMode>, path: &[PathOps]) -> Canvas {
+    let mut canvas = common::new_canvas_with_background(32, 8, Color::black());
+    canvas.draw_shape(path, FillStyle::Plain(Color::white()), fill_mode, |p| *p);
+    canvas
+}
+
+#[test]
+fn coverage_is_kept() {
+    let path = common::rect(10.3, 1.0, 10.4, 6.0);
+    let grayscale = coverage(FillRule::NonZero, &path);
+    let subpixel = coverage(RGB, &path);
+    let sum = |canvas: &Canvas, channels: &[usize]| {
+        canvas
+            .buffer
+            .chunks(4)
+            .map(|pixel| channels.iter().map(|c| pixel[*c]).sum::<f64>())
+            .sum::<f64>()
+    };
+
+    assert!((sum(&subpixel, &[0, 1, 2]) - 3.0 * sum(&grayscale, &[0])).abs() < 1e-3);
+    // Pixels far enough from the edges are fully covered either way.
+    assert_eq!(&subpixel.buffer[(3 * 32 + 15) * 4..][..3], &[1.0; 3]);
+    assert!(subpixel
+        .subpixel_accumulation_buffer
+        .iter()
+        .all(|cell| cell.area == 0.0));
+}
+
+#[test]
+fn subpixel_order() {
+    // The left edge of the shape is in the middle of pixel 10.
+    let path = common::rect(10.5, 1.0, 10.0, 6.0);
+    let (rgb, bgr) = (coverage(RGB, &path), coverage(BGR, &path));
+    let pixel = |canvas: &Canvas| canvas.buffer[(3 * 32 + 10) * 4..][..4].to_vec();
+
+    let (left, right) = (pixel(&rgb), pixel(&bgr));
+    // The blue subpixel is on the right with RGB, covered by more of the shape.
+    assert!(left[0] < left[1] && left[1] < left[2], "{:?}", left);
+    assert_eq!(right, [left[2], left[1], left[0], left[3]]);
+    // The filter spreads some coverage to the previous pixel.
+    assert!(rgb.buffer[(3 * 32 + 9) * 4 + 2] > 0.0);
+}
+
+#[test]
+fn shapes_outside_the_canvas() {
+    for path in [
+        common::rect(-20.0, 1.0, 10.0, 6.0),
+        common::rect(40.0, 1.0, 10.0, 6.0),
+    ] {
+        let canvas = coverage(RGB, &path);
+
+        assert!(canvas
+            .buffer
+            .chunks(4)
+            .all(|pixel| pixel == [0.0, 0.0, 0.0, 1.0]));
+        assert!(canvas
+            .subpixel_accumulation_buffer
+            .iter()
+            .all(|cell| cell.area == 0.0));
+    }
+}
+
+#[test]
+fn only_covered_pixels_are_blended() {
+    // `destination_in` changes every pixel it blends with a translucent color, and clears the
+    // ones with zero coverage.
+    let mut canvas = common::new_canvas(32, 8);
+    canvas.set_blend_mode(BlendMode::DestinationIn);
+    canvas.draw_shape(
+        &common::rect(10.0, 2.0, 4.0, 3.0),
+        FillStyle::Plain(Color {
+            a: 0.5,
+            ..Color::black()
+        }),
+        RGB,
+        |p| *p,
+    );
+
+    for y in 0..8 {
+        for x in 0..32 {
+            let pixel = &canvas.buffer[(y * 32 + x) * 4..][..4];
+            let blended = (2..5).contains(&y) && (9..15).contains(&x);
+
+            assert_eq!(pixel == [1.0; 4], !blended, "pixel {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn subpixel_text() {
+    const FONT_SIZE: f64 = 13.0;
+    const LINE_HEIGHT: f64 = 20.0;
+
+    let mut font = Font::from_file("media/Roboto-MediumItalic.ttf").unwrap();
+    let em = font.scale(FONT_SIZE);
+    let mut canvas = common::new_canvas(240, 3 * LINE_HEIGHT as usize);
+    let modes = [FillMode::from(FillRule::NonZero), RGB, BGR];
+
+    // The same line of text with each mode, one below the other.
+    for (line, fill_mode) in modes.iter().enumerate() {
+        let mut pen = 4.0;
+
+        for c in "Subpixel text, 13px".chars() {
+            let glyph = font.glyph_index(c).unwrap();
+            let advance = font.glyph_metrics(glyph).advance;
+            let baseline = LINE_HEIGHT * (line as f64 + 0.75);
+
+            canvas.draw_shape(
+                font.outline(glyph),
+                FillStyle::Plain(Color::black()),
+                *fill_mode,
+                |p: &Point| translate(&scale(p, em, em), pen, baseline),
+            );
+
+            pen += advance * em;
+        }
+    }
+
+    common::check_against_reference("subpixel_text", &canvas);
+}
@@ -0,0 +1,293 @@
+// This tests parsing and rendering SVG documents: path data, transforms, styles, references,
+// strokes and gradients, with reference renders of a few documents.
+
+use verg::{
+    canvas::{Canvas, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    svg::{parse_path, SvgDocument, SvgError, Transform},
+};
+
+mod common;
+
+fn render(text: &str, width: usize, height: usize) -> Canvas {
+    let mut canvas = common::new_canvas(width, height);
+    canvas.draw_svg(&SvgDocument::parse(text).unwrap());
+    canvas
+}
+
+// The end points of the commands.
+fn points(path: &[PathOps]) -> Vec<(f64, f64)> {
+    path.iter()
+        .filter_map(|op| match *op {
+            PathOps::MoveTo { x, y } | PathOps::LineTo { x, y } => Some((x, y)),
+            PathOps::QuadTo { x2, y2, .. } => Some((x2, y2)),
+            PathOps::CubicTo { x3, y3, .. } => Some((x3, y3)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn assert_close(a: (f64, f64), b: (f64, f64)) {
+    assert!(
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9,
+        "{:?} {:?}",
+        a,
+        b
+    );
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(SvgDocument::parse("<svg"), Err(SvgError::Xml(_))));
+    assert!(matches!(
+        SvgDocument::parse("<html/>"),
+        Err(SvgError::NotSvg)
+    ));
+    assert!(matches!(
+        SvgDocument::from_file("tests/svg/missing.svg"),
+        Err(SvgError::Io(_))
+    ));
+
+    // A malformed transform disables its element instead of failing the document.
+    let document = SvgDocument::parse(
+        r#"<svg xmlns="http://www.w3.org/2000/svg"><rect transform="a)(1" width="10" height="10"/></svg>"#,
+    )
+    .unwrap();
+    assert!(document.is_empty());
+}
+
+#[test]
+fn path_data() {
+    // Implicit line commands, relative coordinates and numbers without separators.
+    let path = parse_path("M10-5.5.5.5l10 0 0 10H0V2z m1 1 1 1");
+    assert_eq!(
+        points(&path),
+        vec![
+            (10.0, -5.5),
+            (0.5, 0.5),
+            (10.5, 0.5),
+            (10.5, 10.5),
+            (0.0, 10.5),
+            (0.0, 2.0),
+            (11.0, -4.5),
+            (12.0, -3.5),
+        ]
+    );
+    assert!(matches!(path[6], PathOps::Close));
+
+    // Smooth curves reflect the previous control point.
+    let path = parse_path("M0 0C0 10 10 10 10 0S20-10 20 0Q25 5 30 0T40 0");
+    match (path[2], path[4]) {
+        (PathOps::CubicTo { x1, y1, .. }, PathOps::QuadTo { x1: qx, y1: qy, .. }) => {
+            assert_close((x1, y1), (10.0, -10.0));
+            assert_close((qx, qy), (35.0, -5.0));
+        }
+        _ => panic!("expected curves"),
+    }
+
+    // A half circle arc ends at its end point and passes through the top of the circle.
+    let path = parse_path("M0 0A10 10 0 0 1 20 0");
+    assert_eq!(path.len(), 3);
+    assert_close(points(&path)[1], (10.0, -10.0));
+    assert_close(points(&path)[2], (20.0, 0.0));
+    // Radii too small are scaled up, flags can be written without separators.
+    assert_eq!(points(&parse_path("M0 0a1 1 0 0020 0")).len(), 3);
+
+    // The path is kept up to the first error.
+    assert_eq!(parse_path("M0 0L10 10L20").len(), 2);
+    assert!(parse_path("L10 10").len() <= 1);
+}
+
+#[test]
+fn transforms() {
+    let t = Transform::parse("translate(10, 20) rotate(90) scale(2)").unwrap();
+    let p = t.apply(&Point { x: 1.0, y: 0.0 });
+    assert_close((p.x, p.y), (10.0, 22.0));
+
+    let around = Transform::parse("rotate(180 5 5)").unwrap();
+    let p = around.apply(&Point { x: 0.0, y: 0.0 });
+    assert_close((p.x, p.y), (10.0, 10.0));
+
+    let skew = Transform::parse("skewX(45)").unwrap();
+    let p = skew.apply(&Point { x: 0.0, y: 2.0 });
+    assert_close((p.x, p.y), (2.0, 2.0));
+
+    assert!(Transform::parse("rotate(1 2)").is_none());
+    assert!(Transform::parse("spin(10)").is_none());
+}
+
+#[test]
+fn documents_resolve_to_shapes() {
+    let document = SvgDocument::parse(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="10cm" height="50%" viewBox="0 0 200 100">
+            <defs><rect id="r" width="10" height="10"/></defs>
+            <g fill="none">
+                <rect width="10" height="10"/>
+                <rect width="10" height="10" stroke="red"/>
+                <use href="#r" fill="blue"/>
+                <use href="#missing"/>
+                <rect width="0" height="10" fill="red"/>
+                <circle r="5" display="none" fill="red"/>
+                <rect width="10" height="10" fill="red" transform="bogus(1)"/>
+            </g>
+        </svg>"##,
+    )
+    .unwrap();
+
+    assert_eq!(document.len(), 2);
+    assert!((document.width - 96.0 / 2.54 * 10.0).abs() < 1e-9);
+    assert_eq!(document.height, 50.0);
+
+    // Numbers can have an exponent, before their unit too.
+    let document = SvgDocument::parse(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="1e2" height="2.5E1px"/>"#,
+    )
+    .unwrap();
+    assert_eq!((document.width, document.height), (100.0, 25.0));
+
+    // The document without a size takes the one of its `viewBox`, which is centered in the
+    // viewport.
+    let document =
+        SvgDocument::parse(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10"/>"#)
+            .unwrap();
+    assert_eq!((document.width, document.height), (20.0, 10.0));
+    assert!(document.is_empty());
+    let viewport = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+    let p = document
+        .viewport_transform(&viewport)
+        .apply(&Point { x: 0.0, y: 0.0 });
+    assert_close((p.x, p.y), (0.0, 25.0));
+}
+
+#[test]
+fn use_expansion_is_bounded() {
+    // Each level uses the previous one ten times, which would make 10^8 rectangles.
+    let mut defs = r#"<rect id="l0" width="1" height="1" fill="red"/>"#.to_string();
+    for level in 1..=8 {
+        defs += &format!(r#"<g id="l{}">"#, level);
+        for _ in 0..10 {
+            defs += &format!(r##"<use href="#l{}"/>"##, level - 1);
+        }
+        defs += "</g>";
+    }
+    let document = SvgDocument::parse(&format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg"><defs>{}</defs><use href="#l8"/></svg>"##,
+        defs
+    ))
+    .unwrap();
+
+    assert!(!document.is_empty() && document.len() <= 100_000);
+}
+
+#[test]
+fn preserve_aspect_ratio() {
+    let viewport = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 50.0,
+    };
+    let corner = |aspect: &str| {
+        let document = SvgDocument::parse(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10" preserveAspectRatio="{}"/>"#,
+            aspect
+        ))
+        .unwrap();
+        let t = document.viewport_transform(&viewport);
+        let p = t.apply(&Point { x: 10.0, y: 10.0 });
+        (p.x, p.y)
+    };
+
+    assert_close(corner("xMidYMid meet"), (75.0, 50.0));
+    assert_close(corner("xMinYMin"), (50.0, 50.0));
+    assert_close(corner("xMaxYMax meet"), (100.0, 50.0));
+    assert_close(corner("xMinYMin slice"), (100.0, 100.0));
+    assert_close(corner("xMidYMax slice"), (100.0, 50.0));
+    assert_close(corner("none"), (100.0, 50.0));
+    // Anything else is the default, centered and meeting.
+    assert_close(corner("aMidbMid"), (75.0, 50.0));
+    assert_close(corner("aaaéaaa"), (75.0, 50.0));
+    assert_close(corner("xMidYMidé"), (75.0, 50.0));
+}
+
+#[test]
+fn fills_match_draw_shape() {
+    let svg = render(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+            <path d="M5.5 4 L30 9 L20 35.3 Z" fill="rgb(0, 0, 255)"/>
+        </svg>"#,
+        40,
+        40,
+    );
+    let mut expected = common::new_canvas(40, 40);
+    expected.draw_shape(
+        &[
+            PathOps::MoveTo { x: 5.5, y: 4.0 },
+            PathOps::LineTo { x: 30.0, y: 9.0 },
+            PathOps::LineTo { x: 20.0, y: 35.3 },
+            PathOps::Close,
+        ],
+        FillStyle::Plain(Color::blue()),
+        FillRule::NonZero,
+        |p| *p,
+    );
+
+    assert_eq!(svg.to_u8(), expected.to_u8());
+
+    // Open subpaths are filled as if they were closed.
+    let open = render(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+            <path d="M5.5 4 L30 9 L20 35.3" fill="rgb(0, 0, 255)"/>
+        </svg>"#,
+        40,
+        40,
+    );
+    assert_eq!(open.to_u8(), expected.to_u8());
+}
+
+#[test]
+fn strokes_cover_their_width() {
+    let canvas = render(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40">
+            <path d="M5 20 H35" stroke="black" stroke-width="4"/>
+            <rect x="5" y="30" width="10" height="6" fill="none" stroke="black" opacity="0.5"/>
+        </svg>"#,
+        40,
+        40,
+    );
+    let red = |x: usize, y: usize| canvas.buffer[(y * 40 + x) * 4];
+
+    // Rows 18 to 21 are covered, butt caps end at the end points.
+    for y in 16..24 {
+        let expected = if (18..22).contains(&y) { 0.0 } else { 1.0 };
+        assert!((red(20, y) - expected).abs() < 1e-6, "{} {}", y, red(20, y));
+    }
+    assert!((red(4, 20) - 1.0).abs() < 1e-6 && red(5, 20) < 1e-6);
+    assert!((red(35, 20) - 1.0).abs() < 1e-6 && red(34, 20) < 1e-6);
+
+    // A one pixel wide stroke straddles the edge of the rectangle, half transparent.
+    assert!((red(10, 29) - 0.75).abs() < 1e-6, "{}", red(10, 29));
+    assert!((red(10, 30) - 0.75).abs() < 1e-6, "{}", red(10, 30));
+    assert!((red(10, 32) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn reference_renders() {
+    for (name, width, height) in [
+        ("shapes", 240, 180),
+        ("gradients", 200, 120),
+        ("strokes", 320, 240),
+    ] {
+        let document = SvgDocument::from_file(format!("tests/svg/{}.svg", name)).unwrap();
+        let mut canvas = common::new_canvas(width, height);
+        canvas.draw_svg(&document);
+
+        common::check_against_reference(&format!("svg_{}", name), &canvas);
+    }
+}
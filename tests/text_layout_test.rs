@@ -0,0 +1,230 @@
+// This tests laying out and drawing text: advances, kerning, alignment, baselines and wrapping.
+
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    math::Angle,
+    text::{layout_text, Baseline, Font, PositionedGlyph, TextAlign, TextLayout},
+};
+
+mod common;
+
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+const ORIGIN: Point = Point { x: 0.0, y: 0.0 };
+
+fn font() -> Font {
+    Font::from_file(ROBOTO).unwrap()
+}
+
+// The x of each glyph, and the y of each line.
+fn rows(glyphs: &[PositionedGlyph]) -> Vec<(f64, Vec<f64>)> {
+    let mut rows: Vec<(f64, Vec<f64>)> = vec![];
+
+    for glyph in glyphs {
+        match rows.last_mut() {
+            Some((y, xs)) if *y == glyph.position.y => xs.push(glyph.position.x),
+            _ => rows.push((glyph.position.y, vec![glyph.position.x])),
+        }
+    }
+
+    rows
+}
+
+#[test]
+fn advances_and_kerning() {
+    let font = font();
+    let scale = font.scale(20.0);
+    let glyph = |c| font.glyph_index(c).unwrap();
+    let (a, v) = (glyph('A'), glyph('V'));
+
+    // Roboto only has GPOS kerning, `A` and `V` are pulled together.
+    let kerning = font.kerning(a, v);
+    assert!(kerning < 0.0);
+    assert_eq!(font.kerning(glyph('l'), glyph('l')), 0.0);
+
+    let glyphs = layout_text("AVl", &font, 20.0, ORIGIN);
+    let advance = |g| font.glyph_metrics(g).advance;
+    let xs = &rows(&glyphs)[0].1;
+    assert_eq!(xs[0], 0.0);
+    assert!((xs[1] - (advance(a) + kerning) * scale).abs() < 1e-9);
+    assert!((xs[2] - (advance(a) + kerning + advance(v)) * scale).abs() < 1e-9);
+}
+
+#[test]
+fn alignment() {
+    let font = font();
+    let width = |glyphs: &[PositionedGlyph]| {
+        let last = glyphs.last().unwrap();
+        last.position.x - glyphs[0].position.x
+            + font.glyph_metrics(last.glyph).advance * font.scale(20.0)
+    };
+    let first_x = |align, box_width| {
+        let glyphs = layout_text(
+            "Verg",
+            &font,
+            20.0,
+            TextLayout {
+                align,
+                width: box_width,
+                ..TextLayout::from(Point { x: 10.0, y: 0.0 })
+            },
+        );
+        (glyphs[0].position.x, width(&glyphs))
+    };
+
+    let (left, text_width) = first_x(TextAlign::Left, None);
+    assert_eq!(left, 10.0);
+    assert!((first_x(TextAlign::Center, None).0 - (10.0 - text_width / 2.0)).abs() < 1e-9);
+    assert!((first_x(TextAlign::Right, None).0 - (10.0 - text_width)).abs() < 1e-9);
+    // In a box, the text is aligned to its sides.
+    assert_eq!(first_x(TextAlign::Left, Some(100.0)).0, 10.0);
+    assert!((first_x(TextAlign::Center, Some(100.0)).0 - (60.0 - text_width / 2.0)).abs() < 1e-9);
+    assert!((first_x(TextAlign::Right, Some(100.0)).0 - (110.0 - text_width)).abs() < 1e-9);
+}
+
+#[test]
+fn baselines() {
+    let font = font();
+    let metrics = font.metrics();
+    let scale = font.scale(20.0);
+    let y = |baseline| {
+        layout_text(
+            "x",
+            &font,
+            20.0,
+            TextLayout {
+                baseline,
+                ..TextLayout::from(Point { x: 0.0, y: 50.0 })
+            },
+        )[0]
+        .position
+        .y
+    };
+
+    assert_eq!(y(Baseline::Alphabetic), 50.0);
+    assert!((y(Baseline::Top) - (50.0 - metrics.ascender * scale)).abs() < 1e-9);
+    assert!((y(Baseline::Bottom) - (50.0 - metrics.descender * scale)).abs() < 1e-9);
+    assert!(y(Baseline::Top) > y(Baseline::Middle) && y(Baseline::Middle) > 50.0);
+}
+
+#[test]
+fn line_breaking() {
+    let font = font();
+    let line_height = font.metrics().line_height * font.scale(20.0);
+
+    // Explicit line breaks.
+    let lines = rows(&layout_text("ab\ncd", &font, 20.0, ORIGIN));
+    assert_eq!(lines.len(), 2);
+    assert!((lines[1].0 - line_height).abs() < 1e-9);
+    assert_eq!(lines[1].1[0], 0.0);
+
+    // Words are moved to the next line when they don't fit.
+    let wrapped = |width| {
+        rows(&layout_text(
+            "one two three",
+            &font,
+            20.0,
+            TextLayout {
+                width: Some(width),
+                line_height: Some(30.0),
+                ..TextLayout::from(ORIGIN)
+            },
+        ))
+        .iter()
+        .map(|(y, xs)| (*y, xs.len()))
+        .collect::<Vec<(f64, usize)>>()
+    };
+    assert_eq!(wrapped(1000.0), [(0.0, 13)]);
+    assert_eq!(wrapped(80.0), [(0.0, 7), (30.0, 5)]);
+    // A word longer than the box stays on its own line.
+    assert_eq!(wrapped(1.0), [(0.0, 3), (30.0, 3), (60.0, 5)]);
+}
+
+#[test]
+fn text_layout() {
+    const WIDTH: usize = 360;
+    const HEIGHT: usize = 240;
+
+    let mut font = font();
+    let mut canvas = Canvas::new(CanvasDescription {
+        width: WIDTH,
+        height: HEIGHT,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: WIDTH as f64,
+            height: HEIGHT as f64,
+        },
+        background_color: Color::white(),
+        ..Default::default()
+    });
+    let text = "The quick brown fox jumps over the lazy dog. AVA Tokyo";
+    let stops = [(Color::crimson(), 0.0), (Color::dark_slate_blue(), 1.0)];
+
+    // Three boxes with each alignment, the text wraps inside them.
+    for (i, align) in [TextAlign::Left, TextAlign::Center, TextAlign::Right]
+        .into_iter()
+        .enumerate()
+    {
+        let x = 10.0 + i as f64 * 115.0;
+        let frame = [
+            PathOps::MoveTo { x, y: 10.0 },
+            PathOps::LineTo {
+                x: x + 110.0,
+                y: 10.0,
+            },
+            PathOps::LineTo {
+                x: x + 110.0,
+                y: 130.0,
+            },
+            PathOps::LineTo { x, y: 130.0 },
+            PathOps::Close,
+        ];
+        canvas.draw_shape(
+            &frame,
+            FillStyle::Plain(Color {
+                a: 0.15,
+                ..Color::steel_blue()
+            }),
+            FillRule::NonZero,
+            |p| *p,
+        );
+        canvas.draw_text(
+            text,
+            &mut font,
+            15.0,
+            TextLayout {
+                align,
+                baseline: Baseline::Top,
+                width: Some(110.0),
+                ..TextLayout::from(Point { x, y: 10.0 })
+            },
+            FillStyle::Plain(Color::black()),
+            |p| *p,
+        );
+    }
+
+    // A gradient spanning the whole text, rotated around its start.
+    let start = Point { x: 30.0, y: 200.0 };
+    canvas.draw_text(
+        "Verg, rotated",
+        &mut font,
+        36.0,
+        start,
+        FillStyle::LinearGradient {
+            stops: &stops,
+            angle: Angle::from_degrees(0.0),
+        },
+        |p| {
+            let (sin, cos) = (-8.0_f64).to_radians().sin_cos();
+            let (x, y) = (p.x - start.x, p.y - start.y);
+            Point {
+                x: start.x + x * cos - y * sin,
+                y: start.y + x * sin + y * cos,
+            }
+        },
+    );
+
+    common::check_against_reference("text_layout", &canvas);
+}
@@ -0,0 +1,251 @@
+// This tests text laid out along paths: the arc length of lines and curves, the placement of the
+// glyphs and the alignment around the start offset.
+
+use verg::{
+    color::{Color, FillStyle},
+    geometry::{PathMeasure, PathOps, Point},
+    text::{Baseline, Font, TextAlign, TextLayout, TextPath, TextRendering},
+};
+
+mod common;
+
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+const COLR: &str = "media/colr_1.ttf";
+
+// A circle of radius `r` around (cx, cy) made of 4 cubic curves, clockwise from its left.
+fn circle(cx: f64, cy: f64, r: f64) -> Vec<PathOps> {
+    let k = 0.5522847498 * r;
+
+    vec![
+        PathOps::MoveTo { x: cx - r, y: cy },
+        PathOps::CubicTo {
+            x1: cx - r,
+            y1: cy - k,
+            x2: cx - k,
+            y2: cy - r,
+            x3: cx,
+            y3: cy - r,
+        },
+        PathOps::CubicTo {
+            x1: cx + k,
+            y1: cy - r,
+            x2: cx + r,
+            y2: cy - k,
+            x3: cx + r,
+            y3: cy,
+        },
+        PathOps::CubicTo {
+            x1: cx + r,
+            y1: cy + k,
+            x2: cx + k,
+            y2: cy + r,
+            x3: cx,
+            y3: cy + r,
+        },
+        PathOps::CubicTo {
+            x1: cx - k,
+            y1: cy + r,
+            x2: cx - r,
+            y2: cy + k,
+            x3: cx - r,
+            y3: cy,
+        },
+        PathOps::Close,
+    ]
+}
+
+#[test]
+fn arc_length() {
+    let lines = [
+        PathOps::MoveTo { x: 0.0, y: 0.0 },
+        PathOps::LineTo { x: 30.0, y: 40.0 },
+        PathOps::LineToRel { x: 10.0, y: 0.0 },
+        // The gap between subpaths doesn't count.
+        PathOps::MoveTo { x: 100.0, y: 100.0 },
+        PathOps::LineTo { x: 100.0, y: 120.0 },
+    ];
+    let measure = PathMeasure::new(&lines, 0.1);
+
+    assert_eq!(measure.length(), 80.0);
+    let (point, tangent) = measure.point_at(25.0).unwrap();
+    assert!((point.x - 15.0).abs() < 1e-9 && (point.y - 20.0).abs() < 1e-9);
+    assert!((tangent.x - 0.6).abs() < 1e-9 && (tangent.y - 0.8).abs() < 1e-9);
+    let (point, tangent) = measure.point_at(70.0).unwrap();
+    assert!((point.x - 100.0).abs() < 1e-9 && (point.y - 110.0).abs() < 1e-9);
+    assert!(tangent.x.abs() < 1e-9 && (tangent.y - 1.0).abs() < 1e-9);
+    assert!(measure.point_at(-1.0).is_none());
+    assert!(measure.point_at(80.5).is_none());
+
+    // Flattened curves get close to the length of the circle, and closer with a lower tolerance.
+    let circle = circle(0.0, 0.0, 100.0);
+    let coarse = PathMeasure::new(&circle, 1.0).length();
+    let fine = PathMeasure::new(&circle, 0.01).length();
+    let perimeter = 2.0 * std::f64::consts::PI * 100.0;
+    assert!((coarse - perimeter).abs() < 1.0, "{}", coarse);
+    assert!(
+        (fine - perimeter).abs() < (coarse - perimeter).abs(),
+        "{}",
+        fine
+    );
+
+    // A quadratic curve with its control point in the middle is a line.
+    let quad = [
+        PathOps::MoveTo { x: 0.0, y: 0.0 },
+        PathOps::QuadTo {
+            x1: 5.0,
+            y1: 5.0,
+            x2: 10.0,
+            y2: 10.0,
+        },
+    ];
+    let length = PathMeasure::new(&quad, 0.1).length();
+    assert!((length - 200.0_f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn straight_paths_match_draw_text() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let black = FillStyle::Plain(Color::black());
+    let line = [
+        PathOps::MoveTo { x: 0.0, y: 30.0 },
+        PathOps::LineTo { x: 200.0, y: 30.0 },
+    ];
+
+    // The gamma applies to the coverage the same way.
+    for gamma in [1.0, 2.2] {
+        let rendering = TextRendering {
+            gamma,
+            ..Default::default()
+        };
+        let (mut expected, mut actual) = (common::new_canvas(200, 40), common::new_canvas(200, 40));
+
+        expected.draw_text(
+            "Straight",
+            &mut font,
+            24.0,
+            TextLayout {
+                rendering,
+                ..TextLayout::from(Point { x: 10.0, y: 30.0 })
+            },
+            black,
+            |p| *p,
+        );
+        actual.draw_text_on_path(
+            "Straight",
+            &mut font,
+            24.0,
+            TextPath {
+                start_offset: 10.0,
+                rendering,
+                ..TextPath::from(&line[..])
+            },
+            black,
+            |p| *p,
+        );
+
+        let difference = expected
+            .buffer
+            .iter()
+            .zip(&actual.buffer)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        assert!(difference < 1e-9, "{} {}", gamma, difference);
+    }
+}
+
+#[test]
+fn alignment_and_ends() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let black = FillStyle::Plain(Color::black());
+    let line = [
+        PathOps::MoveTo { x: 0.0, y: 30.0 },
+        PathOps::LineTo { x: 200.0, y: 30.0 },
+    ];
+    // The columns with ink.
+    let draw = |font: &mut Font, start_offset, align| {
+        let mut canvas = common::new_canvas(200, 40);
+        canvas.draw_text_on_path(
+            "Label",
+            font,
+            24.0,
+            TextPath {
+                start_offset,
+                align,
+                ..TextPath::from(&line[..])
+            },
+            black,
+            |p| *p,
+        );
+        (0..200)
+            .filter(|x| (0..40).any(|y| canvas.buffer[(y * 200 + x) * 4] < 0.5))
+            .collect::<Vec<usize>>()
+    };
+
+    let left = draw(&mut font, 100.0, TextAlign::Left);
+    let center = draw(&mut font, 100.0, TextAlign::Center);
+    let right = draw(&mut font, 100.0, TextAlign::Right);
+    assert!(*left.first().unwrap() >= 100);
+    assert!(*right.last().unwrap() <= 102);
+    let middle = (center.first().unwrap() + center.last().unwrap()) as f64 / 2.0;
+    assert!((middle - 100.0).abs() < 4.0, "{}", middle);
+
+    // Glyphs past the end of the path are left out.
+    let clipped = draw(&mut font, 180.0, TextAlign::Left);
+    assert!(!clipped.is_empty() && *clipped.last().unwrap() < 200);
+    assert!(draw(&mut font, 210.0, TextAlign::Left).is_empty());
+}
+
+#[test]
+fn text_on_path() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut colr = Font::from_file(COLR).unwrap();
+    let mut canvas = common::new_canvas(400, 300);
+    let wave = [
+        PathOps::MoveTo { x: 20.0, y: 240.0 },
+        PathOps::CubicTo {
+            x1: 120.0,
+            y1: 140.0,
+            x2: 260.0,
+            y2: 340.0,
+            x3: 380.0,
+            y3: 220.0,
+        },
+    ];
+    let circle = circle(200.0, 110.0, 70.0);
+
+    canvas.draw_text_on_path(
+        "Along a winding road, glyphs follow the curve",
+        &mut font,
+        18.0,
+        TextPath {
+            start_offset: 190.0,
+            align: TextAlign::Center,
+            baseline: Baseline::Middle,
+            ..TextPath::from(&wave[..])
+        },
+        FillStyle::Plain(Color::dark_slate_blue()),
+        |p| *p,
+    );
+    canvas.draw_text_on_path(
+        "Badges and labels around a circle",
+        &mut font,
+        16.0,
+        &circle[..],
+        FillStyle::Plain(Color::crimson()),
+        |p| *p,
+    );
+    canvas.draw_text_on_path(
+        "\u{F0E00}\u{F0100}\u{F0200}",
+        &mut colr,
+        28.0,
+        TextPath {
+            start_offset: 0.75 * PathMeasure::new(&circle, 0.1).length(),
+            align: TextAlign::Center,
+            ..TextPath::from(&circle[..])
+        },
+        FillStyle::Plain(Color::black()),
+        |p| *p,
+    );
+
+    common::check_against_reference("text_on_path", &canvas);
+}
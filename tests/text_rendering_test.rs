@@ -0,0 +1,219 @@
+// This tests the rendering options of small text: stem darkening, gamma and vertical snapping.
+
+use verg::{
+    color::{Color, FillStyle},
+    geometry::Point,
+    text::{stem_darkening_amount, Font, GlyphAtlas, TextLayout, TextRendering},
+};
+
+mod common;
+
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+
+fn layout(x: f64, y: f64, rendering: TextRendering) -> TextLayout {
+    TextLayout {
+        rendering,
+        ..TextLayout::from(Point { x, y })
+    }
+}
+
+// Black text on white, so the coverage is what's missing from the red channel.
+fn coverage(text: &str, size: f64, rendering: TextRendering) -> Vec<f64> {
+    let mut canvas = common::new_canvas(80, 24);
+    canvas.draw_text(
+        text,
+        &mut Font::from_file(ROBOTO).unwrap(),
+        size,
+        layout(2.0, 18.0, rendering),
+        FillStyle::Plain(Color::black()),
+        |p| *p,
+    );
+    canvas
+        .buffer
+        .chunks(4)
+        .map(|pixel| 1.0 - pixel[0])
+        .collect()
+}
+
+#[test]
+fn stem_darkening() {
+    let darkening = TextRendering {
+        stem_darkening: true,
+        ..Default::default()
+    };
+    let plain = coverage("nil", 12.0, TextRendering::default());
+    let darkened = coverage("nil", 12.0, darkening);
+    let total = |coverage: &[f64]| coverage.iter().sum::<f64>();
+
+    // Darkening grows with the size, up to a limit, and stops for large text.
+    let amount = stem_darkening_amount(12.0);
+    assert!(amount.x > 0.0 && amount.y > amount.x);
+    assert!(stem_darkening_amount(8.0).x < amount.x);
+    assert_eq!(stem_darkening_amount(60.0).y, 0.3);
+    assert_eq!(stem_darkening_amount(80.0).x, 0.0);
+
+    // About 2 * 0.145 pixels more for each of the 3 vertical stems on 9 rows, and a bit more for
+    // the round parts.
+    let added = total(&darkened) - total(&plain);
+    assert!(added > 3.0 * 9.0 * 2.0 * amount.x * 0.8, "{}", added);
+    assert!(added < 3.0 * 9.0 * 2.0 * amount.x * 2.0, "{}", added);
+    // Pixels only get darker.
+    assert!(plain.iter().zip(&darkened).all(|(p, d)| d >= &(p - 1e-6)));
+}
+
+#[test]
+fn gamma() {
+    let plain = coverage("Gamma", 12.0, TextRendering::default());
+    let heavier = coverage(
+        "Gamma",
+        12.0,
+        TextRendering {
+            gamma: 2.0,
+            ..Default::default()
+        },
+    );
+
+    for (p, h) in plain.iter().zip(&heavier) {
+        assert!((h - p.sqrt()).abs() < 1e-5, "{} {}", p, h);
+    }
+}
+
+#[test]
+fn horizontal_stems_are_snapped() {
+    let snapping = TextRendering {
+        snap_vertically: true,
+        ..Default::default()
+    };
+    // Rows crossing the middle of the horizontal bars of the E, away from its stem.
+    let rows = |coverage: &[f64]| {
+        (0..24)
+            .map(|y| coverage[y * 80 + 5])
+            .filter(|c| *c > 0.01)
+            .collect::<Vec<f64>>()
+    };
+
+    let plain = rows(&coverage("E", 11.3, TextRendering::default()));
+    let snapped = rows(&coverage("E", 11.3, snapping));
+
+    assert!(
+        plain.iter().any(|c| (0.05..0.95).contains(c)),
+        "{:?}",
+        plain
+    );
+    assert!(snapped.iter().all(|c| *c > 0.99), "{:?}", snapped);
+    // The baseline is rounded, so the text is sharp at fractional positions too.
+    let mut canvas = common::new_canvas(80, 24);
+    canvas.draw_text(
+        "E",
+        &mut Font::from_file(ROBOTO).unwrap(),
+        11.3,
+        layout(2.0, 18.4, snapping),
+        FillStyle::Plain(Color::black()),
+        |p| *p,
+    );
+    let shifted = canvas
+        .buffer
+        .chunks(4)
+        .map(|pixel| 1.0 - pixel[0])
+        .collect::<Vec<f64>>();
+    assert_eq!(rows(&shifted), snapped);
+}
+
+#[test]
+fn cached_glyphs_use_the_same_options() {
+    let rendering = TextRendering {
+        stem_darkening: true,
+        gamma: 1.4,
+        snap_vertically: true,
+        ..Default::default()
+    };
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let mut atlas = GlyphAtlas::new(128, 128);
+    atlas.subpixel_positions = 64;
+    // Glyphs apart from each other, darkened glyphs that touch are composited one over the other
+    // from the atlas but accumulated in a single path by `draw_text`.
+    let (mut expected, mut actual) = (common::new_canvas(120, 24), common::new_canvas(120, 24));
+    let black = FillStyle::Plain(Color::black());
+
+    expected.draw_text(
+        "H i n t e d",
+        &mut font,
+        13.0,
+        layout(2.0, 18.0, rendering),
+        black,
+        |p| *p,
+    );
+    actual.draw_text_cached(
+        "H i n t e d",
+        &mut font,
+        13.0,
+        layout(2.0, 18.0, rendering),
+        black,
+        &mut atlas,
+    );
+
+    let difference = expected
+        .buffer
+        .iter()
+        .zip(&actual.buffer)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    // Gamma makes the small differences of the subpixel positions a bit larger near 0.
+    assert!(difference < 0.02, "{}", difference);
+    // Glyphs rendered with other options are cached separately.
+    let count = atlas.len();
+    actual.draw_text_cached(
+        "H i n t e d",
+        &mut font,
+        13.0,
+        Point { x: 2.0, y: 18.0 },
+        black,
+        &mut atlas,
+    );
+    assert_eq!(atlas.len(), 2 * count);
+}
+
+#[test]
+fn text_rendering() {
+    const LINE_HEIGHT: f64 = 16.0;
+
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let options = [
+        TextRendering::default(),
+        TextRendering {
+            stem_darkening: true,
+            ..Default::default()
+        },
+        TextRendering {
+            gamma: 1.8,
+            ..Default::default()
+        },
+        TextRendering {
+            snap_vertically: true,
+            ..Default::default()
+        },
+        TextRendering {
+            stem_darkening: true,
+            gamma: 1.4,
+            snap_vertically: true,
+            ..Default::default()
+        },
+    ];
+    let mut canvas = common::new_canvas(300, options.len() * 2 * LINE_HEIGHT as usize + 4);
+
+    // Each option at two small sizes, one below the other.
+    for (i, rendering) in options.into_iter().enumerate() {
+        for (j, size) in [9.0, 11.0].into_iter().enumerate() {
+            canvas.draw_text(
+                "Hamburgefonstiv EFHT 0123",
+                &mut font,
+                size,
+                layout(4.0, LINE_HEIGHT * (2 * i + j + 1) as f64 - 1.6, rendering),
+                FillStyle::Plain(Color::black()),
+                |p| *p,
+            );
+        }
+    }
+
+    common::check_against_reference("text_rendering", &canvas);
+}
@@ -0,0 +1,87 @@
+// This tests loading glyph outlines and metrics from font files.
+
+use verg::{
+    geometry::PathOps,
+    text::{Font, FontError, GlyphId},
+};
+
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+
+#[test]
+fn outlines_keep_quadratic_curves() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let glyph = font.glyph_index('o').unwrap();
+    let outline = font.outline(glyph);
+
+    assert!(matches!(outline[0], PathOps::MoveTo { .. }));
+    assert!(matches!(outline.last(), Some(PathOps::Close)));
+    assert!(outline
+        .iter()
+        .any(|op| matches!(op, PathOps::QuadTo { .. })));
+    assert!(!outline
+        .iter()
+        .any(|op| matches!(op, PathOps::CubicTo { .. })));
+}
+
+#[test]
+fn outlines_point_down_from_the_baseline() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let glyph = font.glyph_index('T').unwrap();
+    let bounds = font.glyph_metrics(glyph).bounds.unwrap();
+    let cap_height = font.metrics().cap_height.unwrap();
+    let ys = font
+        .outline(glyph)
+        .iter()
+        .filter_map(|op| match op {
+            PathOps::MoveTo { y, .. } | PathOps::LineTo { y, .. } => Some(*y),
+            _ => None,
+        })
+        .collect::<Vec<f64>>();
+
+    // The bar of the T is above the baseline, so it has negative coordinates.
+    assert_eq!(ys.iter().cloned().fold(f64::MAX, f64::min), bounds.min_y);
+    assert_eq!(ys.iter().cloned().fold(f64::MIN, f64::max), bounds.max_y);
+    assert_eq!(bounds.min_y, cap_height);
+    assert_eq!(bounds.max_y, 0.0);
+}
+
+#[test]
+fn metrics() {
+    let font = Font::from_file(ROBOTO).unwrap();
+    let metrics = font.metrics();
+
+    assert_eq!(metrics.units_per_em, 2048.0);
+    assert_eq!(font.scale(16.0), 16.0 / 2048.0);
+    assert!(metrics.ascender < 0.0 && metrics.descender > 0.0);
+    assert_eq!(
+        metrics.line_height,
+        metrics.descender - metrics.ascender + metrics.line_gap
+    );
+
+    let space = font.glyph_metrics(font.glyph_index(' ').unwrap());
+    assert!(space.advance > 0.0);
+    assert_eq!(space.bounds, None);
+}
+
+#[test]
+fn outlines_are_cached() {
+    let mut font = Font::from_file(ROBOTO).unwrap();
+    let glyph = font.glyph_index('g').unwrap();
+    let first = font.outline(glyph).as_ptr();
+
+    assert_eq!(font.outline(glyph).as_ptr(), first);
+    assert!(font.outline(font.glyph_index(' ').unwrap()).is_empty());
+    assert!(font.outline(GlyphId(font.num_glyphs())).is_empty());
+}
+
+#[test]
+fn invalid_fonts() {
+    assert!(matches!(
+        Font::from_bytes(vec![0; 16], 0),
+        Err(FontError::Parse(_))
+    ));
+    assert!(matches!(
+        Font::from_file("media/missing.ttf"),
+        Err(FontError::Io(_))
+    ));
+}
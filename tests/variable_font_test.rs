@@ -0,0 +1,151 @@
+// This tests variable fonts: their axes and the outlines, metrics and color glyphs that follow the
+// axis coordinates.
+
+use verg::{
+    color::{Color, FillStyle},
+    geometry::{PathOps, Point},
+    text::{Font, GlyphAtlas},
+};
+
+mod common;
+
+// The COLR v1 test font with axes for the clip boxes, the gradients and the transforms of its
+// glyphs.
+const VARIABLE: &str = "media/colr_1_variable.ttf";
+const ROBOTO: &str = "media/Roboto-MediumItalic.ttf";
+// A square clip outline whose corners move with the `CLXI`, `CLYI`, `CLXA` and `CLYA` axes.
+const CLIP_SQUARE: char = '\u{F0C09}';
+
+fn xs(outline: &[PathOps]) -> Vec<f64> {
+    outline
+        .iter()
+        .filter_map(|op| match op {
+            PathOps::MoveTo { x, .. } | PathOps::LineTo { x, .. } => Some(*x),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn axes() {
+    let font = Font::from_file(VARIABLE).unwrap();
+    let axes = font.variation_axes();
+
+    assert_eq!(axes.len(), 44);
+    assert_eq!(&axes[0].tag, b"SWPS");
+    assert_eq!(
+        (axes[0].min, axes[0].default, axes[0].max),
+        (-90.0, 0.0, 90.0)
+    );
+    assert!(Font::from_file(ROBOTO).unwrap().variation_axes().is_empty());
+}
+
+#[test]
+fn coordinates_are_clamped() {
+    let mut font = Font::from_file(VARIABLE).unwrap();
+
+    assert_eq!(font.variation(b"CLXA"), Some(0.0));
+    assert!(font.set_variation(b"CLXA", 120.0));
+    assert_eq!(font.variation(b"CLXA"), Some(120.0));
+    assert!(font.set_variation(b"CLXA", 900.0));
+    assert_eq!(font.variation(b"CLXA"), Some(500.0));
+
+    assert!(!font.set_variation(b"wght", 700.0));
+    assert_eq!(font.variation(b"wght"), None);
+    let mut roboto = Font::from_file(ROBOTO).unwrap();
+    assert!(!roboto.set_variation(b"wght", 700.0));
+}
+
+#[test]
+fn outlines_and_metrics_are_interpolated() {
+    let mut font = Font::from_file(VARIABLE).unwrap();
+    let glyph = font.glyph_index(CLIP_SQUARE).unwrap();
+    let max = |xs: Vec<f64>| xs.into_iter().fold(f64::MIN, f64::max);
+
+    assert_eq!(max(xs(font.outline(glyph))), 750.0);
+    assert_eq!(font.glyph_metrics(glyph).bounds.unwrap().max_x, 750.0);
+
+    // The cached outline is replaced.
+    font.set_variation(b"CLXA", 250.0);
+    assert_eq!(max(xs(font.outline(glyph))), 1000.0);
+    assert_eq!(font.glyph_metrics(glyph).bounds.unwrap().max_x, 1000.0);
+
+    // Half way between the default and the maximum.
+    font.set_variation(b"CLXA", 0.0);
+    font.set_variation(b"CLXI", 250.0);
+    let bounds = font.glyph_metrics(glyph).bounds.unwrap();
+    assert_eq!((bounds.min_x, bounds.max_x), (500.0, 750.0));
+}
+
+#[test]
+fn cached_glyphs_follow_the_coordinates() {
+    let mut font = Font::from_file(VARIABLE).unwrap();
+    let mut atlas = GlyphAtlas::new(128, 128);
+    let glyph = font.glyph_index(CLIP_SQUARE).unwrap();
+    let black = FillStyle::Plain(Color::black());
+    let draw = |font: &mut Font, atlas: &mut GlyphAtlas| {
+        let mut canvas = common::new_canvas(64, 64);
+        canvas.draw_text_cached(
+            &CLIP_SQUARE.to_string(),
+            font,
+            48.0,
+            Point { x: 4.0, y: 52.0 },
+            black,
+            atlas,
+        );
+        canvas
+    };
+
+    assert!(!font.is_color_glyph(glyph));
+    let default = draw(&mut font, &mut atlas);
+    assert_eq!(atlas.len(), 1);
+
+    let id = font.id();
+    font.set_variation(b"CLXA", 250.0);
+    assert_ne!(font.id(), id);
+    let wider = draw(&mut font, &mut atlas);
+    assert_eq!(atlas.len(), 2);
+    assert_ne!(default.buffer, wider.buffer);
+
+    // Setting the same value again keeps the cached glyphs.
+    let id = font.id();
+    font.set_variation(b"CLXA", 250.0);
+    assert_eq!(font.id(), id);
+}
+
+#[test]
+fn variable_font() {
+    const SIZE: usize = 64;
+
+    let mut font = Font::from_file(VARIABLE).unwrap();
+    // A row for each axis: the clip box of a color glyph, the start of a linear gradient, the
+    // radius of a radial gradient, the end of a sweep and the color of a gradient stop.
+    let rows: [(char, [u8; 4], [f64; 5]); 5] = [
+        ('\u{F0C01}', *b"CLXA", [-400.0, -200.0, 0.0, 200.0, 400.0]),
+        ('\u{F0500}', *b"GRX0", [-800.0, -400.0, 0.0, 400.0, 800.0]),
+        ('\u{F0506}', *b"GRR0", [-400.0, -200.0, 0.0, 200.0, 400.0]),
+        ('\u{F0205}', *b"SWPE", [-90.0, -45.0, 0.0, 45.0, 90.0]),
+        ('\u{F0506}', *b"COL1", [-2.0, -1.0, 0.0, 1.0, 2.0]),
+    ];
+    let mut canvas = common::new_canvas(SIZE * 5, SIZE * rows.len());
+
+    for (i, (glyph, tag, values)) in rows.iter().enumerate() {
+        for (j, value) in values.iter().enumerate() {
+            font.set_variation(tag, *value);
+            canvas.draw_text(
+                &glyph.to_string(),
+                &mut font,
+                SIZE as f64 * 0.8,
+                Point {
+                    x: (j * SIZE) as f64 + 0.1 * SIZE as f64,
+                    y: ((i + 1) * SIZE) as f64 - 0.15 * SIZE as f64,
+                },
+                FillStyle::Plain(Color::dark_slate_blue()),
+                |p| *p,
+            );
+        }
+        font.set_variation(tag, 0.0);
+    }
+
+    common::check_against_reference("variable_font", &canvas);
+}
@@ -0,0 +1,314 @@
+// These tests compare the coverage computed for random, possibly self-intersecting, polygons
+// against the supersampled rasterizer from `verg::reference` for both fill rules.
+
+use proptest::prelude::*;
+use verg::{
+    canvas::{Canvas, CanvasDescription, ViewBox},
+    color::{Color, FillRule, FillStyle},
+    geometry::{PathOps, Point},
+    reference::{self, SAMPLES_PER_AXIS},
+};
+
+const SIZE: usize = 24;
+
+fn canvas_description() -> CanvasDescription {
+    CanvasDescription {
+        width: SIZE,
+        height: SIZE,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: SIZE as f64,
+            height: SIZE as f64,
+        },
+        background_color: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 0.0,
+        },
+        ..Default::default()
+    }
+}
+
+fn to_path(polygons: &[Vec<(f64, f64)>]) -> Vec<PathOps> {
+    let mut path = Vec::new();
+
+    for polygon in polygons {
+        path.push(PathOps::MoveTo {
+            x: polygon[0].0,
+            y: polygon[0].1,
+        });
+        for &(x, y) in &polygon[1..] {
+            path.push(PathOps::LineTo { x, y });
+        }
+        path.push(PathOps::Close);
+    }
+
+    path
+}
+
+fn edges(polygons: &[Vec<(f64, f64)>]) -> Vec<(Point, Point)> {
+    polygons
+        .iter()
+        .flat_map(|polygon| {
+            (0..polygon.len()).map(move |i| {
+                let (x0, y0) = polygon[i];
+                let (x1, y1) = polygon[(i + 1) % polygon.len()];
+
+                (Point { x: x0, y: y0 }, Point { x: x1, y: y1 })
+            })
+        })
+        .collect()
+}
+
+fn coverage(polygons: &[Vec<(f64, f64)>], fill_rule: FillRule) -> Vec<f64> {
+    let mut canvas = Canvas::new(canvas_description());
+
+    canvas.draw_shape(
+        &to_path(polygons),
+        FillStyle::Plain(Color::white()),
+        fill_rule,
+        |p| *p,
+    );
+
+    canvas.buffer.chunks(4).map(|pixel| pixel[3]).collect()
+}
+
+fn reference_coverage(polygons: &[Vec<(f64, f64)>], fill_rule: FillRule) -> Vec<f64> {
+    let mut canvas = Canvas::new(canvas_description());
+
+    reference::draw_shape(
+        &mut canvas,
+        &to_path(polygons),
+        FillStyle::Plain(Color::white()),
+        fill_rule,
+        |p| *p,
+    );
+
+    canvas.buffer.chunks(4).map(|pixel| pixel[3]).collect()
+}
+
+///
+/// Liang-Barsky clipping of the edge against the pixel at (`x`, `y`).
+///
+fn edge_crosses_pixel(edge: &(Point, Point), x: f64, y: f64) -> bool {
+    let (p0, p1) = edge;
+    let (dx, dy) = (p1.x - p0.x, p1.y - p0.y);
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    let eps = 1e-9;
+
+    for (p, q) in [
+        (-dx, p0.x - x + eps),
+        (dx, x + 1.0 - p0.x + eps),
+        (-dy, p0.y - y + eps),
+        (dy, y + 1.0 - p0.y + eps),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+
+    t0 <= t1
+}
+
+///
+/// The average winding number of every pixel, sampled by the reference rasterizer. Winding
+/// numbers add up edge by edge, an edge winds every point right of it between its ends, which is
+/// the quadrilateral between the edge and the right side of the canvas.
+///
+fn reference_winding(edges: &[(Point, Point)]) -> Vec<f64> {
+    let mut result = vec![0.0; SIZE * SIZE];
+    let right = SIZE as f64 + 1.0;
+
+    for (p0, p1) in edges {
+        let mut canvas = Canvas::new(canvas_description());
+        let direction = if p0.y < p1.y { 1.0 } else { -1.0 };
+
+        reference::draw_shape(
+            &mut canvas,
+            &[
+                PathOps::MoveTo { x: p0.x, y: p0.y },
+                PathOps::LineTo { x: p1.x, y: p1.y },
+                PathOps::LineTo { x: right, y: p1.y },
+                PathOps::LineTo { x: right, y: p0.y },
+                PathOps::Close,
+            ],
+            FillStyle::Plain(Color::white()),
+            FillRule::NonZero,
+            |p| *p,
+        );
+
+        for (winding, pixel) in result.iter_mut().zip(canvas.buffer.chunks(4)) {
+            *winding += direction * pixel[3];
+        }
+    }
+
+    result
+}
+
+fn check_against_reference(polygons: &[Vec<(f64, f64)>], fill_rule: FillRule) {
+    let actual = coverage(polygons, fill_rule);
+    let expected = reference_coverage(polygons, fill_rule);
+    let edges = edges(polygons);
+    let winding = reference_winding(&edges);
+    let mut total_error = 0.0;
+
+    for i in 0..SIZE * SIZE {
+        let (x, y) = ((i % SIZE) as f64, (i / SIZE) as f64);
+        let crossing = edges
+            .iter()
+            .filter(|edge| edge_crosses_pixel(edge, x, y))
+            .count();
+
+        // The area accumulated in a pixel is its average winding number. Pixels crossed by
+        // several edges only get the fill rule of that average instead of their coverage, every
+        // edge crossing a pixel adds up to one sampling error.
+        let expected = if crossing < 2 {
+            expected[i]
+        } else {
+            let winding = winding[i].abs();
+            match fill_rule {
+                FillRule::NonZero => winding.min(1.0),
+                FillRule::EvenOdd if winding % 2.0 > 1.0 => 2.0 - winding % 2.0,
+                FillRule::EvenOdd => winding % 2.0,
+            }
+        };
+        let max_error = crossing as f64 / SAMPLES_PER_AXIS as f64 + 1e-3;
+        let error = (actual[i] - expected).abs();
+
+        total_error += error;
+
+        assert!(
+            error <= max_error,
+            "{:?} pixel ({}, {}) crossed by {} edge(s): expected {}, got {}",
+            fill_rule,
+            x,
+            y,
+            crossing,
+            expected,
+            actual[i],
+        );
+    }
+
+    // The sampling error shouldn't add up to a bias in one direction.
+    let mean_error = total_error / (SIZE * SIZE) as f64;
+    assert!(
+        mean_error < 0.005,
+        "{:?} mean error {}",
+        fill_rule,
+        mean_error
+    );
+}
+
+fn polygons() -> impl Strategy<Value = Vec<Vec<(f64, f64)>>> {
+    let coordinate = -6.0..(SIZE as f64 + 6.0);
+    let polygon = prop::collection::vec((coordinate.clone(), coordinate), 3..7);
+
+    prop::collection::vec(polygon, 1..4)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn non_zero_matches_reference(polygons in polygons()) {
+        check_against_reference(&polygons, FillRule::NonZero);
+    }
+
+    #[test]
+    fn even_odd_matches_reference(polygons in polygons()) {
+        check_against_reference(&polygons, FillRule::EvenOdd);
+    }
+}
+
+const PENTAGRAM: [(f64, f64); 5] = [
+    (12.0, 1.0),
+    (18.5, 21.0),
+    (1.5, 8.5),
+    (22.5, 8.5),
+    (5.5, 21.0),
+];
+
+#[test]
+fn self_intersecting_path() {
+    let polygons = vec![PENTAGRAM.to_vec()];
+    let center = 12 * SIZE + 12;
+
+    assert_eq!(coverage(&polygons, FillRule::NonZero)[center], 1.0);
+    assert_eq!(coverage(&polygons, FillRule::EvenOdd)[center], 0.0);
+    check_against_reference(&polygons, FillRule::NonZero);
+    check_against_reference(&polygons, FillRule::EvenOdd);
+}
+
+#[test]
+fn overlapping_subpaths() {
+    let square = |x: f64, y: f64| vec![(x, y), (x + 12.0, y), (x + 12.0, y + 12.0), (x, y + 12.0)];
+    let polygons = vec![square(2.0, 2.0), square(8.0, 8.0), square(5.0, 5.0)];
+    let non_zero = coverage(&polygons, FillRule::NonZero);
+    let even_odd = coverage(&polygons, FillRule::EvenOdd);
+
+    // Covered by all three squares
+    assert_eq!(non_zero[10 * SIZE + 10], 1.0);
+    assert_eq!(even_odd[10 * SIZE + 10], 1.0);
+    // Covered by two squares
+    assert_eq!(non_zero[6 * SIZE + 6], 1.0);
+    assert_eq!(even_odd[6 * SIZE + 6], 0.0);
+    check_against_reference(&polygons, FillRule::NonZero);
+    check_against_reference(&polygons, FillRule::EvenOdd);
+}
+
+#[test]
+fn even_odd_ignores_previous_shapes() {
+    let pentagram = to_path(&[PENTAGRAM.iter().map(|(x, y)| (x + 24.0, *y)).collect()]);
+    let draw_pentagram = |canvas: &mut Canvas| {
+        canvas.draw_shape(
+            &pentagram,
+            FillStyle::Plain(Color::white()),
+            FillRule::EvenOdd,
+            |p| *p,
+        );
+    };
+    let description = CanvasDescription {
+        width: 2 * SIZE,
+        viewbox: ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: 2.0 * SIZE as f64,
+            height: SIZE as f64,
+        },
+        ..canvas_description()
+    };
+
+    let mut fresh = Canvas::new(description);
+    draw_pentagram(&mut fresh);
+
+    // Shapes on the same rows, left of the pentagram
+    let mut canvas = Canvas::new(description);
+    for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+        canvas.draw_shape(
+            &to_path(&[PENTAGRAM.to_vec()]),
+            FillStyle::Plain(Color::white()),
+            fill_rule,
+            |p| *p,
+        );
+    }
+    draw_pentagram(&mut canvas);
+
+    for (i, (actual, expected)) in canvas
+        .buffer
+        .chunks(4)
+        .zip(fresh.buffer.chunks(4))
+        .enumerate()
+    {
+        if i % (2 * SIZE) >= SIZE {
+            assert_eq!(actual, expected);
+        }
+    }
+}